@@ -0,0 +1,139 @@
+//! The `rename_all` policies of `json_parser_derive`, shared with
+//! `json_parser::codegen`, which picks the policy that renames generated
+//! fields to their keys.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Case {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl Case {
+    /// The policy named `policy`, as written in `rename_all = "..."`.
+    pub fn parse(policy: &str) -> Option<Case> {
+        let case = match policy {
+            "lowercase" => Case::Lower,
+            "UPPERCASE" => Case::Upper,
+            "PascalCase" => Case::Pascal,
+            "camelCase" => Case::Camel,
+            "snake_case" => Case::Snake,
+            "SCREAMING_SNAKE_CASE" => Case::ScreamingSnake,
+            "kebab-case" => Case::Kebab,
+            "SCREAMING-KEBAB-CASE" => Case::ScreamingKebab,
+            _ => return None,
+        };
+        Some(case)
+    }
+
+    /// Renames a field (`snake_case`) or variant (`PascalCase`) identifier.
+    pub fn apply(self, name: &str) -> String {
+        let words = words(name);
+        match self {
+            Case::Lower => name.to_lowercase(),
+            Case::Upper => name.to_uppercase(),
+            Case::Pascal => words.iter().map(|w| capitalize(w)).collect(),
+            Case::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(i, w)| {
+                    if i == 0 {
+                        w.to_lowercase()
+                    } else {
+                        capitalize(w)
+                    }
+                })
+                .collect(),
+            Case::Snake => join(&words, "_", str::to_lowercase),
+            Case::ScreamingSnake => join(&words, "_", str::to_uppercase),
+            Case::Kebab => join(&words, "-", str::to_lowercase),
+            Case::ScreamingKebab => join(&words, "-", str::to_uppercase),
+        }
+    }
+}
+
+/// Splits `foo_bar`, `FooBar` and `HTTPServer` into their words, at
+/// non-alphanumerics and case changes, keeping plurals such as `IDs` whole.
+pub fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for part in name.split(|ch: char| !ch.is_alphanumeric()) {
+        let chars: Vec<char> = part.chars().collect();
+        let mut word = String::new();
+        for (i, &ch) in chars.iter().enumerate() {
+            let boundary = i > 0
+                && ch.is_uppercase()
+                && (!chars[i - 1].is_uppercase()
+                    // `HTTPServer`, but not the plural of `IDs`
+                    || chars.get(i + 1).is_some_and(|next| {
+                        next.is_lowercase() && !(*next == 's' && chars.get(i + 2).is_none())
+                    }));
+            if boundary && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            word.push(ch);
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+    }
+    words
+}
+
+/// `word` with its first letter upper case and the rest lower case.
+pub fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+fn join(words: &[String], separator: &str, convert: fn(&str) -> String) -> String {
+    words
+        .iter()
+        .map(|w| convert(w))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_split_at_separators_and_case_changes() {
+        assert_eq!(words("foo_bar"), ["foo", "bar"]);
+        assert_eq!(words("FooBar"), ["Foo", "Bar"]);
+        assert_eq!(words("HTTPServer"), ["HTTP", "Server"]);
+        assert_eq!(words("user IDs"), ["user", "IDs"]);
+        assert_eq!(words("-a--b2-"), ["a", "b2"]);
+        assert!(words("").is_empty());
+    }
+
+    #[test]
+    fn every_policy_renames_fields_and_variants() {
+        let renamed = |policy, name| Case::parse(policy).unwrap().apply(name);
+        for (policy, field, variant) in [
+            ("lowercase", "user_id", "userid"),
+            ("UPPERCASE", "USER_ID", "USERID"),
+            ("PascalCase", "UserId", "UserId"),
+            ("camelCase", "userId", "userId"),
+            ("snake_case", "user_id", "user_id"),
+            ("SCREAMING_SNAKE_CASE", "USER_ID", "USER_ID"),
+            ("kebab-case", "user-id", "user-id"),
+            ("SCREAMING-KEBAB-CASE", "USER-ID", "USER-ID"),
+        ] {
+            assert_eq!(renamed(policy, "user_id"), field, "{}", policy);
+            assert_eq!(renamed(policy, "UserId"), variant, "{}", policy);
+        }
+        assert_eq!(Case::parse("Title Case"), None);
+    }
+}
@@ -0,0 +1,165 @@
+//! Parsing of `#[json(...)]` attributes.
+
+use syn::{Attribute, Error, ExprPath, LitStr, Result};
+
+use json_parser_case::Case;
+
+/// How an enum is written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tagging {
+    /// `{"Variant": content}`, or `"Variant"` for unit variants.
+    External,
+    /// `{"<tag>": "Variant", ...fields}`.
+    Internal { tag: String },
+    /// `{"<tag>": "Variant", "<content>": content}`.
+    Adjacent { tag: String, content: String },
+    /// Just the content; reading tries each variant in turn.
+    Untagged,
+}
+
+pub struct Container {
+    pub rename_all: Option<Case>,
+    pub tagging: Tagging,
+    pub default: bool,
+}
+
+pub enum FieldDefault {
+    /// `Default::default()`
+    Trait,
+    /// A function returning the value.
+    Path(ExprPath),
+}
+
+pub struct Field {
+    pub rename: Option<String>,
+    pub default: Option<FieldDefault>,
+    pub skip: bool,
+    pub flatten: bool,
+}
+
+pub struct Variant {
+    pub rename: Option<String>,
+    pub rename_all: Option<Case>,
+}
+
+fn parse_case(lit: &LitStr) -> Result<Case> {
+    Case::parse(&lit.value()).ok_or_else(|| {
+        Error::new_spanned(
+            lit,
+            "expected one of lowercase, UPPERCASE, PascalCase, camelCase, snake_case, \
+             SCREAMING_SNAKE_CASE, kebab-case, SCREAMING-KEBAB-CASE",
+        )
+    })
+}
+
+fn json_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("json"))
+}
+
+impl Container {
+    pub fn from_attrs(attrs: &[Attribute]) -> Result<Container> {
+        let mut rename_all = None;
+        let mut tag: Option<String> = None;
+        let mut content: Option<String> = None;
+        let mut untagged = false;
+        let mut default = false;
+
+        for attr in json_attrs(attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    rename_all = Some(parse_case(&meta.value()?.parse()?)?);
+                } else if meta.path.is_ident("tag") {
+                    tag = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("content") {
+                    content = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("untagged") {
+                    untagged = true;
+                } else if meta.path.is_ident("default") {
+                    default = true;
+                } else {
+                    return Err(meta.error("unknown container attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        let tagging = match (tag, content, untagged) {
+            (None, None, false) => Tagging::External,
+            (Some(tag), None, false) => Tagging::Internal { tag },
+            (Some(tag), Some(content), false) => Tagging::Adjacent { tag, content },
+            (None, None, true) => Tagging::Untagged,
+            (None, Some(_), _) => {
+                return Err(Error::new(
+                    proc_macro2::Span::call_site(),
+                    "`content` requires `tag`",
+                ));
+            }
+            (_, _, true) => {
+                return Err(Error::new(
+                    proc_macro2::Span::call_site(),
+                    "`untagged` cannot be combined with `tag` or `content`",
+                ));
+            }
+        };
+
+        Ok(Container {
+            rename_all,
+            tagging,
+            default,
+        })
+    }
+}
+
+impl Field {
+    pub fn from_attrs(attrs: &[Attribute]) -> Result<Field> {
+        let mut field = Field {
+            rename: None,
+            default: None,
+            skip: false,
+            flatten: false,
+        };
+        for attr in json_attrs(attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    field.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("default") {
+                    field.default = Some(if meta.input.peek(syn::Token![=]) {
+                        FieldDefault::Path(meta.value()?.parse::<LitStr>()?.parse()?)
+                    } else {
+                        FieldDefault::Trait
+                    });
+                } else if meta.path.is_ident("skip") {
+                    field.skip = true;
+                } else if meta.path.is_ident("flatten") {
+                    field.flatten = true;
+                } else {
+                    return Err(meta.error("unknown field attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(field)
+    }
+}
+
+impl Variant {
+    pub fn from_attrs(attrs: &[Attribute]) -> Result<Variant> {
+        let mut variant = Variant {
+            rename: None,
+            rename_all: None,
+        };
+        for attr in json_attrs(attrs) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    variant.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("rename_all") {
+                    variant.rename_all = Some(parse_case(&meta.value()?.parse()?)?);
+                } else {
+                    return Err(meta.error("unknown variant attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(variant)
+    }
+}
@@ -0,0 +1,358 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DataEnum, DeriveInput, Error, Fields, Ident, Result, parse_quote};
+
+use crate::attr::{Container, FieldDefault, Tagging};
+use crate::{add_bounds, named_fields, variant_name};
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let container = Container::from_attrs(&input.attrs)?;
+    let json = Ident::new("__json", proc_macro2::Span::call_site());
+    let body = match &input.data {
+        Data::Struct(data) => {
+            let construct = construct(
+                quote!(Self),
+                &data.fields,
+                container.rename_all,
+                container.default,
+                &json,
+            )?;
+            quote!(#construct)
+        }
+        Data::Enum(data) => enum_body(&input.ident, &container, data, &json)?,
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                input,
+                "FromJson cannot be derived for unions",
+            ));
+        }
+    };
+
+    let name = &input.ident;
+    let generics = add_bounds(
+        &input.generics,
+        parse_quote!(::json_parser::convert::FromJson),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::json_parser::convert::FromJson for #name #ty_generics #where_clause {
+            fn from_json(
+                #json: &::json_parser::parsen::Json,
+            ) -> ::std::result::Result<Self, ::json_parser::convert::FromJsonError> {
+                #body
+            }
+        }
+    })
+}
+
+/// An expression reading `path` (`Self` or `Self::Variant`) from the `&Json`
+/// named `json`, evaluating to a `Result<Self, FromJsonError>`.
+fn construct(
+    path: TokenStream,
+    fields: &Fields,
+    rename_all: Option<json_parser_case::Case>,
+    container_default: bool,
+    json: &Ident,
+) -> Result<TokenStream> {
+    let expr = match fields {
+        Fields::Named(named) => {
+            let fields = named_fields(named, rename_all)?;
+            let default = container_default
+                .then(|| quote!(let __default: Self = ::std::default::Default::default();));
+            let inits = fields.iter().map(|field| {
+                let ident = field.ident;
+                let key = &field.key;
+                let field_default = match &field.attrs.default {
+                    Some(FieldDefault::Trait) => Some(quote!(::std::default::Default::default())),
+                    Some(FieldDefault::Path(path)) => Some(quote!(#path())),
+                    None => None,
+                };
+                if field.attrs.skip {
+                    let value = match field_default {
+                        Some(value) => value,
+                        None if container_default => quote!(__default.#ident),
+                        None => quote!(::std::default::Default::default()),
+                    };
+                    return quote!(#ident: #value);
+                }
+                if field.attrs.flatten {
+                    return quote!(#ident: ::json_parser::convert::FromJson::from_json(#json)?);
+                }
+                let missing = match field_default {
+                    Some(value) => value,
+                    None if container_default => quote!(__default.#ident),
+                    None => quote! {
+                        ::json_parser::convert::FromJson::from_json(&::json_parser::parsen::Json::Null)
+                            .map_err(|_| ::json_parser::convert::FromJsonError::new(
+                                ::json_parser::convert::ErrorKind::MissingField(
+                                    ::std::string::String::from(#key),
+                                ),
+                            ))?
+                    },
+                };
+                quote! {
+                    #ident: match __map.get(#key) {
+                        ::std::option::Option::Some(__value) => {
+                            ::json_parser::convert::FromJson::from_json(__value)
+                                .map_err(|__err| __err.at_key(#key))?
+                        }
+                        ::std::option::Option::None => #missing,
+                    }
+                }
+            });
+            quote! {
+                (|| -> ::std::result::Result<Self, ::json_parser::convert::FromJsonError> {
+                    let __map = match #json {
+                        ::json_parser::parsen::Json::Object(__map) => __map,
+                        _ => {
+                            return ::std::result::Result::Err(
+                                ::json_parser::convert::FromJsonError::expected("object", #json),
+                            );
+                        }
+                    };
+                    #default
+                    ::std::result::Result::Ok(#path { #(#inits,)* })
+                })()
+            }
+        }
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => quote! {
+            ::json_parser::convert::FromJson::from_json(#json).map(|__value| #path(__value))
+        },
+        Fields::Unnamed(unnamed) => {
+            let len = unnamed.unnamed.len();
+            let items = (0..len).map(|i| {
+                quote! {
+                    ::json_parser::convert::FromJson::from_json(&__vec[#i])
+                        .map_err(|__err| __err.at_index(#i))?
+                }
+            });
+            quote! {
+                (|| -> ::std::result::Result<Self, ::json_parser::convert::FromJsonError> {
+                    let __vec = match #json {
+                        ::json_parser::parsen::Json::Array(__vec) => __vec,
+                        _ => {
+                            return ::std::result::Result::Err(
+                                ::json_parser::convert::FromJsonError::expected("array", #json),
+                            );
+                        }
+                    };
+                    if __vec.len() != #len {
+                        return ::std::result::Result::Err(
+                            ::json_parser::convert::FromJsonError::new(
+                                ::json_parser::convert::ErrorKind::Length {
+                                    expected: #len,
+                                    found: __vec.len(),
+                                },
+                            ),
+                        );
+                    }
+                    ::std::result::Result::Ok(#path(#(#items),*))
+                })()
+            }
+        }
+        Fields::Unit => quote! {
+            match #json {
+                ::json_parser::parsen::Json::Null => ::std::result::Result::Ok(#path),
+                _ => ::std::result::Result::Err(
+                    ::json_parser::convert::FromJsonError::expected("null", #json),
+                ),
+            }
+        },
+    };
+    Ok(expr)
+}
+
+fn unknown_variant(name: TokenStream) -> TokenStream {
+    quote! {
+        ::std::result::Result::Err(::json_parser::convert::FromJsonError::new(
+            ::json_parser::convert::ErrorKind::UnknownVariant(::std::string::String::from(#name)),
+        ))
+    }
+}
+
+/// Reads the tag of an internally or adjacently tagged enum into `__name`.
+fn read_tag(tag: &str, json: &Ident) -> TokenStream {
+    quote! {
+        let __map = match #json {
+            ::json_parser::parsen::Json::Object(__map) => __map,
+            _ => {
+                return ::std::result::Result::Err(
+                    ::json_parser::convert::FromJsonError::expected("object", #json),
+                );
+            }
+        };
+        let __name = match __map.get(#tag) {
+            ::std::option::Option::Some(::json_parser::parsen::Json::Strings(__name)) => {
+                __name.as_str()
+            }
+            ::std::option::Option::Some(__other) => {
+                return ::std::result::Result::Err(
+                    ::json_parser::convert::FromJsonError::expected("string", __other)
+                        .at_key(#tag),
+                );
+            }
+            ::std::option::Option::None => {
+                return ::std::result::Result::Err(::json_parser::convert::FromJsonError::new(
+                    ::json_parser::convert::ErrorKind::MissingField(
+                        ::std::string::String::from(#tag),
+                    ),
+                ));
+            }
+        };
+    }
+}
+
+fn enum_body(
+    enum_name: &Ident,
+    container: &Container,
+    data: &DataEnum,
+    json: &Ident,
+) -> Result<TokenStream> {
+    let content = Ident::new("__content", proc_macro2::Span::call_site());
+    let unknown = unknown_variant(quote!(__other));
+
+    let body = match &container.tagging {
+        Tagging::External => {
+            let mut unit_arms = Vec::new();
+            let mut arms = Vec::new();
+            for variant in &data.variants {
+                let (name, attrs) = variant_name(variant, container)?;
+                let ident = &variant.ident;
+                if let Fields::Unit = variant.fields {
+                    unit_arms.push(quote!(#name => ::std::result::Result::Ok(Self::#ident),));
+                    arms.push(quote!(#name => ::std::result::Result::Ok(Self::#ident),));
+                } else {
+                    let construct = construct(
+                        quote!(Self::#ident),
+                        &variant.fields,
+                        attrs.rename_all,
+                        false,
+                        &content,
+                    )?;
+                    arms.push(quote!(#name => #construct.map_err(|__err| __err.at_key(#name)),));
+                }
+            }
+            quote! {
+                match #json {
+                    ::json_parser::parsen::Json::Strings(__name) => match __name.as_str() {
+                        #(#unit_arms)*
+                        __other => #unknown,
+                    },
+                    ::json_parser::parsen::Json::Object(__map) if __map.len() == 1 => {
+                        let (__name, #content) = __map.iter().next().expect("one member");
+                        match __name.as_str() {
+                            #(#arms)*
+                            __other => #unknown,
+                        }
+                    }
+                    _ => ::std::result::Result::Err(
+                        ::json_parser::convert::FromJsonError::expected(
+                            "string or single-member object",
+                            #json,
+                        ),
+                    ),
+                }
+            }
+        }
+        Tagging::Internal { tag } => {
+            let read_tag = read_tag(tag, json);
+            let mut arms = Vec::new();
+            for variant in &data.variants {
+                let (name, attrs) = variant_name(variant, container)?;
+                let ident = &variant.ident;
+                let value = match &variant.fields {
+                    Fields::Unit => quote!(::std::result::Result::Ok(Self::#ident)),
+                    Fields::Unnamed(unnamed) if unnamed.unnamed.len() > 1 => {
+                        return Err(Error::new_spanned(
+                            variant,
+                            "internally tagged enums cannot contain tuple variants",
+                        ));
+                    }
+                    fields => {
+                        construct(quote!(Self::#ident), fields, attrs.rename_all, false, json)?
+                    }
+                };
+                arms.push(quote!(#name => #value,));
+            }
+            quote! {
+                #read_tag
+                match __name {
+                    #(#arms)*
+                    __other => #unknown,
+                }
+            }
+        }
+        Tagging::Adjacent {
+            tag,
+            content: content_key,
+        } => {
+            let read_tag = read_tag(tag, json);
+            let mut arms = Vec::new();
+            for variant in &data.variants {
+                let (name, attrs) = variant_name(variant, container)?;
+                let ident = &variant.ident;
+                let value = if let Fields::Unit = variant.fields {
+                    quote!(::std::result::Result::Ok(Self::#ident))
+                } else {
+                    let construct = construct(
+                        quote!(Self::#ident),
+                        &variant.fields,
+                        attrs.rename_all,
+                        false,
+                        &content,
+                    )?;
+                    quote! {
+                        match __map.get(#content_key) {
+                            ::std::option::Option::Some(#content) => {
+                                #construct.map_err(|__err| __err.at_key(#content_key))
+                            }
+                            ::std::option::Option::None => ::std::result::Result::Err(
+                                ::json_parser::convert::FromJsonError::new(
+                                    ::json_parser::convert::ErrorKind::MissingField(
+                                        ::std::string::String::from(#content_key),
+                                    ),
+                                ),
+                            ),
+                        }
+                    }
+                };
+                arms.push(quote!(#name => #value,));
+            }
+            quote! {
+                #read_tag
+                match __name {
+                    #(#arms)*
+                    __other => #unknown,
+                }
+            }
+        }
+        Tagging::Untagged => {
+            let mut attempts = Vec::new();
+            for variant in &data.variants {
+                let (_, attrs) = variant_name(variant, container)?;
+                let ident = &variant.ident;
+                let construct = construct(
+                    quote!(Self::#ident),
+                    &variant.fields,
+                    attrs.rename_all,
+                    false,
+                    json,
+                )?;
+                attempts.push(quote! {
+                    if let ::std::result::Result::Ok(__value) = #construct {
+                        return ::std::result::Result::Ok(__value);
+                    }
+                });
+            }
+            let message = format!(
+                "data did not match any variant of untagged enum {}",
+                enum_name
+            );
+            quote! {
+                #(#attempts)*
+                ::std::result::Result::Err(::json_parser::convert::FromJsonError::custom(#message))
+            }
+        }
+    };
+    Ok(body)
+}
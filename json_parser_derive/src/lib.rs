@@ -0,0 +1,109 @@
+//! `#[derive(ToJson, FromJson)]` for `json_parser`.
+//!
+//! Structs with named fields map to objects, newtype structs to their
+//! inner value, tuple structs to arrays and unit structs to `null`. Enums
+//! are externally tagged unless told otherwise.
+//!
+//! Container attributes, `#[json(...)]` on the struct or enum:
+//! - `rename_all = "camelCase"`: renames every field (structs) or variant
+//!   (enums). Also `PascalCase`, `snake_case`, `SCREAMING_SNAKE_CASE`,
+//!   `kebab-case`, `SCREAMING-KEBAB-CASE`, `lowercase` and `UPPERCASE`.
+//! - `default`: fields missing from the input are taken from the struct's
+//!   `Default` value.
+//! - `tag = "type"`: internally tagged, `{"type": "Variant", ...fields}`.
+//!   A newtype variant must hold a `convert::ToJsonObject`, such as a struct
+//!   with named fields, whose members sit next to the tag.
+//! - `tag = "t", content = "c"`: adjacently tagged,
+//!   `{"t": "Variant", "c": content}`.
+//! - `untagged`: the content alone; reading tries each variant in order.
+//!
+//! Field attributes:
+//! - `rename = "Latitude"`
+//! - `default` or `default = "path::to::fn"`: used when the field is missing.
+//! - `skip`: never written; read back as its default.
+//! - `flatten`: the field's members are written into the parent object and
+//!   read back from it.
+//!
+//! Variant attributes: `rename = "..."` and `rename_all = "..."`, the
+//! latter applying to the fields of a struct variant.
+//!
+//! A missing field whose type accepts `null`, such as `Option<T>`, reads as
+//! if it were `null`; any other missing field is an error.
+
+use proc_macro::TokenStream;
+use syn::{DeriveInput, FieldsNamed, GenericParam, Generics, Ident, Result, parse_macro_input};
+
+mod attr;
+mod from_json;
+mod to_json;
+
+use json_parser_case::Case;
+
+#[proc_macro_derive(ToJson, attributes(json))]
+pub fn derive_to_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    to_json::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(FromJson, attributes(json))]
+pub fn derive_from_json(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_json::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// A named field with its attributes resolved.
+struct NamedField<'a> {
+    ident: &'a Ident,
+    /// The member name in JSON.
+    key: String,
+    attrs: attr::Field,
+}
+
+fn named_fields(fields: &FieldsNamed, rename_all: Option<Case>) -> Result<Vec<NamedField<'_>>> {
+    fields
+        .named
+        .iter()
+        .map(|field| {
+            let ident = field.ident.as_ref().expect("named field");
+            let attrs = attr::Field::from_attrs(&field.attrs)?;
+            let name = ident.to_string();
+            let name = name.trim_start_matches("r#");
+            let key = match (&attrs.rename, rename_all) {
+                (Some(rename), _) => rename.clone(),
+                (None, Some(case)) => case.apply(name),
+                (None, None) => name.to_string(),
+            };
+            Ok(NamedField { ident, key, attrs })
+        })
+        .collect()
+}
+
+/// The JSON name of a variant and its attributes.
+fn variant_name(
+    variant: &syn::Variant,
+    container: &attr::Container,
+) -> Result<(String, attr::Variant)> {
+    let attrs = attr::Variant::from_attrs(&variant.attrs)?;
+    let name = variant.ident.to_string();
+    let name = match (&attrs.rename, container.rename_all) {
+        (Some(rename), _) => rename.clone(),
+        (None, Some(case)) => case.apply(&name),
+        (None, None) => name,
+    };
+    Ok((name, attrs))
+}
+
+/// Adds `bound` to every type parameter.
+fn add_bounds(generics: &Generics, bound: syn::TypeParamBound) -> Generics {
+    let mut generics = generics.clone();
+    for param in &mut generics.params {
+        if let GenericParam::Type(param) = param {
+            param.bounds.push(bound.clone());
+        }
+    }
+    generics
+}
@@ -0,0 +1,282 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DataEnum, DeriveInput, Error, Fields, Result, Type, parse_quote};
+
+use crate::attr::{Container, Tagging};
+use crate::{NamedField, add_bounds, named_fields, variant_name};
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let container = Container::from_attrs(&input.attrs)?;
+    let body = match &input.data {
+        Data::Struct(data) => struct_body(&container, &data.fields)?,
+        Data::Enum(data) => enum_body(&container, data)?,
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                input,
+                "ToJson cannot be derived for unions",
+            ));
+        }
+    };
+
+    let name = &input.ident;
+    let generics = add_bounds(
+        &input.generics,
+        parse_quote!(::json_parser::convert::ToJson),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    // types that always write an object can be merged into another one
+    let object = match &input.data {
+        Data::Struct(data) => matches!(data.fields, Fields::Named(_) | Fields::Unit),
+        Data::Enum(_) => matches!(
+            container.tagging,
+            Tagging::Internal { .. } | Tagging::Adjacent { .. }
+        ),
+        Data::Union(_) => false,
+    };
+    let object = object.then(|| {
+        quote! {
+            impl #impl_generics ::json_parser::convert::ToJsonObject
+                for #name #ty_generics #where_clause {}
+        }
+    });
+    Ok(quote! {
+        impl #impl_generics ::json_parser::convert::ToJson for #name #ty_generics #where_clause {
+            fn to_json(&self) -> ::json_parser::parsen::Json {
+                #body
+            }
+        }
+        #object
+    })
+}
+
+fn struct_body(container: &Container, fields: &Fields) -> Result<TokenStream> {
+    let body = match fields {
+        Fields::Named(named) => {
+            let fields = named_fields(named, container.rename_all)?;
+            let inserts = insert_fields(&fields, |field| {
+                let ident = field.ident;
+                quote!(&self.#ident)
+            });
+            quote! {
+                let mut __map = ::json_parser::map::Map::new();
+                #inserts
+                ::json_parser::parsen::Json::Object(::std::boxed::Box::new(__map))
+            }
+        }
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+            quote!(::json_parser::convert::ToJson::to_json(&self.0))
+        }
+        Fields::Unnamed(unnamed) => {
+            let indices = (0..unnamed.unnamed.len()).map(syn::Index::from);
+            quote! {
+                ::json_parser::parsen::Json::Array(vec![
+                    #(::json_parser::convert::ToJson::to_json(&self.#indices)),*
+                ])
+            }
+        }
+        Fields::Unit => quote!(::json_parser::parsen::Json::Null),
+    };
+    Ok(body)
+}
+
+/// Statements adding each field to the `Map` named `__map`.
+fn insert_fields(
+    fields: &[NamedField],
+    access: impl Fn(&NamedField) -> TokenStream,
+) -> TokenStream {
+    let inserts = fields
+        .iter()
+        .filter(|field| !field.attrs.skip)
+        .map(|field| {
+            let value = access(field);
+            let key = &field.key;
+            if field.attrs.flatten {
+                quote! {
+                    if let ::json_parser::parsen::Json::Object(__inner) =
+                        ::json_parser::convert::ToJson::to_json(#value)
+                    {
+                        __map.extend(*__inner);
+                    }
+                }
+            } else {
+                quote! {
+                    __map.insert(
+                        ::std::string::String::from(#key),
+                        ::json_parser::convert::ToJson::to_json(#value),
+                    );
+                }
+            }
+        });
+    quote!(#(#inserts)*)
+}
+
+fn enum_body(container: &Container, data: &DataEnum) -> Result<TokenStream> {
+    if data.variants.is_empty() {
+        return Ok(quote!(match *self {}));
+    }
+
+    let mut arms = Vec::new();
+    for variant in &data.variants {
+        let (name, attrs) = variant_name(variant, container)?;
+        let ident = &variant.ident;
+
+        // the pattern binding the variant's fields, and its content as Json
+        let (pattern, content, fields) = match &variant.fields {
+            Fields::Named(named) => {
+                let fields = named_fields(named, attrs.rename_all)?;
+                let bound = fields.iter().filter(|f| !f.attrs.skip).map(|f| f.ident);
+                let pattern = quote!(Self::#ident { #(#bound,)* .. });
+                (pattern, None, Some(fields))
+            }
+            Fields::Unnamed(unnamed) => {
+                let bindings: Vec<_> = (0..unnamed.unnamed.len())
+                    .map(|i| format_ident!("__{}", i))
+                    .collect();
+                let content = if bindings.len() == 1 {
+                    quote!(::json_parser::convert::ToJson::to_json(__0))
+                } else {
+                    quote! {
+                        ::json_parser::parsen::Json::Array(vec![
+                            #(::json_parser::convert::ToJson::to_json(#bindings)),*
+                        ])
+                    }
+                };
+                (quote!(Self::#ident(#(#bindings),*)), Some(content), None)
+            }
+            Fields::Unit => (quote!(Self::#ident), None, None),
+        };
+        let is_unit = matches!(variant.fields, Fields::Unit);
+        let is_newtype = matches!(&variant.fields, Fields::Unnamed(u) if u.unnamed.len() == 1);
+
+        let inserts = fields.as_ref().map(|fields| {
+            insert_fields(fields, |field| {
+                let ident = field.ident;
+                quote!(#ident)
+            })
+        });
+        // content of a struct variant as a standalone object
+        let content = content.unwrap_or_else(|| match &inserts {
+            Some(inserts) => quote! {{
+                let mut __map = ::json_parser::map::Map::new();
+                #inserts
+                ::json_parser::parsen::Json::Object(::std::boxed::Box::new(__map))
+            }},
+            None => quote!(::json_parser::parsen::Json::Null),
+        });
+
+        let body = match &container.tagging {
+            Tagging::External if is_unit => {
+                quote!(::json_parser::parsen::Json::Strings(::std::string::String::from(#name)))
+            }
+            Tagging::External => quote! {{
+                let mut __map = ::json_parser::map::Map::new();
+                __map.insert(::std::string::String::from(#name), #content);
+                ::json_parser::parsen::Json::Object(::std::boxed::Box::new(__map))
+            }},
+            Tagging::Internal { tag } => {
+                let rest = if let Some(inserts) = &inserts {
+                    inserts.clone()
+                } else if is_newtype {
+                    let payload = &variant.fields.iter().next().expect("newtype").ty;
+                    if !can_be_object(payload) {
+                        return Err(Error::new_spanned(
+                            payload,
+                            "the newtype variant of an internally tagged enum must hold a \
+                             struct with named fields, whose members are merged with the tag",
+                        ));
+                    }
+                    // ToJsonObject also rules out the types this cannot see
+                    quote! {
+                        __map.extend(::json_parser::convert::ToJsonObject::to_json_members(__0));
+                    }
+                } else if is_unit {
+                    quote!()
+                } else {
+                    return Err(Error::new_spanned(
+                        variant,
+                        "internally tagged enums cannot contain tuple variants",
+                    ));
+                };
+                quote! {{
+                    let mut __map = ::json_parser::map::Map::new();
+                    __map.insert(
+                        ::std::string::String::from(#tag),
+                        ::json_parser::parsen::Json::Strings(::std::string::String::from(#name)),
+                    );
+                    #rest
+                    ::json_parser::parsen::Json::Object(::std::boxed::Box::new(__map))
+                }}
+            }
+            Tagging::Adjacent {
+                tag,
+                content: content_key,
+            } => {
+                let content = if is_unit {
+                    quote!()
+                } else {
+                    quote!(__map.insert(::std::string::String::from(#content_key), #content);)
+                };
+                quote! {{
+                    let mut __map = ::json_parser::map::Map::new();
+                    __map.insert(
+                        ::std::string::String::from(#tag),
+                        ::json_parser::parsen::Json::Strings(::std::string::String::from(#name)),
+                    );
+                    #content
+                    ::json_parser::parsen::Json::Object(::std::boxed::Box::new(__map))
+                }}
+            }
+            Tagging::Untagged => content,
+        };
+        arms.push(quote!(#pattern => #body,));
+    }
+
+    Ok(quote! {
+        match self {
+            #(#arms)*
+        }
+    })
+}
+
+/// False for types that are never written as an object: primitives,
+/// strings, sequences, tuples and `Option`s.
+fn can_be_object(ty: &Type) -> bool {
+    match ty {
+        Type::Tuple(_) | Type::Array(_) | Type::Slice(_) => false,
+        Type::Reference(reference) => can_be_object(&reference.elem),
+        Type::Paren(paren) => can_be_object(&paren.elem),
+        Type::Group(group) => can_be_object(&group.elem),
+        Type::Path(path) if path.qself.is_none() => {
+            let Some(last) = path.path.segments.last() else {
+                return true;
+            };
+            !matches!(
+                last.ident.to_string().as_str(),
+                "bool"
+                    | "char"
+                    | "str"
+                    | "String"
+                    | "i8"
+                    | "i16"
+                    | "i32"
+                    | "i64"
+                    | "i128"
+                    | "isize"
+                    | "u8"
+                    | "u16"
+                    | "u32"
+                    | "u64"
+                    | "u128"
+                    | "usize"
+                    | "f32"
+                    | "f64"
+                    | "Option"
+                    | "Vec"
+                    | "VecDeque"
+                    | "HashSet"
+                    | "BTreeSet"
+            )
+        }
+        _ => true,
+    }
+}
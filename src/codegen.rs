@@ -0,0 +1,358 @@
+//! Rust type definitions generated from sample documents.
+//!
+//! The samples are first summarized with `schema::infer`, so a member that
+//! is missing from some objects, or `null` in some, becomes an `Option`,
+//! and the items of an array are merged into one type. Objects become
+//! structs deriving `ToJson` and `FromJson`, named after their key; a value
+//! seen with several types becomes an untagged enum.
+//!
+//! ```
+//! use json_parser::codegen::{CodegenOptions, generate};
+//! use json_parser::json;
+//!
+//! let sample = json!({
+//!     "Image": {
+//!         "Width": 800,
+//!         "Title": "View from 15th Floor",
+//!         "IDs": [116, 943, 234, 38793]
+//!     }
+//! });
+//! let code = generate([&sample], &CodegenOptions::default());
+//!
+//! assert!(code.contains("pub struct Root {\n    pub image: Image,\n}"));
+//! assert!(code.contains("#[json(rename_all = \"PascalCase\")]\npub struct Image {"));
+//! assert!(code.contains("    #[json(rename = \"IDs\")]\n    pub ids: Vec<i64>,"));
+//! ```
+
+use json_parser_case::{Case, capitalize, words};
+
+use crate::parsen::Json;
+use crate::schema::infer::{InferOptions, Inference};
+
+/// The `rename_all` policies tried on the fields of a struct.
+const POLICIES: [&str; 5] = [
+    "PascalCase",
+    "camelCase",
+    "SCREAMING_SNAKE_CASE",
+    "kebab-case",
+    "SCREAMING-KEBAB-CASE",
+];
+
+#[derive(Debug, Clone)]
+pub struct CodegenOptions {
+    /// The name of the type for the whole sample, or for its items when the
+    /// sample is an array.
+    pub root: String,
+    /// Listed in `#[derive(...)]` before `ToJson, FromJson`.
+    pub derives: Vec<String>,
+}
+
+impl Default for CodegenOptions {
+    fn default() -> Self {
+        Self {
+            root: "Root".to_string(),
+            derives: vec![
+                "Debug".to_string(),
+                "Clone".to_string(),
+                "PartialEq".to_string(),
+            ],
+        }
+    }
+}
+
+/// Source code for types that every one of `samples` reads into.
+pub fn generate<'a>(
+    samples: impl IntoIterator<Item = &'a Json>,
+    options: &CodegenOptions,
+) -> String {
+    let mut inference = Inference::new(InferOptions {
+        enum_limit: 0,
+        ranges: false,
+        formats: false,
+    });
+    for sample in samples {
+        inference.add(sample);
+    }
+    let schema = inference.schema();
+
+    let mut generator = Generator {
+        options,
+        defs: Vec::new(),
+        uses_json: false,
+    };
+    // an array is described by its items
+    let types = types(&schema);
+    match (&types[..], schema.get("items")) {
+        (["array"], Some(items)) => generator.type_of(items, &options.root, ""),
+        _ => generator.type_of(&schema, &options.root, ""),
+    };
+
+    let mut out = String::from("use json_parser::convert::{FromJson, ToJson};\n");
+    if generator.uses_json {
+        out.push_str("use json_parser::parsen::Json;\n");
+    }
+    for def in generator.defs.iter().flatten() {
+        out.push('\n');
+        out.push_str(&def.code);
+    }
+    out
+}
+
+struct Def {
+    name: String,
+    /// Everything after the name, to spot a type generated twice.
+    body: String,
+    code: String,
+}
+
+struct Generator<'a> {
+    options: &'a CodegenOptions,
+    /// In the order the types are first met; `None` for a slot whose type
+    /// turned out to be a duplicate.
+    defs: Vec<Option<Def>>,
+    uses_json: bool,
+}
+
+impl Generator<'_> {
+    /// The Rust type for values described by `schema`, defining it first
+    /// if needed. `name` is the name to give a new type, and `parent` the
+    /// name of the type it is in, to fall back on when the name is taken.
+    fn type_of(&mut self, schema: &Json, name: &str, parent: &str) -> String {
+        let types: Vec<&str> = types(schema)
+            .into_iter()
+            .filter(|ty| *ty != "null")
+            .collect();
+        match types[..] {
+            [] => self.json(),
+            ["boolean"] => "bool".to_string(),
+            ["integer"] => "i64".to_string(),
+            ["number"] => "f64".to_string(),
+            ["string"] => "String".to_string(),
+            ["array"] => match schema.get("items") {
+                Some(items) => {
+                    let item = singular(name);
+                    format!("Vec<{}>", self.type_of(items, &item, parent))
+                }
+                None => format!("Vec<{}>", self.json()),
+            },
+            ["object"] => self.object(schema, name, parent),
+            _ => self.either(schema, &types, name, parent),
+        }
+    }
+
+    fn json(&mut self) -> String {
+        self.uses_json = true;
+        "Json".to_string()
+    }
+
+    fn object(&mut self, schema: &Json, name: &str, parent: &str) -> String {
+        let slot = self.defs.len();
+        self.defs.push(None);
+
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Json::as_array)
+            .map(|names| names.iter().filter_map(Json::as_str).collect())
+            .unwrap_or_default();
+        let mut fields: Vec<(String, &str, String, bool)> = Vec::new();
+        if let Some(properties) = schema.get("properties").and_then(Json::as_object) {
+            for (key, property) in properties.iter() {
+                let mut ident = field_ident(key);
+                let mut n = 1;
+                while fields.iter().any(|(taken, ..)| *taken == ident) {
+                    n += 1;
+                    ident = format!("{}_{}", field_ident(key), n);
+                }
+                let ty = self.type_of(property, &type_ident(key), name);
+                let nullable = types(property).contains(&"null") && ty != "Json";
+                let optional = !required.contains(&key.as_str());
+                // a missing array reads as empty rather than as `None`
+                let (ty, default) = match (optional, nullable) {
+                    (true, false) if ty.starts_with("Vec<") => (ty, true),
+                    (false, false) => (ty, false),
+                    _ if ty == "Json" => (ty, false),
+                    _ => (format!("Option<{}>", ty), false),
+                };
+                fields.push((ident, key, ty, default));
+            }
+        }
+
+        // the renaming that leaves the fewest fields to rename one by one
+        let renamed = |case: Option<(&str, Case)>| {
+            fields
+                .iter()
+                .filter(|(ident, key, ..)| {
+                    let ident = ident.trim_start_matches("r#");
+                    case.map_or(ident.to_string(), |(_, case)| case.apply(ident)) != *key
+                })
+                .count()
+        };
+        let mut rename_all = None;
+        for policy in POLICIES {
+            let case = Case::parse(policy).map(|case| (policy, case));
+            if renamed(case) < renamed(rename_all) {
+                rename_all = case;
+            }
+        }
+
+        let mut body = String::new();
+        if let Some((policy, _)) = rename_all {
+            body.push_str(&format!("#[json(rename_all = \"{}\")]\n", policy));
+        }
+        body.push_str(" {\n");
+        for (ident, key, ty, default) in &fields {
+            let plain = ident.trim_start_matches("r#");
+            let mut attrs = Vec::new();
+            if rename_all.map_or(plain.to_string(), |(_, case)| case.apply(plain)) != *key {
+                attrs.push(format!("rename = {:?}", key));
+            }
+            if *default {
+                attrs.push("default".to_string());
+            }
+            if !attrs.is_empty() {
+                body.push_str(&format!("    #[json({})]\n", attrs.join(", ")));
+            }
+            body.push_str(&format!("    pub {}: {},\n", ident, ty));
+        }
+        body.push_str("}\n");
+
+        self.define(slot, name, parent, body, |name, body| {
+            // the attribute goes between the derive and the struct
+            let (attrs, fields) = body.split_at(body.find(" {\n").unwrap_or(0));
+            format!("{}pub struct {}{}", attrs, name, fields)
+        })
+    }
+
+    /// An untagged enum with a variant for each type.
+    fn either(&mut self, schema: &Json, types: &[&str], name: &str, parent: &str) -> String {
+        let slot = self.defs.len();
+        self.defs.push(None);
+
+        let mut body = String::from(" {\n");
+        for ty in types {
+            let (variant, inner) = match *ty {
+                "boolean" => ("Boolean", "bool".to_string()),
+                "integer" => ("Integer", "i64".to_string()),
+                "number" => ("Number", "f64".to_string()),
+                "string" => ("String", "String".to_string()),
+                "array" => {
+                    let items = match schema.get("items") {
+                        Some(items) => self.type_of(items, &singular(name), parent),
+                        None => self.json(),
+                    };
+                    ("Array", format!("Vec<{}>", items))
+                }
+                _ => (
+                    "Object",
+                    self.object(schema, &format!("{}Object", name), parent),
+                ),
+            };
+            body.push_str(&format!("    {}({}),\n", variant, inner));
+        }
+        body.push_str("}\n");
+
+        self.define(slot, name, parent, body, |name, body| {
+            format!("#[json(untagged)]\npub enum {}{}", name, body)
+        })
+    }
+
+    /// Fills `slot` with a type made by `code`, under `name` unless another
+    /// type already has it. An identical type is reused instead.
+    fn define(
+        &mut self,
+        slot: usize,
+        name: &str,
+        parent: &str,
+        body: String,
+        code: impl Fn(&str, &str) -> String,
+    ) -> String {
+        let existing = |name: &str| {
+            self.defs
+                .iter()
+                .flatten()
+                .find(|def| def.name == name)
+                .map(|def| def.body == body)
+        };
+        let mut candidates = vec![name.to_string(), format!("{}{}", parent, name)];
+        candidates.extend((2..).map(|n| format!("{}{}", name, n)).take(100));
+        let name = candidates
+            .into_iter()
+            .find(|name| existing(name) != Some(false))
+            .unwrap_or_else(|| name.to_string());
+        if existing(&name).is_none() {
+            let derives = self.options.derives.join(", ");
+            let separator = if derives.is_empty() { "" } else { ", " };
+            self.defs[slot] = Some(Def {
+                name: name.clone(),
+                code: format!(
+                    "#[derive({}{}ToJson, FromJson)]\n{}",
+                    derives,
+                    separator,
+                    code(&name, &body)
+                ),
+                body,
+            });
+        }
+        name
+    }
+}
+
+/// The `type` of a schema as a list.
+fn types(schema: &Json) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Json::Strings(ty)) => vec![ty.as_str()],
+        Some(Json::Array(types)) => types.iter().filter_map(Json::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn field_ident(key: &str) -> String {
+    let ident = words(key)
+        .iter()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("_");
+    match ident.chars().next() {
+        None => "field".to_string(),
+        Some(first) if first.is_ascii_digit() => format!("_{}", ident),
+        _ if matches!(ident.as_str(), "self" | "super" | "crate" | "Self") => {
+            format!("{}_", ident)
+        }
+        _ if KEYWORDS.contains(&ident.as_str()) => format!("r#{}", ident),
+        _ => ident,
+    }
+}
+
+fn type_ident(key: &str) -> String {
+    let ident: String = words(key).iter().map(|word| capitalize(word)).collect();
+    match ident.chars().next() {
+        None => "Field".to_string(),
+        Some(first) if first.is_ascii_digit() => format!("Field{}", ident),
+        _ => ident,
+    }
+}
+
+/// The name for one item of an array named `name`.
+fn singular(name: &str) -> String {
+    if let Some(stem) = name.strip_suffix("ies") {
+        format!("{}y", stem)
+    } else if let Some(stem) = ["sses", "xes", "ches", "shes"]
+        .iter()
+        .find_map(|suffix| name.strip_suffix(suffix).map(|stem| (stem, suffix)))
+        .map(|(stem, suffix)| format!("{}{}", stem, &suffix[..suffix.len() - 2]))
+    {
+        stem
+    } else if let Some(stem) = name.strip_suffix('s').filter(|stem| !stem.ends_with('s')) {
+        stem.to_string()
+    } else {
+        format!("{}Item", name)
+    }
+}
+
+const KEYWORDS: [&str; 48] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
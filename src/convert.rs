@@ -0,0 +1,710 @@
+//! Conversions between Rust values and `Json`.
+//!
+//! `ToJson` turns a value into a tree; `FromJson` reads one back. When
+//! `FromJson` fails, the error carries the path to the offending value,
+//! printed as e.g. `.[0].Zip: expected string, found number`.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::BuildHasher;
+use std::rc::Rc;
+
+use crate::map::Map;
+use crate::parsen::{Json, JsonType};
+
+#[cfg(feature = "derive")]
+pub use json_parser_derive::{FromJson, ToJson};
+
+pub trait ToJson {
+    fn to_json(&self) -> Json;
+}
+
+pub trait FromJson: Sized {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError>;
+}
+
+/// A `ToJson` whose output is always an object, or `null` for a unit
+/// struct, so that its members can be merged into another object. This is
+/// what the payload of a newtype variant of an internally tagged enum must
+/// be. `#[derive(ToJson)]` implements it for structs with named fields,
+/// unit structs and internally or adjacently tagged enums.
+pub trait ToJsonObject: ToJson {
+    /// The members `to_json` writes; none for `null`.
+    fn to_json_members(&self) -> Map {
+        match self.to_json() {
+            Json::Object(map) => *map,
+            _ => Map::new(),
+        }
+    }
+}
+
+/// One step of the path in a `FromJsonError`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    /// The value has the wrong type, e.g. `expected string, found number`.
+    Expected {
+        expected: String,
+        found: JsonType,
+    },
+    /// A number that does not fit the target type, e.g. 300 for a `u8`.
+    OutOfRange {
+        expected: String,
+        value: f64,
+    },
+    /// An array of the wrong length for a fixed-size array or tuple.
+    Length {
+        expected: usize,
+        found: usize,
+    },
+    MissingField(String),
+    UnknownVariant(String),
+    Custom(String),
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::Expected { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+            ErrorKind::OutOfRange { expected, value } => {
+                write!(f, "expected {}, found {}", expected, value)
+            }
+            ErrorKind::Length { expected, found } => {
+                write!(f, "expected array of length {}, found {}", expected, found)
+            }
+            ErrorKind::MissingField(name) => write!(f, "missing field {:?}", name),
+            ErrorKind::UnknownVariant(name) => write!(f, "unknown variant {:?}", name),
+            ErrorKind::Custom(message) => f.write_str(message),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FromJsonError {
+    // innermost segment first; segments are added as the error bubbles up
+    reversed_path: Vec<Segment>,
+    kind: ErrorKind,
+}
+
+impl FromJsonError {
+    pub fn new(kind: ErrorKind) -> FromJsonError {
+        FromJsonError {
+            reversed_path: Vec::new(),
+            kind,
+        }
+    }
+
+    /// `expected <expected>, found <type of json>`.
+    pub fn expected(expected: &str, json: &Json) -> FromJsonError {
+        FromJsonError::new(ErrorKind::Expected {
+            expected: expected.to_string(),
+            found: json.json_type(),
+        })
+    }
+
+    pub fn custom(message: impl fmt::Display) -> FromJsonError {
+        FromJsonError::new(ErrorKind::Custom(message.to_string()))
+    }
+
+    /// Records that the error happened inside the member `key`.
+    pub fn at_key(mut self, key: &str) -> FromJsonError {
+        self.reversed_path.push(Segment::Key(key.to_string()));
+        self
+    }
+
+    /// Records that the error happened inside the element `index`.
+    pub fn at_index(mut self, index: usize) -> FromJsonError {
+        self.reversed_path.push(Segment::Index(index));
+        self
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// Path from the document root to the value that failed.
+    pub fn segments(&self) -> impl Iterator<Item = &Segment> {
+        self.reversed_path.iter().rev()
+    }
+
+    /// The path as text: `.` for the root, otherwise `.[0].Zip`.
+    pub fn path(&self) -> String {
+        if self.reversed_path.is_empty() {
+            return ".".to_string();
+        }
+        let mut path = String::new();
+        for segment in self.segments() {
+            match segment {
+                Segment::Key(key) => path.push_str(&format!(".{}", key)),
+                Segment::Index(index) => path.push_str(&format!(".[{}]", index)),
+            }
+        }
+        path
+    }
+}
+
+impl fmt::Display for FromJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path(), self.kind)
+    }
+}
+
+impl std::error::Error for FromJsonError {}
+
+impl ToJson for Json {
+    fn to_json(&self) -> Json {
+        self.clone()
+    }
+}
+
+impl FromJson for Json {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        Ok(json.clone())
+    }
+}
+
+impl ToJson for () {
+    fn to_json(&self) -> Json {
+        Json::Null
+    }
+}
+
+impl FromJson for () {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        match json {
+            Json::Null => Ok(()),
+            _ => Err(FromJsonError::expected("null", json)),
+        }
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> Json {
+        Json::Boolean(*self)
+    }
+}
+
+impl FromJson for bool {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        match json {
+            Json::Boolean(b) => Ok(*b),
+            _ => Err(FromJsonError::expected("boolean", json)),
+        }
+    }
+}
+
+macro_rules! integer {
+    ($($t:ident)*) => {
+        $(
+            impl ToJson for $t {
+                fn to_json(&self) -> Json {
+                    Json::Number(*self as f64)
+                }
+            }
+
+            /// Accepts numbers with no fractional part that fit the type.
+            impl FromJson for $t {
+                fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+                    let Json::Number(num) = json else {
+                        return Err(FromJsonError::expected("number", json));
+                    };
+                    let out_of_range = || {
+                        FromJsonError::new(ErrorKind::OutOfRange {
+                            expected: stringify!($t).to_string(),
+                            value: *num,
+                        })
+                    };
+                    // every integer type fits in an i128, and every f64
+                    // below 2^127 in magnitude converts to one exactly.
+                    if num.fract() != 0.0 || num.abs() >= 2f64.powi(127) {
+                        return Err(out_of_range());
+                    }
+                    $t::try_from(*num as i128).map_err(|_| out_of_range())
+                }
+            }
+        )*
+    };
+}
+
+integer!(i8 i16 i32 i64 i128 isize u8 u16 u32 u64 u128 usize);
+
+macro_rules! float {
+    ($($t:ident)*) => {
+        $(
+            impl ToJson for $t {
+                fn to_json(&self) -> Json {
+                    Json::Number(*self as f64)
+                }
+            }
+
+            impl FromJson for $t {
+                fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+                    match json {
+                        Json::Number(num) => Ok(*num as $t),
+                        _ => Err(FromJsonError::expected("number", json)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+float!(f32 f64);
+
+impl ToJson for str {
+    fn to_json(&self) -> Json {
+        Json::Strings(self.to_string())
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> Json {
+        Json::Strings(self.clone())
+    }
+}
+
+impl FromJson for String {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        match json {
+            Json::Strings(st) => Ok(st.clone()),
+            _ => Err(FromJsonError::expected("string", json)),
+        }
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json(&self) -> Json {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &mut T {
+    fn to_json(&self) -> Json {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJsonObject + ?Sized> ToJsonObject for &T {}
+
+impl<T: ToJson + ?Sized> ToJson for Box<T> {
+    fn to_json(&self) -> Json {
+        (**self).to_json()
+    }
+}
+
+impl<T: ToJsonObject + ?Sized> ToJsonObject for Box<T> {}
+
+impl<T: FromJson> FromJson for Box<T> {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        T::from_json(json).map(Box::new)
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for Rc<T> {
+    fn to_json(&self) -> Json {
+        (**self).to_json()
+    }
+}
+
+impl<T: FromJson> FromJson for Rc<T> {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        T::from_json(json).map(Rc::new)
+    }
+}
+
+impl<T: ToJson + ToOwned + ?Sized> ToJson for Cow<'_, T> {
+    fn to_json(&self) -> Json {
+        (**self).to_json()
+    }
+}
+
+/// Always produces `Cow::Owned`.
+impl<T> FromJson for Cow<'_, T>
+where
+    T: ToOwned + ?Sized,
+    T::Owned: FromJson,
+{
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        T::Owned::from_json(json).map(Cow::Owned)
+    }
+}
+
+/// `None` is `null`.
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> Json {
+        match self {
+            Some(value) => value.to_json(),
+            None => Json::Null,
+        }
+    }
+}
+
+impl<T: FromJson> FromJson for Option<T> {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        match json {
+            Json::Null => Ok(None),
+            _ => T::from_json(json).map(Some),
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json(&self) -> Json {
+        Json::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> Json {
+        self.as_slice().to_json()
+    }
+}
+
+impl<T: FromJson> FromJson for Vec<T> {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        let Json::Array(vec) = json else {
+            return Err(FromJsonError::expected("array", json));
+        };
+        vec.iter()
+            .enumerate()
+            .map(|(i, json)| T::from_json(json).map_err(|err| err.at_index(i)))
+            .collect()
+    }
+}
+
+impl<T: ToJson, const N: usize> ToJson for [T; N] {
+    fn to_json(&self) -> Json {
+        self.as_slice().to_json()
+    }
+}
+
+impl<T: FromJson, const N: usize> FromJson for [T; N] {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        let vec = Vec::<T>::from_json(json)?;
+        let found = vec.len();
+        vec.try_into()
+            .map_err(|_| FromJsonError::new(ErrorKind::Length { expected: N, found }))
+    }
+}
+
+macro_rules! tuple {
+    ($($len:literal => ($($name:ident $index:tt)+))*) => {
+        $(
+            impl<$($name: ToJson),+> ToJson for ($($name,)+) {
+                fn to_json(&self) -> Json {
+                    Json::Array(vec![$(self.$index.to_json()),+])
+                }
+            }
+
+            /// Reads an array of exactly this many elements.
+            impl<$($name: FromJson),+> FromJson for ($($name,)+) {
+                fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+                    let Json::Array(vec) = json else {
+                        return Err(FromJsonError::expected("array", json));
+                    };
+                    if vec.len() != $len {
+                        return Err(FromJsonError::new(ErrorKind::Length {
+                            expected: $len,
+                            found: vec.len(),
+                        }));
+                    }
+                    Ok(($(
+                        $name::from_json(&vec[$index]).map_err(|err| err.at_index($index))?,
+                    )+))
+                }
+            }
+        )*
+    };
+}
+
+tuple! {
+    1 => (A 0)
+    2 => (A 0 B 1)
+    3 => (A 0 B 1 C 2)
+    4 => (A 0 B 1 C 2 D 3)
+    5 => (A 0 B 1 C 2 D 3 E 4)
+    6 => (A 0 B 1 C 2 D 3 E 4 F 5)
+    7 => (A 0 B 1 C 2 D 3 E 4 F 5 G 6)
+    8 => (A 0 B 1 C 2 D 3 E 4 F 5 G 6 H 7)
+}
+
+// helpers for the hand-written `FromJson` impls of the format modules
+
+pub(crate) fn object_entries(json: &Json) -> Result<&Map, FromJsonError> {
+    match json {
+        Json::Object(map) => Ok(map),
+        _ => Err(FromJsonError::expected("object", json)),
+    }
+}
+
+pub(crate) fn missing(key: &str) -> FromJsonError {
+    FromJsonError::new(ErrorKind::MissingField(key.to_string()))
+}
+
+/// The member `key` of `map` as a `T`, which must be there.
+pub(crate) fn required<T: FromJson>(map: &Map, key: &str) -> Result<T, FromJsonError> {
+    let json = map.get(key).ok_or_else(|| missing(key))?;
+    T::from_json(json).map_err(|err| err.at_key(key))
+}
+
+/// The member `key` of `map` as a `T`, `None` if it is missing or `null`.
+pub(crate) fn optional<T: FromJson>(map: &Map, key: &str) -> Result<Option<T>, FromJsonError> {
+    match map.get(key) {
+        Some(json) => Option::<T>::from_json(json).map_err(|err| err.at_key(key)),
+        None => Ok(None),
+    }
+}
+
+impl ToJson for Map {
+    fn to_json(&self) -> Json {
+        Json::Object(Box::new(self.clone()))
+    }
+}
+
+impl ToJsonObject for Map {}
+
+impl FromJson for Map {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        object_entries(json).cloned()
+    }
+}
+
+/// Members are written in key order, since a `HashMap` has none of its own.
+impl<T: ToJson, S> ToJson for HashMap<String, T, S> {
+    fn to_json(&self) -> Json {
+        let mut entries: Vec<(&String, &T)> = self.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        Json::Object(Box::new(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.clone(), value.to_json()))
+                .collect(),
+        ))
+    }
+}
+
+impl<T: ToJson, S> ToJsonObject for HashMap<String, T, S> {}
+
+impl<T: FromJson, S: BuildHasher + Default> FromJson for HashMap<String, T, S> {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        object_entries(json)?
+            .iter()
+            .map(|(key, json)| {
+                let value = T::from_json(json).map_err(|err| err.at_key(key))?;
+                Ok((key.clone(), value))
+            })
+            .collect()
+    }
+}
+
+impl<T: ToJson> ToJson for BTreeMap<String, T> {
+    fn to_json(&self) -> Json {
+        Json::Object(Box::new(
+            self.iter()
+                .map(|(key, value)| (key.clone(), value.to_json()))
+                .collect(),
+        ))
+    }
+}
+
+impl<T: ToJson> ToJsonObject for BTreeMap<String, T> {}
+
+impl<T: FromJson> FromJson for BTreeMap<String, T> {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        object_entries(json)?
+            .iter()
+            .map(|(key, json)| {
+                let value = T::from_json(json).map_err(|err| err.at_key(key))?;
+                Ok((key.clone(), value))
+            })
+            .collect()
+    }
+}
+
+impl Json {
+    /// Reads this value as a `T`.
+    pub fn to<T: FromJson>(&self) -> Result<T, FromJsonError> {
+        T::from_json(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    /// Written out by hand the way `#[derive(FromJson)]` does it.
+    #[derive(Debug, PartialEq)]
+    struct Address {
+        city: String,
+        zip: String,
+    }
+
+    impl FromJson for Address {
+        fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+            let map = object_entries(json)?;
+            let field = |key: &str| {
+                map.get(key)
+                    .ok_or_else(|| FromJsonError::new(ErrorKind::MissingField(key.to_string())))
+            };
+            Ok(Address {
+                city: String::from_json(field("City")?).map_err(|err| err.at_key("City"))?,
+                zip: String::from_json(field("Zip")?).map_err(|err| err.at_key("Zip"))?,
+            })
+        }
+    }
+
+    fn error<T: FromJson + fmt::Debug>(json: Json) -> String {
+        T::from_json(&json).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn nested_errors_render_their_path() {
+        let json = json!([{"City": "SF", "Zip": 94107}]);
+        assert_eq!(
+            error::<Vec<Address>>(json),
+            ".[0].Zip: expected string, found number"
+        );
+
+        let json = json!({"home": [{"City": "SF", "Zip": "94107"}, {"City": 1, "Zip": ""}]});
+        let err = HashMap::<String, Vec<Address>>::from_json(&json).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            ".home.[1].City: expected string, found number"
+        );
+        assert_eq!(
+            err.segments().collect::<Vec<_>>(),
+            [
+                &Segment::Key("home".to_string()),
+                &Segment::Index(1),
+                &Segment::Key("City".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn root_errors_have_a_dot_path() {
+        assert_eq!(
+            error::<String>(json!(1)),
+            ".: expected string, found number"
+        );
+        assert_eq!(
+            error::<bool>(json!(null)),
+            ".: expected boolean, found null"
+        );
+        assert_eq!(error::<()>(json!(0)), ".: expected null, found number");
+    }
+
+    #[test]
+    fn missing_fields_are_named() {
+        let json = json!([{"City": "SF"}]);
+        let err = Vec::<Address>::from_json(&json).unwrap_err();
+        assert_eq!(err.kind(), &ErrorKind::MissingField("Zip".to_string()));
+        assert_eq!(err.to_string(), r#".[0]: missing field "Zip""#);
+    }
+
+    #[test]
+    fn numbers_must_fit_the_integer_type() {
+        assert_eq!(u8::from_json(&json!(255)), Ok(255));
+        assert_eq!(error::<u8>(json!(256)), ".: expected u8, found 256");
+        assert_eq!(error::<u32>(json!(-1)), ".: expected u32, found -1");
+        assert_eq!(error::<i32>(json!(1.5)), ".: expected i32, found 1.5");
+        assert_eq!(
+            error::<i64>(json!(1e20)),
+            ".: expected i64, found 100000000000000000000"
+        );
+        assert_eq!(
+            i64::from_json(&json!(-9007199254740992.0)),
+            Ok(-9007199254740992)
+        );
+        assert_eq!(
+            error::<Vec<i8>>(json!([1, 200])),
+            ".[1]: expected i8, found 200"
+        );
+        assert_eq!(f32::from_json(&json!(0.5)), Ok(0.5));
+        assert_eq!(error::<f64>(json!("1")), ".: expected number, found string");
+    }
+
+    #[test]
+    fn options_are_null_or_the_value() {
+        assert_eq!(Option::<u8>::from_json(&json!(null)), Ok(None));
+        assert_eq!(Option::<u8>::from_json(&json!(3)), Ok(Some(3)));
+        assert_eq!(
+            error::<Option<u8>>(json!("3")),
+            ".: expected number, found string"
+        );
+        assert_eq!(Some(3).to_json(), json!(3));
+        assert_eq!(None::<u8>.to_json(), json!(null));
+    }
+
+    #[test]
+    fn vecs_and_arrays() {
+        let values = vec![1u16, 2, 3];
+        assert_eq!(values.to_json(), json!([1, 2, 3]));
+        assert_eq!(Vec::<u16>::from_json(&values.to_json()), Ok(values));
+        assert_eq!(<[u8; 2]>::from_json(&json!([1, 2])), Ok([1, 2]));
+        assert_eq!(
+            error::<[u8; 2]>(json!([1, 2, 3])),
+            ".: expected array of length 2, found 3"
+        );
+        assert_eq!(
+            error::<Vec<u8>>(json!({})),
+            ".: expected array, found object"
+        );
+    }
+
+    #[test]
+    fn maps_round_trip() {
+        let mut map = HashMap::new();
+        map.insert("b".to_string(), vec![true]);
+        map.insert("a".to_string(), vec![]);
+        // a HashMap is written in key order
+        assert_eq!(map.to_json().to_string(), r#"{"a":[],"b":[true]}"#);
+        assert_eq!(HashMap::from_json(&map.to_json()), Ok(map));
+
+        let tree: BTreeMap<String, String> = [("k".to_string(), "v".to_string())].into();
+        assert_eq!(BTreeMap::from_json(&tree.to_json()), Ok(tree));
+        assert_eq!(
+            error::<BTreeMap<String, u8>>(json!({"k": "v"})),
+            ".k: expected number, found string"
+        );
+    }
+
+    #[test]
+    fn tuples_have_a_fixed_length() {
+        let tuple = (1u8, "two".to_string(), Some(false));
+        assert_eq!(tuple.to_json(), json!([1, "two", false]));
+        assert_eq!(
+            <(u8, String, Option<bool>)>::from_json(&tuple.to_json()),
+            Ok(tuple)
+        );
+        assert_eq!(
+            error::<(u8, u8)>(json!([1])),
+            ".: expected array of length 2, found 1"
+        );
+        assert_eq!(
+            error::<(u8, String)>(json!([1, 2])),
+            ".[1]: expected string, found number"
+        );
+    }
+
+    #[test]
+    fn smart_pointers_convert_through() {
+        assert_eq!(Box::new(1u8).to_json(), json!(1));
+        assert_eq!(
+            Rc::<String>::from_json(&json!("s")),
+            Ok(Rc::new("s".to_string()))
+        );
+        let cow: Cow<str> = Cow::from_json(&json!("c")).unwrap();
+        assert_eq!(cow, "c");
+        assert_eq!(json!([1, 2]).to::<Vec<u8>>(), Ok(vec![1, 2]));
+    }
+}
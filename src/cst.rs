@@ -0,0 +1,730 @@
+//! A lossless concrete syntax tree, for editing a document without
+//! reformatting it.
+//!
+//! A `Document` keeps the source text alongside a tree of `Node`s that
+//! record where each value sits in it. Whitespace and comments are trivia
+//! and are never touched, numbers and strings keep their original lexemes,
+//! and printing an unedited document gives back the exact input. `set` and
+//! `remove` splice new text into the affected span only.
+//!
+//! The tree comes from the ordinary `Lexer`, keeping comments, and the
+//! source map of a recovering `Parser`, so a document is read exactly as
+//! `Parser` would read it, and malformed input is an error rather than a
+//! panic.
+//!
+//! ```
+//! use json_parser::cst::Document;
+//! use json_parser::json;
+//!
+//! let mut doc = Document::parse("{\n  \"a\": 1.50,\n  \"b\": [1, 2]\n}\n")?;
+//! doc.set("/b/1", &json!(3))?;
+//! doc.set("/c", &json!(true))?;
+//! assert_eq!(doc.to_string(), "{\n  \"a\": 1.50,\n  \"b\": [1, 3],\n  \"c\": true\n}\n");
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fmt;
+use std::iter::Peekable;
+use std::ops::Range;
+
+use crate::lexen::Lexer;
+use crate::map::Map;
+use crate::parsen::{Json, ParseError, Parser, ParserOptions, write_escaped};
+use crate::source_map::Spans;
+use crate::{Kind, Token, pointer};
+
+/// A value and the byte range of its text, brackets included for arrays
+/// and objects.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub span: Range<usize>,
+    pub kind: NodeKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NodeKind {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Node>),
+    Object(Vec<Member>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    pub key: String,
+    pub key_span: Range<usize>,
+    pub value: Node,
+}
+
+impl Node {
+    /// The element at a reference token: the last member with that key, or
+    /// the array element at that index.
+    fn child(&self, token: &str) -> Option<&Node> {
+        match &self.kind {
+            NodeKind::Object(members) => members
+                .iter()
+                .rev()
+                .find(|member| member.key == token)
+                .map(|member| &member.value),
+            NodeKind::Array(nodes) => nodes.get(parse_index(token)?),
+            _ => None,
+        }
+    }
+
+    pub fn to_json(&self) -> Json {
+        match &self.kind {
+            NodeKind::Null => Json::Null,
+            NodeKind::Boolean(b) => Json::Boolean(*b),
+            NodeKind::Number(num) => Json::Number(*num),
+            NodeKind::String(st) => Json::Strings(st.clone()),
+            NodeKind::Array(nodes) => Json::Array(nodes.iter().map(Node::to_json).collect()),
+            NodeKind::Object(members) => {
+                let mut map = Map::new();
+                for member in members {
+                    map.insert(member.key.clone(), member.value.to_json());
+                }
+                Json::Object(Box::new(map))
+            }
+        }
+    }
+}
+
+/// Array indices in a pointer are plain decimal, without leading zeros.
+fn parse_index(token: &str) -> Option<usize> {
+    if token.len() > 1 && token.starts_with('0') {
+        return None;
+    }
+    token.parse().ok()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    Whitespace,
+    /// A JSONC or JSON5 comment.
+    Comment,
+    /// `{ } [ ] : ,`
+    Punctuation,
+    String,
+    Number,
+    /// `true`, `false` or `null`.
+    Keyword,
+    /// A JSON5 unquoted key.
+    Identifier,
+}
+
+/// One piece of the source. The texts of all of a document's tokens, in
+/// order, add up to its source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxToken<'a> {
+    pub kind: SyntaxKind,
+    pub span: Range<usize>,
+    pub text: &'a str,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditError {
+    /// Not a JSON Pointer: it neither is empty nor starts with `/`.
+    InvalidPointer(String),
+    /// Nothing at the pointer, or for `set`, no container to add it to.
+    NotFound(String),
+    /// The parent of the pointer is a scalar.
+    NotAContainer(String),
+    /// `remove("")`: a document always has a value.
+    RemoveRoot,
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EditError::InvalidPointer(pointer) => write!(f, "invalid pointer {:?}", pointer),
+            EditError::NotFound(pointer) => write!(f, "nothing found at {:?}", pointer),
+            EditError::NotAContainer(pointer) => {
+                write!(f, "parent of {:?} is not an array or object", pointer)
+            }
+            EditError::RemoveRoot => f.write_str("cannot remove the root value"),
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+#[derive(Debug, Clone)]
+pub struct Document {
+    source: String,
+    /// Every token, comments included.
+    tokens: Vec<Token>,
+    root: Node,
+    options: ParserOptions,
+}
+
+impl Document {
+    /// Parses `source`, returning the first problem if it is malformed.
+    pub fn parse(source: impl Into<String>) -> Result<Document, ParseError> {
+        Document::parse_with(source, ParserOptions::default())
+    }
+
+    /// Parses `source` in the given dialect. Comments are always kept.
+    pub fn parse_with(
+        source: impl Into<String>,
+        options: ParserOptions,
+    ) -> Result<Document, ParseError> {
+        let source = source.into();
+        let options = ParserOptions {
+            keep_comments: true,
+            ..options
+        };
+        let tokens = Lexer::with_options(source.clone(), options)
+            .recovering()
+            .lex();
+        let mut parser = Parser::mapped(source.clone(), tokens.clone(), options);
+        if let Some(err) = parser.parse_recovering().errors.into_iter().next() {
+            return Err(err);
+        }
+        let spans = parser
+            .source_map()
+            .expect("a mapped parser builds a source map");
+        let root = build(&mut spans.iter().peekable(), &tokens);
+        Ok(Document {
+            source,
+            tokens,
+            root,
+            options,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn root(&self) -> &Node {
+        &self.root
+    }
+
+    /// The node at `pointer`.
+    pub fn get(&self, pointer: &str) -> Option<&Node> {
+        let tokens = pointer::tokens(pointer)?;
+        tokens
+            .iter()
+            .try_fold(&self.root, |node, token| node.child(token))
+    }
+
+    /// The original text of a node, e.g. `1.50` rather than `1.5`.
+    pub fn text(&self, node: &Node) -> &str {
+        &self.source[node.span.clone()]
+    }
+
+    pub fn to_json(&self) -> Json {
+        self.root.to_json()
+    }
+
+    /// Every token of the source, trivia included.
+    pub fn tokens(&self) -> Vec<SyntaxToken<'_>> {
+        let mut out = Vec::new();
+        let mut end = 0;
+        for token in &self.tokens {
+            let span = token.get_span();
+            if span.start > end {
+                out.push(self.syntax_token(SyntaxKind::Whitespace, end..span.start));
+            }
+            let kind = match token.kind {
+                Kind::EOF => break,
+                Kind::String(_) => SyntaxKind::String,
+                Kind::Number(_) => SyntaxKind::Number,
+                Kind::Boolean(_) | Kind::Null => SyntaxKind::Keyword,
+                Kind::Comment(_) => SyntaxKind::Comment,
+                Kind::Identifier(_) => SyntaxKind::Identifier,
+                _ => SyntaxKind::Punctuation,
+            };
+            end = span.end;
+            out.push(self.syntax_token(kind, span));
+        }
+        out
+    }
+
+    fn syntax_token(&self, kind: SyntaxKind, span: Range<usize>) -> SyntaxToken<'_> {
+        SyntaxToken {
+            kind,
+            text: &self.source[span.clone()],
+            span,
+        }
+    }
+
+    /// Replaces the value at `pointer`, or adds it when its parent exists:
+    /// a new object member, or an array element at index `len` (or `-`).
+    /// New members and elements copy the layout of the last existing one,
+    /// and go after a trailing comma if there is one.
+    pub fn set(&mut self, pointer: &str, value: &Json) -> Result<(), EditError> {
+        let tokens = pointer::tokens(pointer)
+            .ok_or_else(|| EditError::InvalidPointer(pointer.to_string()))?;
+        if let Some(node) = self.get(pointer) {
+            let span = node.span.clone();
+            self.splice(&[(span, value.to_string())]);
+            return Ok(());
+        }
+
+        let (last, parent) = tokens.split_last().expect("the root always exists");
+        let parent = self.parent(parent, pointer)?;
+        let (at, text) = match &parent.kind {
+            NodeKind::Object(members) => {
+                let mut key = String::new();
+                write_escaped(&mut key, last).expect("writing to a String");
+                match members.last() {
+                    Some(member) => {
+                        // the trivia before the last key, then the key and
+                        // the text from it up to the value
+                        let before = self.gap_before(member.key_span.start);
+                        let between = &self.source[member.key_span.end..member.value.span.start];
+                        let text = format!("{}{}{}{}", before, key, between, value);
+                        self.append_after(member.value.span.end, text)
+                    }
+                    None => (parent.span.start + 1, format!("{}: {}", key, value)),
+                }
+            }
+            NodeKind::Array(nodes) => {
+                if last != "-" && parse_index(last) != Some(nodes.len()) {
+                    return Err(EditError::NotFound(pointer.to_string()));
+                }
+                match nodes.last() {
+                    Some(node) => {
+                        let text = format!("{}{}", self.gap_before(node.span.start), value);
+                        self.append_after(node.span.end, text)
+                    }
+                    None => (parent.span.start + 1, value.to_string()),
+                }
+            }
+            _ => return Err(EditError::NotAContainer(pointer.to_string())),
+        };
+        self.splice(&[(at..at, text)]);
+        Ok(())
+    }
+
+    /// Where and what to insert to add `text` after the member or element
+    /// ending at `end`: after its trailing comma, keeping one at the end,
+    /// or after it with a new `,`.
+    fn append_after(&self, end: usize, text: String) -> (usize, String) {
+        let next = self.significant(self.token_after(end), 1);
+        if self.tokens[next].kind == Kind::ValueSeparator {
+            (self.tokens[next].get_span().end, format!("{},", text))
+        } else {
+            (end, format!(",{}", text))
+        }
+    }
+
+    /// Removes the value at `pointer` together with its separator, and
+    /// returns it. Comments around it are kept, and so is the layout of
+    /// the members or elements left.
+    pub fn remove(&mut self, pointer: &str) -> Result<Json, EditError> {
+        let tokens = pointer::tokens(pointer)
+            .ok_or_else(|| EditError::InvalidPointer(pointer.to_string()))?;
+        let (last, parent) = tokens.split_last().ok_or(EditError::RemoveRoot)?;
+        let parent = self.parent(parent, pointer)?;
+
+        // the span of the element, key included
+        let (span, removed) = match &parent.kind {
+            NodeKind::Object(members) => {
+                let member = members.iter().rev().find(|member| member.key == *last);
+                let member = member.ok_or_else(|| EditError::NotFound(pointer.to_string()))?;
+                let span = member.key_span.start..member.value.span.end;
+                (span, member.value.to_json())
+            }
+            NodeKind::Array(nodes) => {
+                let node = parse_index(last).and_then(|i| nodes.get(i));
+                let node = node.ok_or_else(|| EditError::NotFound(pointer.to_string()))?;
+                (node.span.clone(), node.to_json())
+            }
+            _ => return Err(EditError::NotAContainer(pointer.to_string())),
+        };
+
+        let first = self.token_after(span.start);
+        let after = self.token_after(span.end);
+        let before = self.significant(first - 1, -1);
+        let next = self.significant(after, 1);
+        let separated = self.tokens[next].kind == Kind::ValueSeparator;
+        let closer = if separated {
+            self.significant(next + 1, 1)
+        } else {
+            next
+        };
+        let is_closer = |kind: &Kind| matches!(kind, Kind::EndArray | Kind::EndObject);
+
+        let edits = if separated && !is_closer(&self.tokens[closer].kind) {
+            // up to whatever follows the `,`, so the next element keeps its
+            // leading comments
+            vec![(
+                span.start..self.tokens[next + 1].get_span().start,
+                String::new(),
+            )]
+        } else {
+            // the last element, with its trailing comma if it has one
+            let end = if separated {
+                self.tokens[next].get_span().end
+            } else {
+                span.end
+            };
+            let opener = &self.tokens[before];
+            if matches!(opener.kind, Kind::BeginArray | Kind::BeginObject)
+                && first == before + 1
+                && closer == self.token_after(end)
+            {
+                // the only element, with nothing else between the brackets
+                vec![(
+                    opener.get_span().end..self.tokens[closer].get_span().start,
+                    String::new(),
+                )]
+            } else if opener.kind == Kind::ValueSeparator && !separated {
+                // the `,` before it goes too, unless it becomes a trailing
+                // comma in place of the removed one
+                vec![
+                    (opener.get_span(), String::new()),
+                    (self.trivia_start(first)..end, String::new()),
+                ]
+            } else {
+                vec![(self.trivia_start(first)..end, String::new())]
+            }
+        };
+        self.splice(&edits);
+        Ok(removed)
+    }
+
+    /// The container at `tokens`, the parent of `pointer`.
+    fn parent(&self, tokens: &[String], pointer: &str) -> Result<&Node, EditError> {
+        tokens
+            .iter()
+            .try_fold(&self.root, |node, token| node.child(token))
+            .ok_or_else(|| EditError::NotFound(pointer.to_string()))
+    }
+
+    /// The index of the first token starting at or after `offset`.
+    fn token_after(&self, offset: usize) -> usize {
+        self.tokens
+            .partition_point(|token| token.get_span().start < offset)
+    }
+
+    /// The first token from `index` on, stepping by `step`, that is not a
+    /// comment.
+    fn significant(&self, mut index: usize, step: isize) -> usize {
+        while matches!(self.tokens[index].kind, Kind::Comment(_)) {
+            index = index.wrapping_add_signed(step);
+        }
+        index
+    }
+
+    /// Where the whitespace before the token at `index` starts. After a
+    /// line comment that is the next line, so the newline ending the comment
+    /// stays.
+    fn trivia_start(&self, index: usize) -> usize {
+        let end = self.tokens[index - 1].get_span().end;
+        match &self.tokens[index - 1].kind {
+            Kind::Comment(text) if text.starts_with("//") => {
+                let gap = &self.source[end..self.tokens[index].get_span().start];
+                end + gap.find('\n').map_or(0, |i| i + 1)
+            }
+            _ => end,
+        }
+    }
+
+    /// The trivia between the token ending before `offset` and `offset`.
+    fn gap_before(&self, offset: usize) -> &str {
+        let start = self
+            .tokens
+            .iter()
+            .map(|token| token.get_span().end)
+            .take_while(|&end| end <= offset)
+            .last()
+            .unwrap_or(0);
+        &self.source[start..offset]
+    }
+
+    /// Applies edits to disjoint spans of the source and reads it again.
+    fn splice(&mut self, edits: &[(Range<usize>, String)]) {
+        let mut source = std::mem::take(&mut self.source);
+        let mut edits = edits.to_vec();
+        edits.sort_by_key(|(span, _)| std::cmp::Reverse(span.start));
+        for (span, text) in edits {
+            source.replace_range(span, &text);
+        }
+        *self = Document::parse_with(source, self.options)
+            .expect("edits write valid JSON between whole tokens");
+    }
+}
+
+/// The source text, edits included.
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// Rebuilds the tree from a source map, whose spans come in document
+/// order with each value before the values inside it.
+fn build<'a>(spans: &mut Peekable<impl Iterator<Item = &'a Spans>>, tokens: &[Token]) -> Node {
+    let span = spans.next().expect("a span for every value").value.clone();
+    let inside = |spans: &mut Peekable<_>| {
+        spans
+            .peek()
+            .is_some_and(|next: &&Spans| next.value.start < span.end)
+    };
+    let kind = match &token_at(tokens, span.start).kind {
+        Kind::Null => NodeKind::Null,
+        Kind::Boolean(b) => NodeKind::Boolean(*b),
+        Kind::Number(num) => NodeKind::Number(*num),
+        Kind::String(st) => NodeKind::String(st.clone()),
+        Kind::BeginArray => {
+            let mut nodes = Vec::new();
+            while inside(spans) {
+                nodes.push(build(spans, tokens));
+            }
+            NodeKind::Array(nodes)
+        }
+        Kind::BeginObject => {
+            let mut members = Vec::new();
+            while inside(spans) {
+                let key_span = spans.peek().and_then(|next| next.key.clone());
+                let key_span = key_span.expect("object members have a key");
+                let key = match &token_at(tokens, key_span.start).kind {
+                    Kind::String(key) | Kind::Identifier(key) => key.clone(),
+                    kind => unreachable!("a key is a string or identifier, not {:?}", kind),
+                };
+                members.push(Member {
+                    key,
+                    key_span,
+                    value: build(spans, tokens),
+                });
+            }
+            NodeKind::Object(members)
+        }
+        kind => unreachable!("a well-formed value starts with a value, not {:?}", kind),
+    };
+    Node { span, kind }
+}
+
+fn token_at(tokens: &[Token], offset: usize) -> &Token {
+    let index = tokens.partition_point(|token| token.get_span().start < offset);
+    &tokens[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    fn jsonc(source: &str) -> Document {
+        Document::parse_with(source, ParserOptions::jsonc()).unwrap()
+    }
+
+    fn json5(source: &str) -> Document {
+        Document::parse_with(source, ParserOptions::json5()).unwrap()
+    }
+
+    fn removed(mut doc: Document, pointer: &str) -> String {
+        doc.remove(pointer).unwrap();
+        doc.to_string()
+    }
+
+    fn set(mut doc: Document, pointer: &str, value: Json) -> String {
+        doc.set(pointer, &value).unwrap();
+        doc.to_string()
+    }
+
+    #[test]
+    fn untouched_documents_round_trip_byte_for_byte() {
+        let sources = [
+            "null",
+            "  [ ]\n",
+            "{\r\n\t\"a\" :1.50e+2 ,\"b\":[ true,false ,null ],\"c\":{}\r\n}",
+            "{\"é\": \"\\u00e9\\n😀\", \"k\": -0.0}",
+        ];
+        for source in sources {
+            let doc = Document::parse(source).unwrap();
+            assert_eq!(doc.source(), source);
+            assert_eq!(doc.to_string(), source);
+            let tokens: String = doc.tokens().iter().map(|token| token.text).collect();
+            assert_eq!(tokens, source);
+        }
+        let source = "// config\n{\n  \"a\": 1, /* inline */\n  \"b\": [2] // end\n}\n";
+        assert_eq!(jsonc(source).to_string(), source);
+        let source = "{a: 0x1F, 'b': [+.5, Infinity,], c: 'it\\'s',}\n";
+        assert_eq!(json5(source).to_string(), source);
+    }
+
+    #[test]
+    fn nodes_keep_their_spans_and_lexemes() {
+        let doc = Document::parse(r#"{"a": 1.50, "b": ["A"]}"#).unwrap();
+        let a = doc.get("/a").unwrap();
+        assert_eq!(doc.text(a), "1.50");
+        assert_eq!(a.kind, NodeKind::Number(1.5));
+        let b0 = doc.get("/b/0").unwrap();
+        assert_eq!(doc.text(b0), r#""A""#);
+        assert_eq!(b0.kind, NodeKind::String("A".to_string()));
+        assert_eq!(doc.to_json(), json!({"a": 1.5, "b": ["A"]}));
+        assert_eq!(doc.get("/c"), None);
+        assert_eq!(doc.get("/b/01"), None);
+    }
+
+    #[test]
+    fn tokens_classify_trivia() {
+        let doc = jsonc("[1, // one\n true]");
+        let kinds: Vec<SyntaxKind> = doc.tokens().iter().map(|token| token.kind).collect();
+        use SyntaxKind::*;
+        assert_eq!(
+            kinds,
+            [
+                Punctuation,
+                Number,
+                Punctuation,
+                Whitespace,
+                Comment,
+                Whitespace,
+                Keyword,
+                Punctuation
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_input_is_an_error() {
+        for source in [r#"{"a": }"#, "[1, @]", "tru", "[1 2]", "", "[1] 2"] {
+            assert!(Document::parse(source).is_err(), "{source:?}");
+        }
+        let err = Document::parse("{\n  \"a\": 01\n}").unwrap_err();
+        assert_eq!((err.line(), err.column()), (2, 8));
+        assert_eq!(err.pointer(), "/a");
+        // comments need a dialect that has them
+        assert!(Document::parse("[1 /* c */]").is_err());
+        let err = Document::parse_with("[1 /* c", ParserOptions::jsonc()).unwrap_err();
+        assert!(
+            err.message().contains("unterminated block comment"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn set_replaces_only_the_value() {
+        let doc = jsonc("{\n  // port\n  \"port\": 80, /* was 8080 */\n  \"host\": \"a\"\n}");
+        assert_eq!(
+            set(doc, "/port", json!(443)),
+            "{\n  // port\n  \"port\": 443, /* was 8080 */\n  \"host\": \"a\"\n}"
+        );
+        let doc = Document::parse("[1, [2,  3]]").unwrap();
+        assert_eq!(
+            set(doc, "/1/1", json!({"a": null})),
+            r#"[1, [2,  {"a":null}]]"#
+        );
+        let doc = Document::parse(" 1 ").unwrap();
+        assert_eq!(set(doc, "", json!("x")), r#" "x" "#);
+    }
+
+    #[test]
+    fn set_adds_members_in_the_layout_of_the_last_one() {
+        let doc = Document::parse("{\n    \"a\" : 1\n}").unwrap();
+        assert_eq!(
+            set(doc, "/b", json!(2)),
+            "{\n    \"a\" : 1,\n    \"b\" : 2\n}"
+        );
+        let doc = Document::parse("[1,2]").unwrap();
+        assert_eq!(set(doc, "/-", json!(3)), "[1,2,3]");
+        let doc = Document::parse("[1, 2]").unwrap();
+        assert_eq!(set(doc, "/2", json!(3)), "[1, 2, 3]");
+        assert_eq!(
+            set(Document::parse("{}").unwrap(), "/a", json!(1)),
+            r#"{"a": 1}"#
+        );
+        assert_eq!(set(Document::parse("[ ]").unwrap(), "/0", json!(1)), "[1 ]");
+    }
+
+    #[test]
+    fn set_after_a_trailing_comma_keeps_one_comma() {
+        let doc = json5("{\n  a: 1,\n}");
+        assert_eq!(set(doc, "/b", json!(2)), "{\n  a: 1,\n  \"b\": 2,\n}");
+        let doc = json5("[1, 2, ]");
+        assert_eq!(set(doc, "/-", json!(3)), "[1, 2, 3, ]");
+        let doc = json5("[1 /* one */, ]");
+        assert_eq!(set(doc, "/-", json!(2)), "[1 /* one */,2, ]");
+    }
+
+    #[test]
+    fn remove_keeps_the_next_members_comments() {
+        let doc = jsonc("{\"a\": 1, // keep me\n \"b\": 2}");
+        assert_eq!(removed(doc, "/a"), "{// keep me\n \"b\": 2}");
+        let doc = jsonc("{\"a\": 1, // keep me\n \"b\": 2}");
+        assert_eq!(removed(doc, "/b"), "{\"a\": 1 // keep me\n}");
+        let doc = jsonc("[1, /* two */ 2, 3]");
+        assert_eq!(removed(doc, "/0"), "[/* two */ 2, 3]");
+    }
+
+    #[test]
+    fn remove_first_middle_and_last() {
+        let source = "{\n  \"a\": 1,\n  \"b\": 2,\n  \"c\": 3\n}";
+        let doc = || Document::parse(source).unwrap();
+        assert_eq!(removed(doc(), "/a"), "{\n  \"b\": 2,\n  \"c\": 3\n}");
+        assert_eq!(removed(doc(), "/b"), "{\n  \"a\": 1,\n  \"c\": 3\n}");
+        assert_eq!(removed(doc(), "/c"), "{\n  \"a\": 1,\n  \"b\": 2\n}");
+        let doc = || Document::parse("[1, 2, 3]").unwrap();
+        assert_eq!(removed(doc(), "/0"), "[2, 3]");
+        assert_eq!(removed(doc(), "/1"), "[1, 3]");
+        assert_eq!(removed(doc(), "/2"), "[1, 2]");
+    }
+
+    #[test]
+    fn removing_the_only_element_keeps_comments_between_the_brackets() {
+        assert_eq!(removed(Document::parse("[ 1 ]").unwrap(), "/0"), "[]");
+        assert_eq!(
+            removed(Document::parse("{\n  \"a\": 1\n}").unwrap(), "/a"),
+            "{}"
+        );
+        assert_eq!(removed(jsonc("[ /* c */ 1 ]"), "/0"), "[ /* c */ ]");
+        assert_eq!(removed(jsonc("{ // c\n  \"a\": 1\n}"), "/a"), "{ // c\n\n}");
+        assert_eq!(removed(jsonc("[1 /* one */]"), "/0"), "[ /* one */]");
+    }
+
+    #[test]
+    fn remove_with_trailing_commas() {
+        assert_eq!(removed(json5("[1, 2,]"), "/1"), "[1,]");
+        assert_eq!(removed(json5("[1, 2,]"), "/0"), "[2,]");
+        assert_eq!(removed(json5("{a: 1,}"), "/a"), "{}");
+    }
+
+    #[test]
+    fn remove_returns_the_value_and_reads_the_rest_again() {
+        let mut doc = Document::parse(r#"{"a": [1, {"b": 2}], "c": 3}"#).unwrap();
+        assert_eq!(doc.remove("/a/1"), Ok(json!({"b": 2})));
+        assert_eq!(doc.to_json(), json!({"a": [1], "c": 3}));
+        assert_eq!(doc.remove("/a"), Ok(json!([1])));
+        assert_eq!(doc.to_string(), r#"{"c": 3}"#);
+        // the last of a repeated key is the one that counts
+        let mut doc = Document::parse(r#"{"k": 1, "k": 2}"#).unwrap();
+        assert_eq!(doc.remove("/k"), Ok(json!(2)));
+        assert_eq!(doc.to_string(), r#"{"k": 1}"#);
+    }
+
+    #[test]
+    fn edit_errors() {
+        let mut doc = Document::parse(r#"{"a": [1], "s": "x"}"#).unwrap();
+        let value = json!(1);
+        assert_eq!(
+            doc.set("a", &value),
+            Err(EditError::InvalidPointer("a".to_string()))
+        );
+        assert_eq!(
+            doc.set("/b/c", &value),
+            Err(EditError::NotFound("/b/c".to_string()))
+        );
+        assert_eq!(
+            doc.set("/a/5", &value),
+            Err(EditError::NotFound("/a/5".to_string()))
+        );
+        assert_eq!(
+            doc.set("/s/0", &value),
+            Err(EditError::NotAContainer("/s/0".to_string()))
+        );
+        assert_eq!(doc.remove(""), Err(EditError::RemoveRoot));
+        assert_eq!(doc.remove("/b"), Err(EditError::NotFound("/b".to_string())));
+        assert_eq!(
+            doc.remove("/a/1"),
+            Err(EditError::NotFound("/a/1".to_string()))
+        );
+        assert_eq!(doc.to_string(), r#"{"a": [1], "s": "x"}"#);
+    }
+}
@@ -0,0 +1,535 @@
+//! Deserializing with serde, behind the `serde` feature.
+//!
+//! `from_str` pulls tokens from the `Lexer` one at a time and hands them
+//! straight to the target type's `Deserialize` impl, so no `Json` tree is
+//! built on the way. Malformed input, whether a bad token or a bad
+//! structure, comes back as an `Error`; it never panics.
+
+use std::fmt;
+
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+
+use crate::lexen::Lexer;
+use crate::map::Map;
+use crate::parsen::Json;
+use crate::{Kind, Token};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    message: String,
+    /// Zero-based line and column of the offending token, if there was one.
+    position: Option<(u32, u32)>,
+}
+
+impl Error {
+    fn at(token: &Token, message: impl fmt::Display) -> Error {
+        Error {
+            message: message.to_string(),
+            position: Some((token.line, token.column)),
+        }
+    }
+
+    /// The one-based line the error was found on.
+    pub fn line(&self) -> Option<u32> {
+        self.position.map(|(line, _)| line + 1)
+    }
+
+    /// The one-based column the error was found at, in characters.
+    pub fn column(&self) -> Option<u32> {
+        self.position.map(|(_, column)| column + 1)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.line(), self.column()) {
+            (Some(line), Some(column)) => {
+                write!(f, "{} at line {}, column {}", self.message, line, column)
+            }
+            _ => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error {
+            message: msg.to_string(),
+            position: None,
+        }
+    }
+}
+
+/// Reads a `T` from JSON text.
+pub fn from_str<T: DeserializeOwned>(text: &str) -> Result<T, Error> {
+    let mut deserializer = Deserializer::from_str(text);
+    let value = T::deserialize(&mut deserializer).map_err(|mut err| {
+        // errors raised by the target type carry no position of their own
+        err.position = err.position.or(Some(deserializer.position));
+        err
+    })?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+pub struct Deserializer {
+    lexer: Lexer,
+    peeked: Option<Token>,
+    /// Line and column of the last token taken.
+    position: (u32, u32),
+}
+
+impl Deserializer {
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(text: &str) -> Deserializer {
+        Deserializer {
+            // malformed tokens come out as `Kind::Error` to be reported
+            lexer: Lexer::new(text.to_string()).recovering(),
+            peeked: None,
+            position: (0, 0),
+        }
+    }
+
+    /// Checks that nothing but whitespace follows the value just read.
+    pub fn end(&mut self) -> Result<(), Error> {
+        let token = self.next();
+        match &token.kind {
+            Kind::EOF => Ok(()),
+            Kind::Error(message) => Err(Error::at(&token, message)),
+            _ => Err(Error::at(&token, "trailing characters")),
+        }
+    }
+
+    fn peek(&mut self) -> &Token {
+        self.peeked.get_or_insert_with(|| self.lexer.next_token())
+    }
+
+    fn next(&mut self) -> Token {
+        let token = match self.peeked.take() {
+            Some(token) => token,
+            None => self.lexer.next_token(),
+        };
+        self.position = (token.line, token.column);
+        token
+    }
+
+    fn expect(&mut self, kind: Kind, what: &str) -> Result<(), Error> {
+        let token = self.next();
+        if token.kind == kind {
+            Ok(())
+        } else {
+            Err(unexpected(&token, what))
+        }
+    }
+}
+
+// a malformed token speaks for itself; anything else was unexpected
+fn unexpected(token: &Token, expected: &str) -> Error {
+    match &token.kind {
+        Kind::Error(message) => Error::at(token, message),
+        found => Error::at(
+            token,
+            format!("expected {}, found {}", expected, found.describe()),
+        ),
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let token = self.next();
+        match token.kind {
+            Kind::Null => visitor.visit_unit(),
+            Kind::Boolean(b) => visitor.visit_bool(b),
+            // integral numbers are offered as integers so that integer
+            // targets accept them
+            Kind::Number(num)
+                if num.fract() == 0.0 && num.is_sign_positive() && num < u64::MAX as f64 =>
+            {
+                visitor.visit_u64(num as u64)
+            }
+            Kind::Number(num) if num.fract() == 0.0 && num >= i64::MIN as f64 && num < 0.0 => {
+                visitor.visit_i64(num as i64)
+            }
+            Kind::Number(num) => visitor.visit_f64(num),
+            Kind::String(st) => visitor.visit_string(st),
+            Kind::BeginArray => {
+                let value = visitor.visit_seq(Seq {
+                    de: self,
+                    first: true,
+                })?;
+                self.expect(Kind::EndArray, "`]`")?;
+                Ok(value)
+            }
+            Kind::BeginObject => {
+                let value = visitor.visit_map(Members {
+                    de: self,
+                    first: true,
+                })?;
+                self.expect(Kind::EndObject, "`}`")?;
+                Ok(value)
+            }
+            _ => Err(unexpected(&token, "value")),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.peek().kind == Kind::Null {
+            self.next();
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    /// `"Variant"` for unit variants, `{"Variant": content}` otherwise.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let token = self.next();
+        match token.kind {
+            Kind::String(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Kind::BeginObject => {
+                let value = visitor.visit_enum(Variant { de: self })?;
+                self.expect(Kind::EndObject, "`}`")?;
+                Ok(value)
+            }
+            _ => Err(unexpected(&token, "string or object")),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct Seq<'a> {
+    de: &'a mut Deserializer,
+    first: bool,
+}
+
+impl<'de> SeqAccess<'de> for Seq<'_> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        if self.de.peek().kind == Kind::EndArray {
+            return Ok(None);
+        }
+        if !std::mem::replace(&mut self.first, false) {
+            self.de.expect(Kind::ValueSeparator, "`,` or `]`")?;
+        }
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+struct Members<'a> {
+    de: &'a mut Deserializer,
+    first: bool,
+}
+
+impl<'de> MapAccess<'de> for Members<'_> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        if self.de.peek().kind == Kind::EndObject {
+            return Ok(None);
+        }
+        if !std::mem::replace(&mut self.first, false) {
+            self.de.expect(Kind::ValueSeparator, "`,` or `}`")?;
+        }
+        read_key(self.de, seed).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        self.de.expect(Kind::NameSeparator, "`:`")?;
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+fn read_key<'de, K: DeserializeSeed<'de>>(
+    de: &mut Deserializer,
+    seed: K,
+) -> Result<K::Value, Error> {
+    let token = de.next();
+    match token.kind {
+        Kind::String(key) => seed.deserialize(key.into_deserializer()),
+        _ => Err(unexpected(&token, "string key")),
+    }
+}
+
+/// The `{"Variant": content}` form of an enum.
+struct Variant<'a> {
+    de: &'a mut Deserializer,
+}
+
+impl<'de> EnumAccess<'de> for Variant<'_> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), Error> {
+        let variant = read_key(self.de, seed)?;
+        self.de.expect(Kind::NameSeparator, "`:`")?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Variant<'_> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        de::Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self.de, visitor)
+    }
+}
+
+impl<'de> de::Deserialize<'de> for Json {
+    fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Json, D::Error> {
+        deserializer.deserialize_any(JsonVisitor)
+    }
+}
+
+struct JsonVisitor;
+
+impl<'de> Visitor<'de> for JsonVisitor {
+    type Value = Json;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<E>(self, b: bool) -> Result<Json, E> {
+        Ok(Json::Boolean(b))
+    }
+
+    fn visit_i64<E>(self, num: i64) -> Result<Json, E> {
+        Ok(Json::Number(num as f64))
+    }
+
+    fn visit_u64<E>(self, num: u64) -> Result<Json, E> {
+        Ok(Json::Number(num as f64))
+    }
+
+    fn visit_f64<E>(self, num: f64) -> Result<Json, E> {
+        Ok(Json::Number(num))
+    }
+
+    fn visit_str<E>(self, st: &str) -> Result<Json, E> {
+        Ok(Json::Strings(st.to_string()))
+    }
+
+    fn visit_string<E>(self, st: String) -> Result<Json, E> {
+        Ok(Json::Strings(st))
+    }
+
+    fn visit_unit<E>(self) -> Result<Json, E> {
+        Ok(Json::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Json, E> {
+        Ok(Json::Null)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Json, D::Error> {
+        de::Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Json, A::Error> {
+        let mut vec = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(json) = seq.next_element()? {
+            vec.push(json);
+        }
+        Ok(Json::Array(vec))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut members: A) -> Result<Json, A::Error> {
+        let mut map = Map::new();
+        while let Some((key, json)) = members.next_entry::<String, Json>()? {
+            map.insert(key, json);
+        }
+        Ok(Json::Object(Box::new(map)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+    use crate::json;
+    use crate::ser;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Point {
+        x: i32,
+        y: i32,
+        label: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Empty,
+        Circle(f64),
+        Segment(Point, Point),
+        Rect { width: u32, height: u32 },
+    }
+
+    /// Writes `value` with `ser::to_string`, checks the text and reads it back.
+    fn round_trip<T>(value: T, expected: &str)
+    where
+        T: Serialize + DeserializeOwned + PartialEq + fmt::Debug,
+    {
+        let text = ser::to_string(&value).unwrap();
+        assert_eq!(text, expected);
+        assert_eq!(from_str::<T>(&text), Ok(value));
+    }
+
+    #[test]
+    fn structs_round_trip() {
+        round_trip(
+            Point {
+                x: 1,
+                y: -2,
+                label: Some("a".to_string()),
+            },
+            r#"{"x":1,"y":-2,"label":"a"}"#,
+        );
+    }
+
+    #[test]
+    fn enums_round_trip_externally_tagged() {
+        round_trip(Shape::Empty, r#""Empty""#);
+        round_trip(Shape::Circle(1.5), r#"{"Circle":1.5}"#);
+        round_trip(
+            Shape::Segment(
+                Point {
+                    x: 0,
+                    y: 0,
+                    label: None,
+                },
+                Point {
+                    x: 1,
+                    y: 1,
+                    label: None,
+                },
+            ),
+            r#"{"Segment":[{"x":0,"y":0,"label":null},{"x":1,"y":1,"label":null}]}"#,
+        );
+        round_trip(
+            Shape::Rect {
+                width: 2,
+                height: 3,
+            },
+            r#"{"Rect":{"width":2,"height":3}}"#,
+        );
+    }
+
+    #[test]
+    fn options_round_trip() {
+        round_trip(None::<u8>, "null");
+        round_trip(Some(7u8), "7");
+        round_trip(vec![Some(true), None], "[true,null]");
+    }
+
+    #[test]
+    fn maps_round_trip() {
+        let map = BTreeMap::from([("a".to_string(), 1u32), ("b".to_string(), 2)]);
+        round_trip(map, r#"{"a":1,"b":2}"#);
+        round_trip(BTreeMap::<String, u32>::new(), "{}");
+    }
+
+    #[test]
+    fn json_values_round_trip() {
+        round_trip(
+            json!({"a": [1, -2, 0.5, null, true], "b": {"c": "d"}}),
+            r#"{"a":[1,-2,0.5,null,true],"b":{"c":"d"}}"#,
+        );
+    }
+
+    #[test]
+    fn whitespace_between_tokens_is_skipped() {
+        let point: Point = from_str(" { \"x\" : 1 ,\n \"y\" : 2 } ").unwrap();
+        assert_eq!(
+            point,
+            Point {
+                x: 1,
+                y: 2,
+                label: None,
+            }
+        );
+    }
+
+    #[test]
+    fn malformed_tokens_are_errors() {
+        let err = from_str::<Vec<u32>>("[1, @]").unwrap_err();
+        assert_eq!((err.line(), err.column()), (Some(1), Some(5)));
+        assert!(err.to_string().starts_with("unexpected character"), "{err}");
+        assert!(from_str::<String>(r#""open"#).is_err());
+        assert!(from_str::<bool>("tru").is_err());
+        assert!(from_str::<Json>("[1, 2").is_err());
+    }
+
+    #[test]
+    fn malformed_structure_is_an_error() {
+        let err = from_str::<Vec<u32>>("[1 2]").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected `,` or `]`, found number at line 1, column 4"
+        );
+        let err = from_str::<u32>("1 2").unwrap_err();
+        assert_eq!(err.to_string(), "trailing characters at line 1, column 3");
+        let err = from_str::<BTreeMap<String, u32>>("{1: 2}").unwrap_err();
+        assert!(err.to_string().starts_with("expected string key"), "{err}");
+    }
+
+    #[test]
+    fn type_mismatches_carry_a_position() {
+        let err = from_str::<Point>(r#"{"x": "1", "y": 2}"#).unwrap_err();
+        assert_eq!(err.line(), Some(1));
+        assert!(err.column().is_some());
+        let err = from_str::<Point>(r#"{"x": 1}"#).unwrap_err();
+        assert!(err.to_string().starts_with("missing field `y`"), "{err}");
+    }
+}
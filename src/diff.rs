@@ -0,0 +1,611 @@
+//! Structural diff between two `Json` documents.
+//!
+//! `diff` walks both trees side by side and reports every added, removed and
+//! changed value by its JSON Pointer. `report` renders the changes as a
+//! unified-style text report, optionally colored for a terminal.
+
+use std::fmt;
+use std::fmt::Write as _;
+
+use crate::map::Map;
+use crate::parsen::Json;
+use crate::pointer;
+
+/// How elements of two arrays are paired up before they are compared.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArrayMatch {
+    /// Element `i` on the left is compared with element `i` on the right.
+    Index,
+    /// Elements are aligned on their longest common subsequence, so an
+    /// insertion in the middle of an array shows up as a single addition.
+    Lcs,
+    /// Objects are paired by the value of the named field, e.g. `"id"`.
+    /// Falls back to `Index` unless every element on both sides is an object
+    /// carrying a unique value for that field.
+    Key(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    pub arrays: ArrayMatch,
+    /// Numbers whose absolute difference is at most this are equal.
+    pub tolerance: f64,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            arrays: ArrayMatch::Index,
+            tolerance: 0.0,
+        }
+    }
+}
+
+/// A single difference. Removed and changed values are addressed by their
+/// position in the left document, added values by their position in the
+/// right one.
+#[derive(Debug)]
+pub enum Change<'a> {
+    Added {
+        path: String,
+        value: &'a Json,
+    },
+    Removed {
+        path: String,
+        value: &'a Json,
+    },
+    Changed {
+        path: String,
+        old: &'a Json,
+        new: &'a Json,
+    },
+}
+
+impl Change<'_> {
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. } => path,
+            Change::Removed { path, .. } => path,
+            Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Change<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Change::Added { path, value } => write!(f, "added {}: {}", display_path(path), value),
+            Change::Removed { path, value } => {
+                write!(f, "removed {}: {}", display_path(path), value)
+            }
+            Change::Changed { path, old, new } => {
+                write!(f, "changed {}: {} -> {}", display_path(path), old, new)
+            }
+        }
+    }
+}
+
+/// Lists the changes that turn `left` into `right`, in document order: the
+/// members of an object in the left document's order, followed by those only
+/// the right one has, in its order.
+pub fn diff<'a>(left: &'a Json, right: &'a Json, options: &DiffOptions) -> Vec<Change<'a>> {
+    let mut changes = Vec::new();
+    walk("", left, right, options, &mut changes);
+    changes
+}
+
+/// Renders `changes` as a unified-style report: one `@@ path @@` hunk per
+/// change with the old value on a `-` line and the new one on a `+` line.
+/// With `color` set the lines carry ANSI escape codes.
+pub fn report(changes: &[Change], color: bool) -> String {
+    let (red, green, cyan, reset) = if color {
+        ("\x1b[31m", "\x1b[32m", "\x1b[36m", "\x1b[0m")
+    } else {
+        ("", "", "", "")
+    };
+
+    let mut out = String::new();
+    for change in changes {
+        let _ = writeln!(out, "{cyan}@@ {} @@{reset}", display_path(change.path()));
+        match change {
+            Change::Added { value, .. } => {
+                let _ = writeln!(out, "{green}+{}{reset}", value);
+            }
+            Change::Removed { value, .. } => {
+                let _ = writeln!(out, "{red}-{}{reset}", value);
+            }
+            Change::Changed { old, new, .. } => {
+                let _ = writeln!(out, "{red}-{}{reset}", old);
+                let _ = writeln!(out, "{green}+{}{reset}", new);
+            }
+        }
+    }
+    out
+}
+
+fn display_path(path: &str) -> &str {
+    if path.is_empty() { "<root>" } else { path }
+}
+
+fn walk<'a>(
+    path: &str,
+    left: &'a Json,
+    right: &'a Json,
+    options: &DiffOptions,
+    out: &mut Vec<Change<'a>>,
+) {
+    match (left, right) {
+        (Json::Object(l), Json::Object(r)) => diff_objects(path, l, r, options, out),
+        (Json::Array(l), Json::Array(r)) => match &options.arrays {
+            ArrayMatch::Index => diff_by_index(path, l, r, options, out),
+            ArrayMatch::Lcs => diff_by_lcs(path, l, r, options, out),
+            ArrayMatch::Key(field) => diff_by_key(path, l, r, field, options, out),
+        },
+        (Json::Number(a), Json::Number(b)) => {
+            if !(a == b || (a - b).abs() <= options.tolerance) {
+                out.push(changed(path, left, right));
+            }
+        }
+        (Json::Strings(a), Json::Strings(b)) => {
+            if a != b {
+                out.push(changed(path, left, right));
+            }
+        }
+        (Json::Boolean(a), Json::Boolean(b)) => {
+            if a != b {
+                out.push(changed(path, left, right));
+            }
+        }
+        (Json::Null, Json::Null) => {}
+        _ => out.push(changed(path, left, right)),
+    }
+}
+
+fn changed<'a>(path: &str, old: &'a Json, new: &'a Json) -> Change<'a> {
+    Change::Changed {
+        path: path.to_string(),
+        old,
+        new,
+    }
+}
+
+/// Whether `diff` would find no changes between `left` and `right`,
+/// stopping at the first difference instead of listing them all.
+fn same(left: &Json, right: &Json, options: &DiffOptions) -> bool {
+    match (left, right) {
+        (Json::Object(l), Json::Object(r)) => {
+            l.len() == r.len()
+                && l.iter()
+                    .all(|(key, l)| r.get(key).is_some_and(|r| same(l, r, options)))
+        }
+        (Json::Array(l), Json::Array(r)) => match &options.arrays {
+            ArrayMatch::Key(field) => match (keys_of(l, field), keys_of(r, field)) {
+                (Some(left_keys), Some(right_keys)) => {
+                    left_keys.len() == right_keys.len()
+                        && left_keys.iter().zip(l).all(|(key, l)| {
+                            right_keys
+                                .iter()
+                                .position(|k| k == key)
+                                .is_some_and(|j| same(l, &r[j], options))
+                        })
+                }
+                _ => same_elements(l, r, options),
+            },
+            // no changes either way only if the elements pair up in order
+            ArrayMatch::Index | ArrayMatch::Lcs => same_elements(l, r, options),
+        },
+        (Json::Number(a), Json::Number(b)) => a == b || (a - b).abs() <= options.tolerance,
+        (Json::Strings(a), Json::Strings(b)) => a == b,
+        (Json::Boolean(a), Json::Boolean(b)) => a == b,
+        (Json::Null, Json::Null) => true,
+        _ => false,
+    }
+}
+
+fn same_elements(left: &[Json], right: &[Json], options: &DiffOptions) -> bool {
+    left.len() == right.len() && left.iter().zip(right).all(|(l, r)| same(l, r, options))
+}
+
+fn diff_objects<'a>(
+    path: &str,
+    left: &'a Map,
+    right: &'a Map,
+    options: &DiffOptions,
+    out: &mut Vec<Change<'a>>,
+) {
+    let keys = left
+        .keys()
+        .chain(right.keys().filter(|k| !left.contains_key(k)));
+
+    for key in keys {
+        let child = pointer::push(path, key);
+        match (left.get(key), right.get(key)) {
+            (Some(l), Some(r)) => walk(&child, l, r, options, out),
+            (Some(l), None) => out.push(Change::Removed {
+                path: child,
+                value: l,
+            }),
+            (None, Some(r)) => out.push(Change::Added {
+                path: child,
+                value: r,
+            }),
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+fn diff_by_index<'a>(
+    path: &str,
+    left: &'a [Json],
+    right: &'a [Json],
+    options: &DiffOptions,
+    out: &mut Vec<Change<'a>>,
+) {
+    for i in 0..left.len().max(right.len()) {
+        let child = pointer::push(path, &i.to_string());
+        match (left.get(i), right.get(i)) {
+            (Some(l), Some(r)) => walk(&child, l, r, options, out),
+            (Some(l), None) => out.push(Change::Removed {
+                path: child,
+                value: l,
+            }),
+            (None, Some(r)) => out.push(Change::Added {
+                path: child,
+                value: r,
+            }),
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+fn diff_by_lcs<'a>(
+    path: &str,
+    left: &'a [Json],
+    right: &'a [Json],
+    options: &DiffOptions,
+    out: &mut Vec<Change<'a>>,
+) {
+    // the common ends are matched as they are, leaving the table to the
+    // elements in between
+    let prefix = left
+        .iter()
+        .zip(right)
+        .take_while(|(l, r)| same(l, r, options))
+        .count();
+    let suffix = left[prefix..]
+        .iter()
+        .rev()
+        .zip(right[prefix..].iter().rev())
+        .take_while(|(l, r)| same(l, r, options))
+        .count();
+    let middle_left = &left[prefix..left.len() - suffix];
+    let middle_right = &right[prefix..right.len() - suffix];
+
+    let (n, m) = (middle_left.len(), middle_right.len());
+    let mut equal = vec![false; n * m];
+    for i in 0..n {
+        for j in 0..m {
+            equal[i * m + j] = same(&middle_left[i], &middle_right[j], options);
+        }
+    }
+
+    // lengths[i][j] = length of the LCS of left[i..] and right[j..]
+    let mut lengths = vec![0usize; (n + 1) * (m + 1)];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i * (m + 1) + j] = if equal[i * m + j] {
+                1 + lengths[(i + 1) * (m + 1) + j + 1]
+            } else {
+                lengths[(i + 1) * (m + 1) + j].max(lengths[i * (m + 1) + j + 1])
+            };
+        }
+    }
+
+    // Walk the table, collecting the unmatched runs between two matches.
+    // Runs are paired up positionally so an edited element is reported as
+    // nested changes rather than as a removal plus an addition.
+    let (mut i, mut j) = (0, 0);
+    let mut gap_left = Vec::new();
+    let mut gap_right = Vec::new();
+    loop {
+        let at_end = i == n || j == m;
+        if at_end || equal[i * m + j] {
+            if at_end {
+                gap_left.extend(prefix + i..prefix + n);
+                gap_right.extend(prefix + j..prefix + m);
+            }
+            flush_gap(path, left, right, &gap_left, &gap_right, options, out);
+            gap_left.clear();
+            gap_right.clear();
+            if at_end {
+                break;
+            }
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * (m + 1) + j] >= lengths[i * (m + 1) + j + 1] {
+            gap_left.push(prefix + i);
+            i += 1;
+        } else {
+            gap_right.push(prefix + j);
+            j += 1;
+        }
+    }
+}
+
+fn flush_gap<'a>(
+    path: &str,
+    left: &'a [Json],
+    right: &'a [Json],
+    gap_left: &[usize],
+    gap_right: &[usize],
+    options: &DiffOptions,
+    out: &mut Vec<Change<'a>>,
+) {
+    for k in 0..gap_left.len().max(gap_right.len()) {
+        match (gap_left.get(k), gap_right.get(k)) {
+            (Some(&i), Some(&j)) => walk(
+                &pointer::push(path, &i.to_string()),
+                &left[i],
+                &right[j],
+                options,
+                out,
+            ),
+            (Some(&i), None) => out.push(Change::Removed {
+                path: pointer::push(path, &i.to_string()),
+                value: &left[i],
+            }),
+            (None, Some(&j)) => out.push(Change::Added {
+                path: pointer::push(path, &j.to_string()),
+                value: &right[j],
+            }),
+            (None, None) => unreachable!(),
+        }
+    }
+}
+
+fn diff_by_key<'a>(
+    path: &str,
+    left: &'a [Json],
+    right: &'a [Json],
+    field: &str,
+    options: &DiffOptions,
+    out: &mut Vec<Change<'a>>,
+) {
+    let (Some(left_keys), Some(right_keys)) = (keys_of(left, field), keys_of(right, field)) else {
+        return diff_by_index(path, left, right, options, out);
+    };
+
+    for (i, key) in left_keys.iter().enumerate() {
+        let child = pointer::push(path, &i.to_string());
+        match right_keys.iter().position(|k| k == key) {
+            Some(j) => walk(&child, &left[i], &right[j], options, out),
+            None => out.push(Change::Removed {
+                path: child,
+                value: &left[i],
+            }),
+        }
+    }
+    for (j, key) in right_keys.iter().enumerate() {
+        if !left_keys.contains(key) {
+            out.push(Change::Added {
+                path: pointer::push(path, &j.to_string()),
+                value: &right[j],
+            });
+        }
+    }
+}
+
+/// The text of `field` in each element, or `None` if any element is not an
+/// object with that field or two elements share a value.
+fn keys_of(elements: &[Json], field: &str) -> Option<Vec<String>> {
+    let mut keys: Vec<String> = Vec::with_capacity(elements.len());
+    for element in elements {
+        let Json::Object(map) = element else {
+            return None;
+        };
+        let key = map.get(field)?.to_string();
+        if keys.contains(&key) {
+            return None;
+        }
+        keys.push(key);
+    }
+    Some(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    fn lines(left: &Json, right: &Json, options: &DiffOptions) -> Vec<String> {
+        diff(left, right, options)
+            .iter()
+            .map(Change::to_string)
+            .collect()
+    }
+
+    fn with_arrays(arrays: ArrayMatch) -> DiffOptions {
+        DiffOptions {
+            arrays,
+            ..DiffOptions::default()
+        }
+    }
+
+    #[test]
+    fn equal_documents_have_no_changes() {
+        let doc = json!({"a": [1, {"b": null}], "c": "x"});
+        assert!(diff(&doc, &doc.clone(), &DiffOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn object_members_come_in_document_order() {
+        let left = json!({"z": 1, "a": 2, "m": 3});
+        let right = json!({"z": 1, "m": 4, "b": 5, "a": 2});
+        assert_eq!(
+            lines(&left, &right, &DiffOptions::default()),
+            ["changed /m: 3 -> 4", "added /b: 5"]
+        );
+    }
+
+    #[test]
+    fn arrays_by_index_compare_positions() {
+        let left = json!([1, 2, 3]);
+        let right = json!([0, 1, 2, 3]);
+        assert_eq!(
+            lines(&left, &right, &with_arrays(ArrayMatch::Index)),
+            [
+                "changed /0: 1 -> 0",
+                "changed /1: 2 -> 1",
+                "changed /2: 3 -> 2",
+                "added /3: 3",
+            ]
+        );
+    }
+
+    #[test]
+    fn arrays_by_lcs_report_an_insertion_once() {
+        let left = json!([1, 2, 3]);
+        let right = json!([0, 1, 2, 3]);
+        assert_eq!(
+            lines(&left, &right, &with_arrays(ArrayMatch::Lcs)),
+            ["added /0: 0"]
+        );
+
+        let left = json!([1, 2, 3, 4]);
+        let right = json!([1, 4]);
+        assert_eq!(
+            lines(&left, &right, &with_arrays(ArrayMatch::Lcs)),
+            ["removed /1: 2", "removed /2: 3"]
+        );
+    }
+
+    #[test]
+    fn arrays_by_lcs_pair_up_edited_elements() {
+        let left = json!([1, {"v": 1}, 3]);
+        let right = json!([1, {"v": 2}, 3]);
+        assert_eq!(
+            lines(&left, &right, &with_arrays(ArrayMatch::Lcs)),
+            ["changed /1/v: 1 -> 2"]
+        );
+    }
+
+    #[test]
+    fn arrays_by_lcs_handle_long_arrays_with_common_ends() {
+        let element = |i: usize| json!({"id": (i as f64), "tags": ["a", "b"]});
+        let left = Json::Array((0..5_000).map(element).collect());
+        let mut elements: Vec<Json> = (0..5_000).map(element).collect();
+        elements.insert(2_500, json!("new"));
+        elements[2_600] = json!({"id": 2_599, "tags": ["a", "c"]});
+        let right = Json::Array(elements);
+        assert_eq!(
+            lines(&left, &right, &with_arrays(ArrayMatch::Lcs)),
+            [
+                r#"added /2500: "new""#,
+                r#"changed /2599/tags/1: "b" -> "c""#
+            ]
+        );
+    }
+
+    #[test]
+    fn arrays_by_lcs_match_elements_the_way_diff_compares_them() {
+        // within the tolerance, and with nested arrays paired by key
+        let options = DiffOptions {
+            arrays: ArrayMatch::Key("id".to_string()),
+            tolerance: 0.01,
+        };
+        let left = json!([{"id": 1, "v": 1.0}, {"id": 2, "v": 2.0}]);
+        let right = json!([{"id": 2, "v": 2.001}, {"id": 1, "v": 1.0}]);
+        assert!(diff(&left, &right, &options).is_empty());
+
+        let options = DiffOptions {
+            arrays: ArrayMatch::Lcs,
+            tolerance: 0.01,
+        };
+        let left = json!([0, [1.0, {"a": null}], 9]);
+        let right = json!([[1.001, {"a": null}], 9, 10]);
+        assert_eq!(
+            lines(&left, &right, &options),
+            ["removed /0: 0", "added /2: 10"]
+        );
+    }
+
+    #[test]
+    fn arrays_by_key_follow_moved_objects() {
+        let left = json!([{"id": 1, "v": "a"}, {"id": 2, "v": "b"}, {"id": 3, "v": "c"}]);
+        let right = json!([{"id": 2, "v": "B"}, {"id": 1, "v": "a"}, {"id": 4, "v": "d"}]);
+        assert_eq!(
+            lines(
+                &left,
+                &right,
+                &with_arrays(ArrayMatch::Key("id".to_string()))
+            ),
+            [
+                r#"changed /1/v: "b" -> "B""#,
+                r#"removed /2: {"id":3,"v":"c"}"#,
+                r#"added /2: {"id":4,"v":"d"}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn arrays_by_key_fall_back_to_index() {
+        // a duplicate key, and an element that is not an object
+        let options = with_arrays(ArrayMatch::Key("id".to_string()));
+        let left = json!([{"id": 1}, {"id": 1}]);
+        let right = json!([{"id": 1}, {"id": 2}]);
+        assert_eq!(lines(&left, &right, &options), ["changed /1/id: 1 -> 2"]);
+
+        let left = json!([{"id": 1}, 2]);
+        let right = json!([{"id": 1}, 3]);
+        assert_eq!(lines(&left, &right, &options), ["changed /1: 2 -> 3"]);
+    }
+
+    #[test]
+    fn numbers_within_tolerance_are_equal() {
+        let options = DiffOptions {
+            tolerance: 0.01,
+            ..DiffOptions::default()
+        };
+        let left = json!({"a": 1.0, "b": 2.0});
+        let right = json!({"a": 1.005, "b": 2.5});
+        assert_eq!(lines(&left, &right, &options), ["changed /b: 2 -> 2.5"]);
+        assert_eq!(
+            lines(&left, &right, &DiffOptions::default()),
+            ["changed /a: 1 -> 1.005", "changed /b: 2 -> 2.5"]
+        );
+    }
+
+    #[test]
+    fn type_changes_and_the_root() {
+        assert_eq!(
+            lines(&json!(1), &json!("1"), &DiffOptions::default()),
+            [r#"changed <root>: 1 -> "1""#]
+        );
+        assert_eq!(
+            lines(&json!({"a~b/c": 1}), &json!({}), &DiffOptions::default()),
+            ["removed /a~0b~1c: 1"]
+        );
+    }
+
+    #[test]
+    fn report_renders_one_hunk_per_change() {
+        let left = json!({"a": 1, "b": true});
+        let right = json!({"a": 2, "c": null});
+        let changes = diff(&left, &right, &DiffOptions::default());
+        assert_eq!(
+            report(&changes, false),
+            "@@ /a @@\n-1\n+2\n@@ /b @@\n-true\n@@ /c @@\n+null\n"
+        );
+    }
+
+    #[test]
+    fn colored_report_wraps_lines_in_ansi_codes() {
+        let left = json!({"a": 1});
+        let right = json!({"a": 2});
+        let changes = diff(&left, &right, &DiffOptions::default());
+        assert_eq!(
+            report(&changes, true),
+            "\x1b[36m@@ /a @@\x1b[0m\n\x1b[31m-1\x1b[0m\n\x1b[32m+2\x1b[0m\n"
+        );
+    }
+}
@@ -0,0 +1,357 @@
+//! Content hashes of `Json` values.
+//!
+//! A fingerprint is the digest of a value's canonical form (see `jcs`), so
+//! it ignores key order and whitespace in the source. SHA-256 is built in;
+//! any other digest can be plugged in through the `Digest` trait.
+//!
+//! `Merkle` hashes every subtree separately, a node's hash covering the
+//! hashes of its children, so two documents can be compared by walking
+//! down only the branches whose hashes differ.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::jcs::{CanonicalError, to_canonical_string};
+use crate::parsen::Json;
+use crate::pointer;
+
+/// A hash function fed incrementally.
+pub trait Digest: Default {
+    fn update(&mut self, data: &[u8]);
+    fn finish(self) -> Vec<u8>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Fingerprint(pub Vec<u8>);
+
+impl Fingerprint {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Lowercase hex.
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// SHA-256 of the canonical form of `json`.
+pub fn fingerprint(json: &Json) -> Result<Fingerprint, CanonicalError> {
+    fingerprint_with::<Sha256>(json)
+}
+
+pub fn fingerprint_with<D: Digest>(json: &Json) -> Result<Fingerprint, CanonicalError> {
+    let mut digest = D::default();
+    digest.update(to_canonical_string(json)?.as_bytes());
+    Ok(Fingerprint(digest.finish()))
+}
+
+/// Hashes of every subtree of a document, keyed by JSON Pointer.
+///
+/// Scalars hash their canonical text. An array hashes `[` followed by its
+/// elements' hashes, an object hashes `{` followed by each key's canonical
+/// text and its value's hash, in canonical key order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Merkle {
+    hashes: BTreeMap<String, Fingerprint>,
+}
+
+impl Merkle {
+    pub fn new(json: &Json) -> Result<Merkle, CanonicalError> {
+        Merkle::with::<Sha256>(json)
+    }
+
+    pub fn with<D: Digest>(json: &Json) -> Result<Merkle, CanonicalError> {
+        let mut hashes = BTreeMap::new();
+        hash_node::<D>("", json, &mut hashes)?;
+        Ok(Merkle { hashes })
+    }
+
+    pub fn root(&self) -> &Fingerprint {
+        &self.hashes[""]
+    }
+
+    /// Hash of the subtree at `pointer`.
+    pub fn get(&self, pointer: &str) -> Option<&Fingerprint> {
+        self.hashes.get(pointer)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Fingerprint)> {
+        self.hashes.iter()
+    }
+
+    /// Pointers whose subtree differs between the two documents, including
+    /// the ancestors of every change. Equal subtrees are not descended into.
+    pub fn changed_paths(&self, other: &Merkle) -> Vec<String> {
+        let mut changed = Vec::new();
+        self.compare("", other, &mut changed);
+        changed
+    }
+
+    fn compare(&self, at: &str, other: &Merkle, changed: &mut Vec<String>) {
+        if self.hashes.get(at) == other.hashes.get(at) {
+            return;
+        }
+        changed.push(at.to_string());
+        let mut children: Vec<&String> = self.children(at).chain(other.children(at)).collect();
+        children.sort();
+        children.dedup();
+        for child in children {
+            self.compare(child, other, changed);
+        }
+    }
+
+    fn children<'a>(&'a self, at: &'a str) -> impl Iterator<Item = &'a String> {
+        let prefix = format!("{}/", at);
+        self.hashes
+            .range(prefix.clone()..)
+            .map(|(key, _)| key)
+            .take_while(move |key| key.starts_with(&prefix))
+            .filter(move |key| !key[at.len() + 1..].contains('/'))
+    }
+}
+
+fn hash_node<D: Digest>(
+    path: &str,
+    json: &Json,
+    hashes: &mut BTreeMap<String, Fingerprint>,
+) -> Result<Fingerprint, CanonicalError> {
+    let mut digest = D::default();
+    match json {
+        Json::Array(vec) => {
+            digest.update(b"[");
+            for (i, json) in vec.iter().enumerate() {
+                let child = hash_node::<D>(&pointer::push(path, &i.to_string()), json, hashes)?;
+                digest.update(child.as_bytes());
+            }
+        }
+        Json::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort_by(|a, b| a.encode_utf16().cmp(b.encode_utf16()));
+            digest.update(b"{");
+            for key in keys {
+                let child = hash_node::<D>(&pointer::push(path, key), &map[key], hashes)?;
+                digest.update(to_canonical_string(&Json::Strings(key.clone()))?.as_bytes());
+                digest.update(child.as_bytes());
+            }
+        }
+        _ => digest.update(to_canonical_string(json)?.as_bytes()),
+    }
+    let hash = Fingerprint(digest.finish());
+    hashes.insert(path.to_string(), hash.clone());
+    Ok(hash)
+}
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// SHA-256 (FIPS 180-4).
+#[derive(Debug, Clone)]
+pub struct Sha256 {
+    state: [u32; 8],
+    block: [u8; 64],
+    filled: usize,
+    length: u64,
+}
+
+impl Default for Sha256 {
+    fn default() -> Self {
+        Self {
+            state: [
+                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+                0x5be0cd19,
+            ],
+            block: [0; 64],
+            filled: 0,
+            length: 0,
+        }
+    }
+}
+
+impl Sha256 {
+    fn compress(&mut self) {
+        let mut w = [0u32; 64];
+        for (i, word) in self.block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (word, add) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *word = word.wrapping_add(add);
+        }
+        self.filled = 0;
+    }
+}
+
+impl Digest for Sha256 {
+    fn update(&mut self, data: &[u8]) {
+        self.length += data.len() as u64;
+        for &byte in data {
+            self.block[self.filled] = byte;
+            self.filled += 1;
+            if self.filled == 64 {
+                self.compress();
+            }
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        let bits = self.length * 8;
+        self.block[self.filled] = 0x80;
+        self.filled += 1;
+        if self.filled > 56 {
+            self.block[self.filled..].fill(0);
+            self.compress();
+        }
+        self.block[self.filled..56].fill(0);
+        self.block[56..].copy_from_slice(&bits.to_be_bytes());
+        self.compress();
+        self.state
+            .iter()
+            .flat_map(|word| word.to_be_bytes())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+    use crate::parsen::Parser;
+
+    fn sha256(data: &[u8]) -> String {
+        let mut digest = Sha256::default();
+        digest.update(data);
+        Fingerprint(digest.finish()).to_string()
+    }
+
+    fn parse(text: &str) -> Json {
+        Parser::from_string(text.to_string()).parse()
+    }
+
+    /// FIPS 180-4 examples, as published with the NIST test vectors.
+    #[test]
+    fn sha256_vectors() {
+        assert_eq!(
+            sha256(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            sha256(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+        assert_eq!(
+            sha256(&vec![b'a'; 1_000_000]),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+
+    #[test]
+    fn sha256_is_the_same_fed_in_pieces() {
+        let data: Vec<u8> = (0..200u8).collect();
+        let mut digest = Sha256::default();
+        for piece in data.chunks(7) {
+            digest.update(piece);
+        }
+        assert_eq!(Fingerprint(digest.finish()).to_string(), sha256(&data));
+    }
+
+    #[test]
+    fn equal_documents_get_equal_digests() {
+        let a = parse(r#"{"b": [1, 2.0, {"c": null}], "a": "x"}"#);
+        let b = parse(r#"{ "a" : "x", "b" : [1.0, 2, {"c": null}] }"#);
+        assert_eq!(fingerprint(&a).unwrap(), fingerprint(&b).unwrap());
+        assert_eq!(
+            fingerprint(&a).unwrap().to_string(),
+            sha256(br#"{"a":"x","b":[1,2,{"c":null}]}"#)
+        );
+        assert_ne!(
+            fingerprint(&a).unwrap(),
+            fingerprint(&json!({"a": "y", "b": [1, 2, {"c": null}]})).unwrap()
+        );
+    }
+
+    #[test]
+    fn merkle_hashes_ignore_key_order_and_number_spelling() {
+        let a = Merkle::new(&parse(r#"{"x": {"p": 1, "q": 1e2}, "y": [0.5]}"#)).unwrap();
+        let b = Merkle::new(&parse(r#"{"y": [5e-1], "x": {"q": 100.0, "p": 1.0}}"#)).unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a.root(), b.root());
+        assert!(a.changed_paths(&b).is_empty());
+        let pointers: Vec<&String> = a.iter().map(|(pointer, _)| pointer).collect();
+        assert_eq!(pointers, ["", "/x", "/x/p", "/x/q", "/y", "/y/0"]);
+    }
+
+    #[test]
+    fn merkle_leaves_hash_their_canonical_text() {
+        let merkle = Merkle::new(&json!({"s": "a", "n": 1})).unwrap();
+        assert_eq!(merkle.get("/s").unwrap().to_string(), sha256(br#""a""#));
+        assert_eq!(merkle.get("/n").unwrap().to_string(), sha256(b"1"));
+        assert_eq!(merkle.get("/missing"), None);
+    }
+
+    #[test]
+    fn changed_paths_are_the_edits_and_their_ancestors() {
+        let before = json!({"a": {"b": 1, "c": 2}, "d": [1, 2, 3], "e": true});
+        let after = json!({"a": {"b": 1, "c": 20}, "d": [1, 2, 3], "e": false});
+        let changed = Merkle::new(&before)
+            .unwrap()
+            .changed_paths(&Merkle::new(&after).unwrap());
+        assert_eq!(changed, ["", "/a", "/a/c", "/e"]);
+    }
+
+    #[test]
+    fn changed_paths_include_added_and_removed_members() {
+        let before = json!({"a": 1, "list": [1, 2]});
+        let after = json!({"b": 1, "list": [1, 2, 3]});
+        let changed = Merkle::new(&before)
+            .unwrap()
+            .changed_paths(&Merkle::new(&after).unwrap());
+        assert_eq!(changed, ["", "/a", "/b", "/list", "/list/2"]);
+    }
+}
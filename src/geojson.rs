@@ -0,0 +1,873 @@
+//! Typed GeoJSON (RFC 7946) read from and written to `Json`.
+//!
+//! `FromJson` checks the structure as it reads: the `type` of every
+//! object, the nesting depth of `coordinates`, positions with two or three
+//! finite numbers inside the longitude and latitude ranges, line strings of
+//! at least two positions and polygon rings that are closed and have at
+//! least four. Members the RFC does not define are kept as foreign members
+//! and written back out.
+//!
+//! ```
+//! use json_parser::convert::{FromJson, ToJson};
+//! use json_parser::geojson::{GeoJson, Position, Value};
+//! use json_parser::json;
+//!
+//! let json = json!({
+//!     "type": "FeatureCollection",
+//!     "features": [
+//!         {
+//!             "type": "Feature",
+//!             "geometry": {"type": "Point", "coordinates": [-122.3959, 37.7668]},
+//!             "properties": {"City": "SAN FRANCISCO"}
+//!         },
+//!         {
+//!             "type": "Feature",
+//!             "geometry": {"type": "Point", "coordinates": [-122.02602, 37.371991]},
+//!             "properties": {"City": "SUNNYVALE"}
+//!         }
+//!     ]
+//! });
+//! let geojson = GeoJson::from_json(&json)?;
+//!
+//! let GeoJson::FeatureCollection(collection) = &geojson else { unreachable!() };
+//! let geometry = collection.features[0].geometry.as_ref().unwrap();
+//! assert_eq!(geometry.value, Value::Point(Position::new(-122.3959, 37.7668)));
+//!
+//! let bbox = geojson.compute_bbox().unwrap();
+//! assert_eq!(bbox.to_json(), json!([-122.3959, 37.371991, -122.02602, 37.7668]));
+//! assert_eq!(geojson.to_json(), json);
+//!
+//! let open = json!({"type": "Polygon", "coordinates": [[[0, 0], [1, 0], [1, 1]]]});
+//! let err = GeoJson::from_json(&open).unwrap_err();
+//! assert_eq!(err.to_string(), ".coordinates.[0]: expected a linear ring of at least 4 positions");
+//! # Ok::<(), json_parser::convert::FromJsonError>(())
+//! ```
+
+use crate::convert::{
+    FromJson, FromJsonError, ToJson, missing, object_entries, optional, required,
+};
+use crate::map::Map;
+use crate::parsen::Json;
+
+/// Longitude, latitude and optionally altitude, in WGS 84 degrees and
+/// meters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub lon: f64,
+    pub lat: f64,
+    pub alt: Option<f64>,
+}
+
+impl Position {
+    pub fn new(lon: f64, lat: f64) -> Position {
+        Position {
+            lon,
+            lat,
+            alt: None,
+        }
+    }
+
+    pub fn with_alt(lon: f64, lat: f64, alt: f64) -> Position {
+        Position {
+            lon,
+            lat,
+            alt: Some(alt),
+        }
+    }
+}
+
+impl ToJson for Position {
+    fn to_json(&self) -> Json {
+        let mut vec = vec![Json::Number(self.lon), Json::Number(self.lat)];
+        vec.extend(self.alt.map(Json::Number));
+        Json::Array(vec)
+    }
+}
+
+impl FromJson for Position {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        let numbers = Vec::<f64>::from_json(json)?;
+        if !(2..=3).contains(&numbers.len()) {
+            return Err(FromJsonError::custom(
+                "expected a position of 2 or 3 numbers",
+            ));
+        }
+        let (lon, lat) = (numbers[0], numbers[1]);
+        if !(-180.0..=180.0).contains(&lon) {
+            return Err(
+                FromJsonError::custom(format!("longitude {} is outside [-180, 180]", lon))
+                    .at_index(0),
+            );
+        }
+        if !(-90.0..=90.0).contains(&lat) {
+            return Err(
+                FromJsonError::custom(format!("latitude {} is outside [-90, 90]", lat)).at_index(1),
+            );
+        }
+        match numbers.get(2) {
+            Some(alt) if !alt.is_finite() => {
+                Err(FromJsonError::custom("expected a finite altitude").at_index(2))
+            }
+            alt => Ok(Position {
+                lon,
+                lat,
+                alt: alt.copied(),
+            }),
+        }
+    }
+}
+
+/// A bounding box, from its south-west corner to its north-east one. The
+/// corners have an altitude when the box is three-dimensional.
+///
+/// A box crossing the antimeridian has `min.lon > max.lon`; `compute_bbox`
+/// never makes one, but one read from a document is kept as it is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bbox {
+    pub min: Position,
+    pub max: Position,
+}
+
+impl Bbox {
+    /// The smallest box holding both boxes, three-dimensional only if both
+    /// are.
+    pub fn union(&self, other: &Bbox) -> Bbox {
+        let mut bounds = Bounds::default();
+        for position in [self.min, self.max, other.min, other.max] {
+            bounds.add(&position);
+        }
+        bounds.bbox().unwrap_or(*self)
+    }
+
+    pub fn contains(&self, position: &Position) -> bool {
+        let lon = if self.min.lon <= self.max.lon {
+            (self.min.lon..=self.max.lon).contains(&position.lon)
+        } else {
+            position.lon >= self.min.lon || position.lon <= self.max.lon
+        };
+        let alt = match (self.min.alt, self.max.alt, position.alt) {
+            (Some(low), Some(high), Some(alt)) => (low..=high).contains(&alt),
+            _ => true,
+        };
+        lon && (self.min.lat..=self.max.lat).contains(&position.lat) && alt
+    }
+}
+
+/// `[west, south, east, north]`, or with the altitudes
+/// `[west, south, low, east, north, high]`.
+impl ToJson for Bbox {
+    fn to_json(&self) -> Json {
+        let Json::Array(mut vec) = self.min.to_json() else {
+            unreachable!("a position is an array");
+        };
+        if let Json::Array(max) = self.max.to_json() {
+            vec.extend(max);
+        }
+        Json::Array(vec)
+    }
+}
+
+impl FromJson for Bbox {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        let numbers = Vec::<f64>::from_json(json)?;
+        let (min, max) = match numbers[..] {
+            [west, south, east, north] => (Position::new(west, south), Position::new(east, north)),
+            [west, south, low, east, north, high] => (
+                Position::with_alt(west, south, low),
+                Position::with_alt(east, north, high),
+            ),
+            _ => return Err(FromJsonError::custom("expected a bbox of 4 or 6 numbers")),
+        };
+        if min.lat > max.lat || min.alt > max.alt {
+            return Err(FromJsonError::custom(
+                "expected the south-west corner of the bbox first",
+            ));
+        }
+        Ok(Bbox { min, max })
+    }
+}
+
+/// The coordinates of a geometry, by type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Point(Position),
+    MultiPoint(Vec<Position>),
+    /// At least two positions.
+    LineString(Vec<Position>),
+    MultiLineString(Vec<Vec<Position>>),
+    /// The exterior ring, then any holes. Each ring is closed, with its
+    /// last position equal to its first, and has at least four.
+    Polygon(Vec<Vec<Position>>),
+    MultiPolygon(Vec<Vec<Vec<Position>>>),
+    GeometryCollection(Vec<Geometry>),
+}
+
+impl Value {
+    /// The `type` member of the geometry.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Point(_) => "Point",
+            Value::MultiPoint(_) => "MultiPoint",
+            Value::LineString(_) => "LineString",
+            Value::MultiLineString(_) => "MultiLineString",
+            Value::Polygon(_) => "Polygon",
+            Value::MultiPolygon(_) => "MultiPolygon",
+            Value::GeometryCollection(_) => "GeometryCollection",
+        }
+    }
+
+    fn positions(&self, visit: &mut dyn FnMut(&Position)) {
+        match self {
+            Value::Point(position) => visit(position),
+            Value::MultiPoint(positions) | Value::LineString(positions) => {
+                positions.iter().for_each(visit)
+            }
+            Value::MultiLineString(lines) | Value::Polygon(lines) => {
+                lines.iter().flatten().for_each(visit)
+            }
+            Value::MultiPolygon(polygons) => polygons.iter().flatten().flatten().for_each(visit),
+            Value::GeometryCollection(geometries) => {
+                for geometry in geometries {
+                    geometry.value.positions(visit);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Geometry {
+    pub value: Value,
+    /// The `bbox` member as written, which `compute_bbox` does not change.
+    pub bbox: Option<Bbox>,
+    /// Members the RFC does not define.
+    pub foreign: Map,
+}
+
+impl Geometry {
+    pub fn new(value: Value) -> Geometry {
+        Geometry {
+            value,
+            bbox: None,
+            foreign: Map::new(),
+        }
+    }
+
+    /// The box around every position, or `None` if there are none.
+    pub fn compute_bbox(&self) -> Option<Bbox> {
+        let mut bounds = Bounds::default();
+        self.value.positions(&mut |position| bounds.add(position));
+        bounds.bbox()
+    }
+}
+
+impl From<Value> for Geometry {
+    fn from(value: Value) -> Geometry {
+        Geometry::new(value)
+    }
+}
+
+impl ToJson for Geometry {
+    fn to_json(&self) -> Json {
+        let (key, value) = match &self.value {
+            Value::Point(position) => ("coordinates", position.to_json()),
+            Value::MultiPoint(positions) | Value::LineString(positions) => {
+                ("coordinates", positions.to_json())
+            }
+            Value::MultiLineString(lines) | Value::Polygon(lines) => {
+                ("coordinates", lines.to_json())
+            }
+            Value::MultiPolygon(polygons) => ("coordinates", polygons.to_json()),
+            Value::GeometryCollection(geometries) => ("geometries", geometries.to_json()),
+        };
+        object(
+            self.value.type_name(),
+            vec![
+                ("bbox", self.bbox.map(|bbox| bbox.to_json())),
+                (key, Some(value)),
+            ],
+            &self.foreign,
+        )
+    }
+}
+
+impl FromJson for Geometry {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        let map = object_entries(json)?;
+        let kind = type_of(map)?;
+        let value = match kind {
+            "Point" => Value::Point(coordinates(map)?),
+            "MultiPoint" => Value::MultiPoint(coordinates(map)?),
+            "LineString" => {
+                let line: Vec<Position> = coordinates(map)?;
+                check_line(&line).map_err(|err| err.at_key("coordinates"))?;
+                Value::LineString(line)
+            }
+            "MultiLineString" => {
+                let lines: Vec<Vec<Position>> = coordinates(map)?;
+                for (i, line) in lines.iter().enumerate() {
+                    check_line(line).map_err(|err| err.at_index(i).at_key("coordinates"))?;
+                }
+                Value::MultiLineString(lines)
+            }
+            "Polygon" => {
+                let rings: Vec<Vec<Position>> = coordinates(map)?;
+                check_polygon(&rings).map_err(|err| err.at_key("coordinates"))?;
+                Value::Polygon(rings)
+            }
+            "MultiPolygon" => {
+                let polygons: Vec<Vec<Vec<Position>>> = coordinates(map)?;
+                for (i, rings) in polygons.iter().enumerate() {
+                    check_polygon(rings).map_err(|err| err.at_index(i).at_key("coordinates"))?;
+                }
+                Value::MultiPolygon(polygons)
+            }
+            "GeometryCollection" => Value::GeometryCollection(required(map, "geometries")?),
+            _ => return Err(unknown_type(kind, "a geometry")),
+        };
+        let known: &[&str] = match value {
+            Value::GeometryCollection(_) => &["type", "bbox", "geometries"],
+            _ => &["type", "bbox", "coordinates"],
+        };
+        Ok(Geometry {
+            value,
+            bbox: optional(map, "bbox")?,
+            foreign: foreign(map, known),
+        })
+    }
+}
+
+/// The `id` of a feature.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeatureId {
+    String(String),
+    Number(f64),
+}
+
+impl ToJson for FeatureId {
+    fn to_json(&self) -> Json {
+        match self {
+            FeatureId::String(st) => Json::Strings(st.clone()),
+            FeatureId::Number(num) => Json::Number(*num),
+        }
+    }
+}
+
+impl FromJson for FeatureId {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        match json {
+            Json::Strings(st) => Ok(FeatureId::String(st.clone())),
+            Json::Number(num) => Ok(FeatureId::Number(*num)),
+            _ => Err(FromJsonError::expected("string or number", json)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Feature {
+    pub id: Option<FeatureId>,
+    /// `None` for an unlocated feature, written as `null`.
+    pub geometry: Option<Geometry>,
+    /// `None` is written as `null`.
+    pub properties: Option<Map>,
+    pub bbox: Option<Bbox>,
+    pub foreign: Map,
+}
+
+impl Feature {
+    pub fn compute_bbox(&self) -> Option<Bbox> {
+        self.geometry.as_ref()?.compute_bbox()
+    }
+}
+
+impl ToJson for Feature {
+    fn to_json(&self) -> Json {
+        object(
+            "Feature",
+            vec![
+                ("id", self.id.as_ref().map(ToJson::to_json)),
+                ("bbox", self.bbox.map(|bbox| bbox.to_json())),
+                ("geometry", Some(self.geometry.to_json())),
+                ("properties", Some(self.properties.to_json())),
+            ],
+            &self.foreign,
+        )
+    }
+}
+
+impl FromJson for Feature {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        let map = object_entries(json)?;
+        match type_of(map)? {
+            "Feature" => {}
+            kind => return Err(unknown_type(kind, "a Feature")),
+        }
+        Ok(Feature {
+            id: optional(map, "id")?,
+            geometry: required(map, "geometry")?,
+            properties: required(map, "properties")?,
+            bbox: optional(map, "bbox")?,
+            foreign: foreign(map, &["type", "id", "bbox", "geometry", "properties"]),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FeatureCollection {
+    pub features: Vec<Feature>,
+    pub bbox: Option<Bbox>,
+    pub foreign: Map,
+}
+
+impl FeatureCollection {
+    pub fn compute_bbox(&self) -> Option<Bbox> {
+        self.features
+            .iter()
+            .filter_map(Feature::compute_bbox)
+            .reduce(|a, b| a.union(&b))
+    }
+}
+
+impl ToJson for FeatureCollection {
+    fn to_json(&self) -> Json {
+        object(
+            "FeatureCollection",
+            vec![
+                ("bbox", self.bbox.map(|bbox| bbox.to_json())),
+                ("features", Some(self.features.to_json())),
+            ],
+            &self.foreign,
+        )
+    }
+}
+
+impl FromJson for FeatureCollection {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        let map = object_entries(json)?;
+        match type_of(map)? {
+            "FeatureCollection" => {}
+            kind => return Err(unknown_type(kind, "a FeatureCollection")),
+        }
+        Ok(FeatureCollection {
+            features: required(map, "features")?,
+            bbox: optional(map, "bbox")?,
+            foreign: foreign(map, &["type", "bbox", "features"]),
+        })
+    }
+}
+
+/// Any GeoJSON document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoJson {
+    Geometry(Geometry),
+    Feature(Box<Feature>),
+    FeatureCollection(FeatureCollection),
+}
+
+impl GeoJson {
+    pub fn compute_bbox(&self) -> Option<Bbox> {
+        match self {
+            GeoJson::Geometry(geometry) => geometry.compute_bbox(),
+            GeoJson::Feature(feature) => feature.compute_bbox(),
+            GeoJson::FeatureCollection(collection) => collection.compute_bbox(),
+        }
+    }
+}
+
+impl ToJson for GeoJson {
+    fn to_json(&self) -> Json {
+        match self {
+            GeoJson::Geometry(geometry) => geometry.to_json(),
+            GeoJson::Feature(feature) => feature.to_json(),
+            GeoJson::FeatureCollection(collection) => collection.to_json(),
+        }
+    }
+}
+
+impl FromJson for GeoJson {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        match type_of(object_entries(json)?)? {
+            "Feature" => {
+                Feature::from_json(json).map(|feature| GeoJson::Feature(Box::new(feature)))
+            }
+            "FeatureCollection" => {
+                FeatureCollection::from_json(json).map(GeoJson::FeatureCollection)
+            }
+            _ => Geometry::from_json(json).map(GeoJson::Geometry),
+        }
+    }
+}
+
+/// The running extent of a set of positions.
+#[derive(Default)]
+struct Bounds {
+    bbox: Option<Bbox>,
+    /// Whether every position so far had an altitude.
+    altitudes: bool,
+}
+
+impl Bounds {
+    fn add(&mut self, position: &Position) {
+        let Some(bbox) = &mut self.bbox else {
+            self.bbox = Some(Bbox {
+                min: *position,
+                max: *position,
+            });
+            self.altitudes = position.alt.is_some();
+            return;
+        };
+        bbox.min.lon = bbox.min.lon.min(position.lon);
+        bbox.min.lat = bbox.min.lat.min(position.lat);
+        bbox.max.lon = bbox.max.lon.max(position.lon);
+        bbox.max.lat = bbox.max.lat.max(position.lat);
+        self.altitudes &= position.alt.is_some();
+        if let (Some(low), Some(high), Some(alt)) = (bbox.min.alt, bbox.max.alt, position.alt) {
+            bbox.min.alt = Some(low.min(alt));
+            bbox.max.alt = Some(high.max(alt));
+        }
+    }
+
+    fn bbox(&self) -> Option<Bbox> {
+        let mut bbox = self.bbox?;
+        if !self.altitudes {
+            bbox.min.alt = None;
+            bbox.max.alt = None;
+        }
+        Some(bbox)
+    }
+}
+
+/// `{"type": kind, ...members, ...foreign}`, leaving out `None` members.
+fn object(kind: &str, members: Vec<(&str, Option<Json>)>, foreign: &Map) -> Json {
+    let mut map = Map::new();
+    map.insert("type".to_string(), Json::Strings(kind.to_string()));
+    for (key, value) in members {
+        if let Some(value) = value {
+            map.insert(key.to_string(), value);
+        }
+    }
+    for (key, value) in foreign.iter() {
+        map.insert(key.clone(), value.clone());
+    }
+    Json::Object(Box::new(map))
+}
+
+fn type_of(map: &Map) -> Result<&str, FromJsonError> {
+    let kind = map.get("type").ok_or_else(|| missing("type"))?;
+    kind.as_str()
+        .ok_or_else(|| FromJsonError::expected("string", kind).at_key("type"))
+}
+
+fn unknown_type(kind: &str, expected: &str) -> FromJsonError {
+    FromJsonError::custom(format!("expected {}, found type {:?}", expected, kind)).at_key("type")
+}
+
+fn coordinates<T: FromJson>(map: &Map) -> Result<T, FromJsonError> {
+    required(map, "coordinates")
+}
+
+fn foreign(map: &Map, known: &[&str]) -> Map {
+    map.iter()
+        .filter(|(key, _)| !known.contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+fn check_line(line: &[Position]) -> Result<(), FromJsonError> {
+    if line.len() < 2 {
+        return Err(FromJsonError::custom(
+            "expected a line string of at least 2 positions",
+        ));
+    }
+    Ok(())
+}
+
+fn check_polygon(rings: &[Vec<Position>]) -> Result<(), FromJsonError> {
+    for (i, ring) in rings.iter().enumerate() {
+        if ring.len() < 4 {
+            return Err(
+                FromJsonError::custom("expected a linear ring of at least 4 positions").at_index(i),
+            );
+        }
+        if ring.first() != ring.last() {
+            return Err(FromJsonError::custom(
+                "expected a closed linear ring, ending where it starts",
+            )
+            .at_index(i));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    /// Reads `json`, checks the value read, and writes it back unchanged.
+    fn round_trip(json: Json, expected: Value) -> Geometry {
+        let geometry = Geometry::from_json(&json).unwrap();
+        assert_eq!(geometry.value, expected);
+        assert_eq!(geometry.to_json(), json);
+        geometry
+    }
+
+    fn error(json: Json) -> String {
+        GeoJson::from_json(&json).unwrap_err().to_string()
+    }
+
+    fn square() -> Vec<Position> {
+        vec![
+            Position::new(0.0, 0.0),
+            Position::new(1.0, 0.0),
+            Position::new(1.0, 1.0),
+            Position::new(0.0, 0.0),
+        ]
+    }
+
+    #[test]
+    fn every_geometry_type_round_trips() {
+        round_trip(
+            json!({"type": "Point", "coordinates": [100.0, 0.5]}),
+            Value::Point(Position::new(100.0, 0.5)),
+        );
+        round_trip(
+            json!({"type": "Point", "coordinates": [100.0, 0.5, 12]}),
+            Value::Point(Position::with_alt(100.0, 0.5, 12.0)),
+        );
+        round_trip(
+            json!({"type": "MultiPoint", "coordinates": [[100, 0], [101, 1]]}),
+            Value::MultiPoint(vec![Position::new(100.0, 0.0), Position::new(101.0, 1.0)]),
+        );
+        round_trip(
+            json!({"type": "LineString", "coordinates": [[100, 0], [101, 1]]}),
+            Value::LineString(vec![Position::new(100.0, 0.0), Position::new(101.0, 1.0)]),
+        );
+        round_trip(
+            json!({"type": "MultiLineString", "coordinates": [[[0, 0], [1, 1]], [[2, 2], [3, 3]]]}),
+            Value::MultiLineString(vec![
+                vec![Position::new(0.0, 0.0), Position::new(1.0, 1.0)],
+                vec![Position::new(2.0, 2.0), Position::new(3.0, 3.0)],
+            ]),
+        );
+        round_trip(
+            json!({"type": "Polygon", "coordinates": [[[0, 0], [1, 0], [1, 1], [0, 0]]]}),
+            Value::Polygon(vec![square()]),
+        );
+        round_trip(
+            json!({
+                "type": "MultiPolygon",
+                "coordinates": [[[[0, 0], [1, 0], [1, 1], [0, 0]]], [[[0, 0], [1, 0], [1, 1], [0, 0]]]]
+            }),
+            Value::MultiPolygon(vec![vec![square()], vec![square()]]),
+        );
+        round_trip(
+            json!({
+                "type": "GeometryCollection",
+                "geometries": [
+                    {"type": "Point", "coordinates": [100, 0]},
+                    {"type": "LineString", "coordinates": [[101, 0], [102, 1]]}
+                ]
+            }),
+            Value::GeometryCollection(vec![
+                Value::Point(Position::new(100.0, 0.0)).into(),
+                Value::LineString(vec![Position::new(101.0, 0.0), Position::new(102.0, 1.0)])
+                    .into(),
+            ]),
+        );
+    }
+
+    #[test]
+    fn bbox_and_foreign_members_are_kept() {
+        let json = json!({
+            "type": "Point",
+            "bbox": [100, 0, 100, 0],
+            "coordinates": [100, 0],
+            "title": "origin"
+        });
+        let geometry = round_trip(json, Value::Point(Position::new(100.0, 0.0)));
+        assert_eq!(geometry.bbox, geometry.compute_bbox());
+        assert_eq!(geometry.foreign.get("title"), Some(&json!("origin")));
+    }
+
+    #[test]
+    fn a_feature_may_have_no_geometry() {
+        let json = json!({"type": "Feature", "geometry": null, "properties": {"name": "nowhere"}});
+        let GeoJson::Feature(feature) = GeoJson::from_json(&json).unwrap() else {
+            panic!("expected a feature");
+        };
+        assert_eq!(feature.geometry, None);
+        assert_eq!(feature.compute_bbox(), None);
+        assert_eq!(feature.to_json(), json);
+
+        // `geometry` and `properties` are required, if only as `null`
+        let json = json!({"type": "Feature", "properties": null});
+        assert_eq!(error(json), ".: missing field \"geometry\"");
+    }
+
+    #[test]
+    fn features_keep_their_id() {
+        let json = json!({"type": "Feature", "id": 7, "geometry": null, "properties": null});
+        let feature = Feature::from_json(&json).unwrap();
+        assert_eq!(feature.id, Some(FeatureId::Number(7.0)));
+        assert_eq!(feature.to_json(), json);
+
+        let json = json!({"type": "Feature", "id": true, "geometry": null, "properties": null});
+        assert_eq!(error(json), ".id: expected string or number, found boolean");
+    }
+
+    #[test]
+    fn polygon_rings_must_be_closed_with_four_positions() {
+        let json = json!({"type": "Polygon", "coordinates": [[[0, 0], [1, 0], [1, 1], [0, 1]]]});
+        assert_eq!(
+            error(json),
+            ".coordinates.[0]: expected a closed linear ring, ending where it starts"
+        );
+        let json = json!({"type": "Polygon", "coordinates": [[[0, 0], [1, 1], [0, 0]]]});
+        assert_eq!(
+            error(json),
+            ".coordinates.[0]: expected a linear ring of at least 4 positions"
+        );
+        // a hole is checked like the exterior ring
+        let json = json!({
+            "type": "MultiPolygon",
+            "coordinates": [[[[0, 0], [1, 0], [1, 1], [0, 0]], [[0, 0], [1, 1]]]]
+        });
+        assert_eq!(
+            error(json),
+            ".coordinates.[0].[1]: expected a linear ring of at least 4 positions"
+        );
+    }
+
+    #[test]
+    fn line_strings_need_two_positions() {
+        let json = json!({"type": "LineString", "coordinates": [[0, 0]]});
+        assert_eq!(
+            error(json),
+            ".coordinates: expected a line string of at least 2 positions"
+        );
+        let json = json!({"type": "MultiLineString", "coordinates": [[[0, 0], [1, 1]], []]});
+        assert_eq!(
+            error(json),
+            ".coordinates.[1]: expected a line string of at least 2 positions"
+        );
+    }
+
+    #[test]
+    fn positions_are_checked() {
+        let point = |coordinates: Json| json!({"type": "Point", "coordinates": coordinates});
+        assert_eq!(
+            error(point(json!([181, 0]))),
+            ".coordinates.[0]: longitude 181 is outside [-180, 180]"
+        );
+        assert_eq!(
+            error(point(json!([0, -90.5]))),
+            ".coordinates.[1]: latitude -90.5 is outside [-90, 90]"
+        );
+        assert_eq!(
+            error(point(json!([0]))),
+            ".coordinates: expected a position of 2 or 3 numbers"
+        );
+        assert_eq!(
+            error(point(json!([0, 0, 0, 0]))),
+            ".coordinates: expected a position of 2 or 3 numbers"
+        );
+        let nan = Json::Array(vec![Json::Number(f64::NAN), Json::Number(0.0)]);
+        assert_eq!(
+            error(point(nan)),
+            ".coordinates.[0]: longitude NaN is outside [-180, 180]"
+        );
+        let infinite = Json::Array(vec![
+            Json::Number(0.0),
+            Json::Number(0.0),
+            Json::Number(f64::INFINITY),
+        ]);
+        assert_eq!(
+            error(point(infinite)),
+            ".coordinates.[2]: expected a finite altitude"
+        );
+    }
+
+    #[test]
+    fn unknown_types_are_errors() {
+        assert_eq!(
+            error(json!({"type": "Circle", "coordinates": [0, 0]})),
+            ".type: expected a geometry, found type \"Circle\""
+        );
+        assert_eq!(
+            error(json!({"coordinates": [0, 0]})),
+            ".: missing field \"type\""
+        );
+        let json = json!({"type": "FeatureCollection", "features": [{"type": "Point"}]});
+        assert_eq!(
+            error(json),
+            ".features.[0].type: expected a Feature, found type \"Point\""
+        );
+    }
+
+    #[test]
+    fn a_bbox_may_cross_the_antimeridian() {
+        let bbox = Bbox::from_json(&json!([177.0, -20.0, -178.0, -16.0])).unwrap();
+        assert_eq!(bbox.min, Position::new(177.0, -20.0));
+        assert_eq!(bbox.max, Position::new(-178.0, -16.0));
+        assert!(bbox.contains(&Position::new(179.0, -18.0)));
+        assert!(bbox.contains(&Position::new(-179.0, -18.0)));
+        assert!(!bbox.contains(&Position::new(0.0, -18.0)));
+        assert_eq!(bbox.to_json(), json!([177.0, -20.0, -178.0, -16.0]));
+
+        let bbox = Bbox::from_json(&json!([0, 0, -10, 1, 1, 10])).unwrap();
+        assert_eq!(bbox.min, Position::with_alt(0.0, 0.0, -10.0));
+        assert!(!bbox.contains(&Position::with_alt(0.5, 0.5, 11.0)));
+
+        // only longitude may wrap around
+        assert_eq!(
+            Bbox::from_json(&json!([0, 10, 1, 0]))
+                .unwrap_err()
+                .to_string(),
+            ".: expected the south-west corner of the bbox first"
+        );
+        assert_eq!(
+            Bbox::from_json(&json!([0, 0, 1])).unwrap_err().to_string(),
+            ".: expected a bbox of 4 or 6 numbers"
+        );
+    }
+
+    #[test]
+    fn computed_bbox_is_3d_only_if_every_position_is() {
+        let line = |positions: Vec<Position>| Geometry::new(Value::LineString(positions));
+        let bbox = line(vec![
+            Position::with_alt(0.0, 0.0, 5.0),
+            Position::with_alt(2.0, -1.0, 1.0),
+        ])
+        .compute_bbox()
+        .unwrap();
+        assert_eq!(bbox.to_json(), json!([0, -1, 1, 2, 0, 5]));
+
+        for positions in [
+            vec![Position::with_alt(0.0, 0.0, 5.0), Position::new(2.0, -1.0)],
+            vec![Position::new(2.0, -1.0), Position::with_alt(0.0, 0.0, 5.0)],
+        ] {
+            let bbox = line(positions).compute_bbox().unwrap();
+            assert_eq!(bbox.to_json(), json!([0, -1, 2, 0]));
+        }
+
+        // and across the features of a collection
+        let collection = FeatureCollection {
+            features: vec![
+                Feature {
+                    geometry: Some(Value::Point(Position::with_alt(1.0, 1.0, 3.0)).into()),
+                    ..Feature::default()
+                },
+                Feature::default(),
+                Feature {
+                    geometry: Some(Value::Point(Position::new(-1.0, 2.0)).into()),
+                    ..Feature::default()
+                },
+            ],
+            ..FeatureCollection::default()
+        };
+        let bbox = collection.compute_bbox().unwrap();
+        assert_eq!(bbox.to_json(), json!([-1, 1, 1, 2]));
+        assert_eq!(FeatureCollection::default().compute_bbox(), None);
+    }
+}
@@ -0,0 +1,243 @@
+//! JSON Canonicalization Scheme (RFC 8785).
+//!
+//! The canonical form has no whitespace, object members sorted by the UTF-16
+//! code units of their keys, numbers formatted the way ECMAScript's
+//! `Number.prototype.toString` does and strings escaped minimally. Two
+//! implementations given equal data produce byte-identical output, which is
+//! what makes it usable as input to a signature.
+//! https://www.rfc-editor.org/rfc/rfc8785
+
+use std::fmt;
+
+use crate::parsen::{Json, write_escaped};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CanonicalError {
+    /// NaN and the infinities have no JSON representation.
+    NonFiniteNumber(f64),
+}
+
+impl fmt::Display for CanonicalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CanonicalError::NonFiniteNumber(num) => {
+                write!(f, "{} cannot be canonicalized", num)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CanonicalError {}
+
+/// The canonical text of `json`.
+pub fn to_canonical_string(json: &Json) -> Result<String, CanonicalError> {
+    let mut out = String::new();
+    write_canonical(&mut out, json)?;
+    Ok(out)
+}
+
+pub fn write_canonical(out: &mut String, json: &Json) -> Result<(), CanonicalError> {
+    match json {
+        Json::Null => out.push_str("null"),
+        Json::Boolean(b) => out.push_str(if *b { "true" } else { "false" }),
+        Json::Number(num) => out.push_str(&format_number(*num)?),
+        Json::Strings(st) => push_string(out, st),
+        Json::Array(vec) => {
+            out.push('[');
+            for (i, json) in vec.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(out, json)?;
+            }
+            out.push(']');
+        }
+        Json::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort_by(|a, b| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                push_string(out, key);
+                out.push(':');
+                write_canonical(out, &map[key])?;
+            }
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+fn push_string(out: &mut String, st: &str) {
+    // the tree serializer already escapes only what JSON requires, using
+    // the short forms where they exist and lowercase \u00xx otherwise.
+    let _ = write_escaped(out, st);
+}
+
+/// Formats `num` the way ECMAScript's `Number.prototype.toString` does,
+/// e.g. `1e+30`, `0.002`, `333333333.3333333`.
+pub fn format_number(num: f64) -> Result<String, CanonicalError> {
+    if !num.is_finite() {
+        return Err(CanonicalError::NonFiniteNumber(num));
+    }
+    if num == 0.0 {
+        // covers -0 as well
+        return Ok("0".to_string());
+    }
+    if num < 0.0 {
+        return Ok(format!("-{}", format_number(-num)?));
+    }
+
+    // `{:e}` gives the shortest digits that round-trip, e.g. "1.2345e-7".
+    let sci = format!("{:e}", num);
+    let (mantissa, exponent) = sci.split_once('e').expect("exponent in {:e} output");
+    let mut digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = digits.len() as i32;
+    // the value is 0.<digits> * 10^n
+    let n = exponent.parse::<i32>().expect("integer exponent") + 1;
+    if let Some(even) = even_tie_break(num, &digits, n) {
+        digits = even;
+    }
+
+    let text = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let sign = if n - 1 < 0 { '-' } else { '+' };
+        let fraction = if k > 1 {
+            format!(".{}", &digits[1..])
+        } else {
+            String::new()
+        };
+        format!("{}{}e{}{}", &digits[..1], fraction, sign, (n - 1).abs())
+    };
+    Ok(text)
+}
+
+/// ECMAScript picks the even candidate when two shortest digit strings
+/// round-trip and `num` lies exactly halfway between them; Rust's
+/// formatter rounds such ties up. Returns the even digits in that case.
+fn even_tie_break(num: f64, digits: &str, n: i32) -> Option<String> {
+    let k = digits.len();
+    let shortest: u64 = digits.parse().ok()?;
+    let lower = shortest.checked_sub(1)?;
+    if shortest.is_multiple_of(2) || lower.to_string().len() != k {
+        return None;
+    }
+    // nearly always false, so checked before the expensive expansion
+    if format!("{}e{}", lower, n - k as i32).parse::<f64>() != Ok(num) {
+        return None;
+    }
+    // the exact decimal expansion of an f64 never exceeds 767 digits
+    let exact = format!("{:.1100e}", num);
+    let (mantissa, _) = exact.split_once('e')?;
+    let exact_digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let (head, tail) = exact_digits.split_at(k);
+    let halfway =
+        head == lower.to_string() && tail.starts_with('5') && tail[1..].chars().all(|c| c == '0');
+    halfway.then(|| lower.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsen::Parser;
+
+    fn canonical(text: &str) -> String {
+        let json = Parser::from_string(text.to_string()).parse();
+        to_canonical_string(&json).unwrap()
+    }
+
+    /// RFC 8785, Appendix B.
+    #[test]
+    fn number_vectors() {
+        let vectors = [
+            (0x0000000000000000, "0"),
+            (0x8000000000000000, "0"),
+            (0x0000000000000001, "5e-324"),
+            (0x8000000000000001, "-5e-324"),
+            (0x7fefffffffffffff, "1.7976931348623157e+308"),
+            (0xffefffffffffffff, "-1.7976931348623157e+308"),
+            (0x4340000000000000, "9007199254740992"),
+            (0xc340000000000000, "-9007199254740992"),
+            (0x4430000000000000, "295147905179352830000"),
+            (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+            (0x44b52d02c7e14af6, "1e+23"),
+            (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+            (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+            (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+            (0x444b1ae4d6e2ef50, "1e+21"),
+            (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+            (0x3eb0c6f7a0b5ed8d, "0.000001"),
+            (0x41b3de4355555553, "333333333.3333332"),
+            (0x41b3de4355555554, "333333333.33333325"),
+            (0x41b3de4355555555, "333333333.3333333"),
+            (0x41b3de4355555556, "333333333.3333334"),
+            (0x41b3de4355555557, "333333333.33333343"),
+            (0xbecbf647612f3696, "-0.0000033333333333333333"),
+            (0x43143ff3c1cb0959, "1424953923781206.2"),
+        ];
+        for (bits, expected) in vectors {
+            let num = f64::from_bits(bits);
+            assert_eq!(format_number(num).unwrap(), expected, "{:016x}", bits);
+        }
+    }
+
+    #[test]
+    fn non_finite_numbers_are_rejected() {
+        for num in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(matches!(
+                to_canonical_string(&Json::Number(num)),
+                Err(CanonicalError::NonFiniteNumber(_))
+            ));
+        }
+    }
+
+    /// RFC 8785, section 3.2.3.
+    #[test]
+    fn members_sort_by_utf16_code_units() {
+        let text = r#"{
+            "\u20ac": "Euro Sign",
+            "\r": "Carriage Return",
+            "\ufb33": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "\ud83d\ude00": "Emoji: Grinning Face",
+            "\u0080": "Control",
+            "\u00f6": "Latin Small Letter O With Diaeresis"
+        }"#;
+        let json = Parser::from_string(text.to_string()).parse();
+        let canonical = to_canonical_string(&json).unwrap();
+        let order: Vec<usize> = [
+            "Carriage Return",
+            "One",
+            "Control",
+            "Latin Small Letter O With Diaeresis",
+            "Euro Sign",
+            "Emoji: Grinning Face",
+            "Hebrew Letter Dalet With Dagesh",
+        ]
+        .iter()
+        .map(|value| canonical.find(value).unwrap())
+        .collect();
+        assert!(order.is_sorted(), "{}", canonical);
+    }
+
+    /// RFC 8785, section 3.2.2.
+    #[test]
+    fn whole_document() {
+        let text = r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#;
+        assert_eq!(
+            canonical(text),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+    }
+}
@@ -0,0 +1,815 @@
+//! JSON-RPC 2.0 messages, and a `Dispatcher` that answers them.
+//!
+//! Each message type reads itself with `FromJson`, which rejects anything
+//! the specification does not allow, and writes itself with `ToJson`. The
+//! dispatcher takes requests as text, one message or batch per line when
+//! serving a stream, and writes one line for each response.
+//!
+//! ```
+//! use json_parser::jsonrpc::{Dispatcher, Error};
+//! use json_parser::json;
+//!
+//! let mut dispatcher = Dispatcher::new();
+//! dispatcher.register("subtract", |params| {
+//!     let (a, b): (f64, f64) = params.to()?;
+//!     Ok(json!(a - b))
+//! });
+//! dispatcher.register("fail", |_| Err(Error::new(7, "no")));
+//!
+//! let input = concat!(
+//!     r#"{"jsonrpc": "2.0", "method": "subtract", "params": [42, 23], "id": 1}"#, "\n",
+//!     r#"{"jsonrpc": "2.0", "method": "subtract", "params": [42]}"#, "\n",
+//!     r#"[{"jsonrpc": "2.0", "method": "fail", "id": "a"}, {"foo": "boo"}]"#, "\n",
+//!     r#"{"jsonrpc": "2.0", "method": "#, "\n",
+//! );
+//! let mut output = Vec::new();
+//! dispatcher.serve(input.as_bytes(), &mut output)?;
+//!
+//! let lines: Vec<&str> = std::str::from_utf8(&output).unwrap().lines().collect();
+//! assert_eq!(lines[0], r#"{"jsonrpc":"2.0","result":19,"id":1}"#);
+//! // the notification got no answer, not even for its error
+//! assert!(lines[1].starts_with(r#"[{"jsonrpc":"2.0","error":{"code":7,"message":"no"},"id":"a"}"#));
+//! assert!(lines[1].contains(r#""code":-32600"#));
+//! assert!(lines[2].contains(r#""code":-32700"#));
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use crate::convert::{FromJson, FromJsonError, ToJson, missing, object_entries, required};
+use crate::map::Map;
+use crate::parsen::{Json, Parser, ParserOptions};
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+
+/// The `id` of a request, echoed in its response.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Id {
+    Number(f64),
+    String(String),
+    /// Only in a response to a request whose id could not be read.
+    Null,
+}
+
+impl ToJson for Id {
+    fn to_json(&self) -> Json {
+        match self {
+            Id::Number(num) => Json::Number(*num),
+            Id::String(st) => Json::Strings(st.clone()),
+            Id::Null => Json::Null,
+        }
+    }
+}
+
+impl FromJson for Id {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        match json {
+            Json::Number(num) => Ok(Id::Number(*num)),
+            Json::Strings(st) => Ok(Id::String(st.clone())),
+            Json::Null => Ok(Id::Null),
+            _ => Err(FromJsonError::expected("string, number or null", json)),
+        }
+    }
+}
+
+impl From<i64> for Id {
+    fn from(num: i64) -> Id {
+        Id::Number(num as f64)
+    }
+}
+
+impl From<&str> for Id {
+    fn from(st: &str) -> Id {
+        Id::String(st.to_string())
+    }
+}
+
+/// The `error` member of a response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<Json>,
+}
+
+impl Error {
+    pub fn new(code: i64, message: impl Into<String>) -> Error {
+        Error {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    pub fn with_data(mut self, data: Json) -> Error {
+        self.data = Some(data);
+        self
+    }
+
+    pub fn parse_error() -> Error {
+        Error::new(PARSE_ERROR, "Parse error")
+    }
+
+    pub fn invalid_request() -> Error {
+        Error::new(INVALID_REQUEST, "Invalid Request")
+    }
+
+    pub fn method_not_found() -> Error {
+        Error::new(METHOD_NOT_FOUND, "Method not found")
+    }
+
+    pub fn invalid_params() -> Error {
+        Error::new(INVALID_PARAMS, "Invalid params")
+    }
+
+    pub fn internal_error() -> Error {
+        Error::new(INTERNAL_ERROR, "Internal error")
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({})", self.message, self.code)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Params that do not read as a handler expects, so that handlers can
+/// use `?` on `Json::to`.
+impl From<FromJsonError> for Error {
+    fn from(err: FromJsonError) -> Error {
+        Error::invalid_params().with_data(Json::Strings(err.to_string()))
+    }
+}
+
+impl ToJson for Error {
+    fn to_json(&self) -> Json {
+        let mut map = Map::new();
+        map.insert("code".to_string(), Json::Number(self.code as f64));
+        map.insert("message".to_string(), Json::Strings(self.message.clone()));
+        if let Some(data) = &self.data {
+            map.insert("data".to_string(), data.clone());
+        }
+        Json::Object(Box::new(map))
+    }
+}
+
+impl FromJson for Error {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        let map = object_entries(json)?;
+        Ok(Error {
+            code: required(map, "code")?,
+            message: required(map, "message")?,
+            data: map.get("data").cloned(),
+        })
+    }
+}
+
+/// A call that expects a response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub id: Id,
+    pub method: String,
+    /// An array or an object.
+    pub params: Option<Json>,
+}
+
+impl Request {
+    pub fn new(id: impl Into<Id>, method: impl Into<String>, params: Option<Json>) -> Request {
+        Request {
+            id: id.into(),
+            method: method.into(),
+            params,
+        }
+    }
+}
+
+/// A call without an `id`, which is never answered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub method: String,
+    pub params: Option<Json>,
+}
+
+impl Notification {
+    pub fn new(method: impl Into<String>, params: Option<Json>) -> Notification {
+        Notification {
+            method: method.into(),
+            params,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub id: Id,
+    /// The `result` member, or the `error` one.
+    pub result: Result<Json, Error>,
+}
+
+/// Any single message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Request(Request),
+    Notification(Notification),
+    Response(Response),
+}
+
+impl ToJson for Request {
+    fn to_json(&self) -> Json {
+        envelope(vec![
+            ("method", Some(Json::Strings(self.method.clone()))),
+            ("params", self.params.clone()),
+            ("id", Some(self.id.to_json())),
+        ])
+    }
+}
+
+impl ToJson for Notification {
+    fn to_json(&self) -> Json {
+        envelope(vec![
+            ("method", Some(Json::Strings(self.method.clone()))),
+            ("params", self.params.clone()),
+        ])
+    }
+}
+
+impl ToJson for Response {
+    fn to_json(&self) -> Json {
+        let outcome = match &self.result {
+            Ok(result) => ("result", Some(result.clone())),
+            Err(error) => ("error", Some(error.to_json())),
+        };
+        envelope(vec![outcome, ("id", Some(self.id.to_json()))])
+    }
+}
+
+impl ToJson for Message {
+    fn to_json(&self) -> Json {
+        match self {
+            Message::Request(request) => request.to_json(),
+            Message::Notification(notification) => notification.to_json(),
+            Message::Response(response) => response.to_json(),
+        }
+    }
+}
+
+impl FromJson for Request {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        match Message::from_json(json)? {
+            Message::Request(request) => Ok(request),
+            _ => Err(FromJsonError::custom("expected a request")),
+        }
+    }
+}
+
+impl FromJson for Notification {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        match Message::from_json(json)? {
+            Message::Notification(notification) => Ok(notification),
+            _ => Err(FromJsonError::custom("expected a notification")),
+        }
+    }
+}
+
+impl FromJson for Response {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        match Message::from_json(json)? {
+            Message::Response(response) => Ok(response),
+            _ => Err(FromJsonError::custom("expected a response")),
+        }
+    }
+}
+
+/// A message with a `method` is a request, or a notification if it has no
+/// `id`; one with a `result` or an `error` is a response.
+impl FromJson for Message {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        let map = object_entries(json)?;
+        match map.get("jsonrpc") {
+            Some(Json::Strings(version)) if version == "2.0" => {}
+            Some(_) => return Err(FromJsonError::custom("expected \"2.0\"").at_key("jsonrpc")),
+            None => return Err(missing("jsonrpc")),
+        }
+
+        if map.contains_key("method") {
+            let method: String = required(map, "method")?;
+            let params = match map.get("params") {
+                None => None,
+                Some(params @ (Json::Array(_) | Json::Object(_))) => Some(params.clone()),
+                Some(params) => {
+                    return Err(FromJsonError::expected("array or object", params).at_key("params"));
+                }
+            };
+            check_members(map, &["jsonrpc", "method", "params", "id"])?;
+            return Ok(match map.get("id") {
+                Some(_) => Message::Request(Request {
+                    id: required(map, "id")?,
+                    method,
+                    params,
+                }),
+                None => Message::Notification(Notification { method, params }),
+            });
+        }
+
+        let result = match (map.get("result"), map.get("error")) {
+            (Some(result), None) => Ok(result.clone()),
+            (None, Some(_)) => Err(required(map, "error")?),
+            (Some(_), Some(_)) => {
+                return Err(FromJsonError::custom(
+                    "expected either `result` or `error`, not both",
+                ));
+            }
+            (None, None) => {
+                return Err(FromJsonError::custom(
+                    "expected `method`, `result` or `error`",
+                ));
+            }
+        };
+        check_members(map, &["jsonrpc", "result", "error", "id"])?;
+        Ok(Message::Response(Response {
+            id: required(map, "id")?,
+            result,
+        }))
+    }
+}
+
+/// What one line of input holds: a message, or a batch of them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Packet {
+    Single(Message),
+    /// Never empty.
+    Batch(Vec<Message>),
+}
+
+impl ToJson for Packet {
+    fn to_json(&self) -> Json {
+        match self {
+            Packet::Single(message) => message.to_json(),
+            Packet::Batch(messages) => messages.to_json(),
+        }
+    }
+}
+
+impl FromJson for Packet {
+    fn from_json(json: &Json) -> Result<Self, FromJsonError> {
+        match json {
+            Json::Array(vec) if vec.is_empty() => Err(FromJsonError::custom("empty batch")),
+            Json::Array(_) => Vec::from_json(json).map(Packet::Batch),
+            _ => Message::from_json(json).map(Packet::Single),
+        }
+    }
+}
+
+type Handler = Box<dyn FnMut(&Json) -> Result<Json, Error>>;
+
+/// Routes requests and notifications to handlers by method name.
+#[derive(Default)]
+pub struct Dispatcher {
+    handlers: HashMap<String, Handler>,
+}
+
+impl Dispatcher {
+    pub fn new() -> Dispatcher {
+        Dispatcher::default()
+    }
+
+    /// Handles `method` with `handler`, which gets the params, or `null`
+    /// when there are none. An earlier handler for the method is replaced.
+    pub fn register<F>(&mut self, method: impl Into<String>, handler: F)
+    where
+        F: FnMut(&Json) -> Result<Json, Error> + 'static,
+    {
+        self.handlers.insert(method.into(), Box::new(handler));
+    }
+
+    /// The response to a message or batch, if it needs one: notifications
+    /// and responses are not answered, and neither is a batch of only
+    /// those.
+    pub fn handle(&mut self, json: &Json) -> Option<Json> {
+        match json {
+            Json::Array(vec) if vec.is_empty() => Some(invalid(json, "empty batch".to_string())),
+            Json::Array(vec) => {
+                let responses: Vec<Json> = vec
+                    .iter()
+                    .filter_map(|json| self.handle_one(json))
+                    .collect();
+                (!responses.is_empty()).then_some(Json::Array(responses))
+            }
+            _ => self.handle_one(json),
+        }
+    }
+
+    /// `handle` for text, answering text that does not parse with a parse
+    /// error.
+    pub fn handle_str(&mut self, text: &str) -> Option<String> {
+        let recovered = Parser::from_string_recovering(text.to_string(), ParserOptions::default())
+            .parse_recovering();
+        let response = match recovered.errors.first() {
+            Some(err) => Some(
+                Response {
+                    id: Id::Null,
+                    result: Err(Error::parse_error().with_data(Json::Strings(err.to_string()))),
+                }
+                .to_json(),
+            ),
+            None => self.handle(&recovered.json),
+        };
+        response.map(|json| json.to_string())
+    }
+
+    /// Answers each line of `input` on a line of `output` until `input`
+    /// ends. Blank lines are skipped.
+    pub fn serve<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(response) = self.handle_str(&line) {
+                writeln!(output, "{}", response)?;
+                output.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_one(&mut self, json: &Json) -> Option<Json> {
+        let (id, method, params) = match Message::from_json(json) {
+            Ok(Message::Request(request)) => (Some(request.id), request.method, request.params),
+            Ok(Message::Notification(notification)) => {
+                (None, notification.method, notification.params)
+            }
+            Ok(Message::Response(_)) => return None,
+            Err(err) => return Some(invalid(json, err.to_string())),
+        };
+        let result = match self.handlers.get_mut(&method) {
+            Some(handler) => handler(params.as_ref().unwrap_or(&Json::Null)),
+            None => Err(Error::method_not_found().with_data(Json::Strings(method))),
+        };
+        let id = id?;
+        Some(Response { id, result }.to_json())
+    }
+}
+
+/// The answer to a message that is not valid JSON-RPC, with its id if it
+/// had a readable one.
+fn invalid(json: &Json, reason: String) -> Json {
+    let id = json
+        .get("id")
+        .and_then(|id| Id::from_json(id).ok())
+        .unwrap_or(Id::Null);
+    Response {
+        id,
+        result: Err(Error::invalid_request().with_data(Json::Strings(reason))),
+    }
+    .to_json()
+}
+
+/// `{"jsonrpc": "2.0", ...members}`, leaving out `None` members.
+fn envelope(members: Vec<(&str, Option<Json>)>) -> Json {
+    let mut map = Map::new();
+    map.insert("jsonrpc".to_string(), Json::Strings("2.0".to_string()));
+    for (key, value) in members {
+        if let Some(value) = value {
+            map.insert(key.to_string(), value);
+        }
+    }
+    Json::Object(Box::new(map))
+}
+
+fn check_members(map: &Map, known: &[&str]) -> Result<(), FromJsonError> {
+    match map.keys().find(|key| !known.contains(&key.as_str())) {
+        Some(key) => Err(FromJsonError::custom(format!(
+            "unexpected member {:?}",
+            key
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    /// A dispatcher with `subtract` and a `log` that records its params.
+    fn dispatcher(log: std::rc::Rc<std::cell::RefCell<Vec<Json>>>) -> Dispatcher {
+        let mut dispatcher = Dispatcher::new();
+        dispatcher.register("subtract", |params| {
+            let (a, b): (f64, f64) = params.to()?;
+            Ok(json!(a - b))
+        });
+        dispatcher.register("log", move |params| {
+            log.borrow_mut().push(params.clone());
+            Ok(Json::Null)
+        });
+        dispatcher
+    }
+
+    /// Serves `input` and returns the lines written, parsed.
+    fn serve(input: &str) -> Vec<Json> {
+        let mut output = Vec::new();
+        dispatcher(Default::default())
+            .serve(input.as_bytes(), &mut output)
+            .unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| Parser::from_string(line.to_string()).parse())
+            .collect()
+    }
+
+    /// The response to a message that is not valid JSON-RPC.
+    fn invalid_request(id: Json, reason: &str) -> Json {
+        json!({
+            "jsonrpc": "2.0",
+            "error": {"code": -32600, "message": "Invalid Request", "data": (reason)},
+            "id": id
+        })
+    }
+
+    #[test]
+    fn a_request_is_answered_on_its_own_line() {
+        let input = concat!(
+            r#"{"jsonrpc": "2.0", "method": "subtract", "params": [42, 23], "id": 1}"#,
+            "\n\n",
+            r#"{"jsonrpc": "2.0", "method": "subtract", "params": [23, 42], "id": "b"}"#,
+        );
+        assert_eq!(
+            serve(input),
+            [
+                json!({"jsonrpc": "2.0", "result": 19, "id": 1}),
+                json!({"jsonrpc": "2.0", "result": -19, "id": "b"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn each_response_is_flushed_before_the_next_line_is_read() {
+        use std::io::{BufReader, BufWriter};
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let (requests, mut to_server) = io::pipe().unwrap();
+        let (from_server, responses) = io::pipe().unwrap();
+        let server = std::thread::spawn(move || {
+            dispatcher(Default::default())
+                .serve(BufReader::new(requests), BufWriter::new(responses))
+        });
+        // lines from the server, so that a missing one times out
+        let (lines, received) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(from_server).lines() {
+                lines.send(line.unwrap()).unwrap();
+            }
+        });
+
+        // the input stays open, so each answer must arrive on its own
+        for (request, response) in [
+            (
+                r#"{"jsonrpc": "2.0", "method": "subtract", "params": [5, 3], "id": 1}"#,
+                r#"{"jsonrpc":"2.0","result":2,"id":1}"#,
+            ),
+            (
+                r#"{"jsonrpc": "2.0", "method": "subtract", "params": [3, 5], "id": 2}"#,
+                r#"{"jsonrpc":"2.0","result":-2,"id":2}"#,
+            ),
+        ] {
+            writeln!(to_server, "{}", request).unwrap();
+            let line = received
+                .recv_timeout(Duration::from_secs(10))
+                .expect("a response before the input ends");
+            assert_eq!(line, response);
+        }
+
+        drop(to_server);
+        server.join().unwrap().unwrap();
+        assert!(received.recv().is_err());
+    }
+
+    #[test]
+    fn notifications_are_run_but_not_answered() {
+        let log = std::rc::Rc::default();
+        let mut output = Vec::new();
+        let input = concat!(
+            r#"{"jsonrpc": "2.0", "method": "log", "params": {"a": 1}}"#,
+            "\n",
+            r#"{"jsonrpc": "2.0", "method": "log"}"#,
+            "\n",
+            r#"{"jsonrpc": "2.0", "method": "missing"}"#,
+        );
+        dispatcher(std::rc::Rc::clone(&log))
+            .serve(input.as_bytes(), &mut output)
+            .unwrap();
+        assert!(output.is_empty());
+        assert_eq!(*log.borrow(), [json!({"a": 1}), Json::Null]);
+    }
+
+    #[test]
+    fn a_batch_of_notifications_is_not_answered() {
+        let input = r#"[{"jsonrpc": "2.0", "method": "log"}, {"jsonrpc": "2.0", "method": "log"}]"#;
+        assert_eq!(serve(input), []);
+    }
+
+    #[test]
+    fn an_empty_batch_is_an_invalid_request() {
+        assert_eq!(serve("[]"), [invalid_request(Json::Null, "empty batch")]);
+    }
+
+    #[test]
+    fn a_batch_answers_each_request_in_order() {
+        let input = concat!(
+            "[",
+            r#"{"jsonrpc": "2.0", "method": "subtract", "params": [3, 1], "id": 1},"#,
+            r#"{"jsonrpc": "2.0", "method": "log"},"#,
+            r#"1,"#,
+            r#"{"jsonrpc": "2.0", "method": 5, "id": 3},"#,
+            r#"{"jsonrpc": "2.0", "method": "subtract", "params": [1, 3], "id": 4}"#,
+            "]",
+        );
+        assert_eq!(
+            serve(input),
+            [json!([
+                {"jsonrpc": "2.0", "result": 2, "id": 1},
+                (invalid_request(Json::Null, ".: expected object, found number")),
+                (invalid_request(json!(3), ".method: expected string, found number")),
+                {"jsonrpc": "2.0", "result": -2, "id": 4}
+            ])]
+        );
+    }
+
+    #[test]
+    fn text_that_does_not_parse_is_a_parse_error() {
+        let input = concat!(
+            r#"{"jsonrpc": "2.0", "method": "subtract", "params": [1, 2], "id": 1"#,
+            "\n",
+            r#"[1, 2"#,
+        );
+        let responses = serve(input);
+        assert_eq!(responses.len(), 2);
+        for response in responses {
+            assert_eq!(response.get("id"), Some(&Json::Null));
+            let error = response.get("error").unwrap();
+            assert_eq!(error.get("code"), Some(&json!(-32700)));
+            assert_eq!(error.get("message"), Some(&json!("Parse error")));
+            assert!(error.get("data").is_some_and(Json::is_string));
+        }
+    }
+
+    #[test]
+    fn an_unknown_method_is_not_found() {
+        let input = r#"{"jsonrpc": "2.0", "method": "add", "id": 1}"#;
+        assert_eq!(
+            serve(input),
+            [json!({
+                "jsonrpc": "2.0",
+                "error": {"code": -32601, "message": "Method not found", "data": "add"},
+                "id": 1
+            })]
+        );
+    }
+
+    #[test]
+    fn params_that_do_not_read_are_invalid_params() {
+        let input = r#"{"jsonrpc": "2.0", "method": "subtract", "params": ["a", 1], "id": 1}"#;
+        let responses = serve(input);
+        let error = responses[0].get("error").unwrap();
+        assert_eq!(error.get("code"), Some(&json!(-32602)));
+        assert_eq!(error.get("message"), Some(&json!("Invalid params")));
+    }
+
+    #[test]
+    fn params_must_be_an_array_or_an_object() {
+        for params in ["1", "\"a\"", "null", "true"] {
+            let input = format!(
+                r#"{{"jsonrpc": "2.0", "method": "subtract", "params": {}, "id": 1}}"#,
+                params
+            );
+            let responses = serve(&input);
+            let found = Parser::from_string(params.to_string()).parse();
+            let reason = FromJsonError::expected("array or object", &found)
+                .at_key("params")
+                .to_string();
+            assert_eq!(responses, [invalid_request(json!(1), &reason)]);
+        }
+    }
+
+    #[test]
+    fn messages_are_checked_against_the_specification() {
+        let error = |json: Json| Message::from_json(&json).unwrap_err().to_string();
+        assert_eq!(
+            error(
+                json!({"jsonrpc": "2.0", "result": 1, "error": {"code": 1, "message": "a"}, "id": 1})
+            ),
+            ".: expected either `result` or `error`, not both"
+        );
+        assert_eq!(
+            error(json!({"jsonrpc": "2.0", "method": "a", "id": 1, "extra": true})),
+            ".: unexpected member \"extra\""
+        );
+        assert_eq!(
+            error(json!({"jsonrpc": "2.0", "result": 1, "id": 1, "method_": "a"})),
+            ".: unexpected member \"method_\""
+        );
+        assert_eq!(
+            error(json!({"jsonrpc": "1.0", "method": "a", "id": 1})),
+            ".jsonrpc: expected \"2.0\""
+        );
+        assert_eq!(
+            error(json!({"jsonrpc": 2.0, "method": "a", "id": 1})),
+            ".jsonrpc: expected \"2.0\""
+        );
+        assert_eq!(
+            error(json!({"method": "a", "id": 1})),
+            ".: missing field \"jsonrpc\""
+        );
+        assert_eq!(
+            error(json!({"jsonrpc": "2.0", "id": 1})),
+            ".: expected `method`, `result` or `error`"
+        );
+        assert_eq!(
+            error(json!({"jsonrpc": "2.0", "method": "a", "id": [1]})),
+            ".id: expected string, number or null, found array"
+        );
+
+        // and answered as invalid requests
+        let input = r#"{"jsonrpc": "1.0", "method": "subtract", "params": [2, 1], "id": 7}"#;
+        assert_eq!(
+            serve(input),
+            [invalid_request(json!(7), ".jsonrpc: expected \"2.0\"")]
+        );
+        let input = r#"{"jsonrpc": "2.0", "method": "subtract", "id": 7, "x": 1}"#;
+        assert_eq!(
+            serve(input),
+            [invalid_request(json!(7), ".: unexpected member \"x\"")]
+        );
+    }
+
+    #[test]
+    fn responses_are_not_answered() {
+        let input = concat!(
+            r#"{"jsonrpc": "2.0", "result": 1, "id": 1}"#,
+            "\n",
+            r#"[{"jsonrpc": "2.0", "error": {"code": 1, "message": "a"}, "id": null}]"#,
+        );
+        assert_eq!(serve(input), []);
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        let messages = [
+            Message::Request(Request::new(1, "subtract", Some(json!([42, 23])))),
+            Message::Request(Request::new("a", "list", Some(json!({"limit": 2})))),
+            Message::Request(Request::new(1, "ping", None)),
+            Message::Notification(Notification::new("update", Some(json!([1, 2])))),
+            Message::Notification(Notification::new("ping", None)),
+            Message::Response(Response {
+                id: Id::from(1),
+                result: Ok(json!(19)),
+            }),
+            Message::Response(Response {
+                id: Id::from("a"),
+                result: Ok(Json::Null),
+            }),
+            Message::Response(Response {
+                id: Id::Null,
+                result: Err(Error::invalid_request().with_data(json!({"why": "no"}))),
+            }),
+        ];
+        for message in &messages {
+            let json = message.to_json();
+            assert_eq!(Message::from_json(&json).as_ref(), Ok(message));
+            let text = json.to_string();
+            let parsed = Parser::from_string(text).parse();
+            assert_eq!(Message::from_json(&parsed).as_ref(), Ok(message));
+        }
+
+        assert_eq!(
+            messages[0].to_json().to_string(),
+            r#"{"jsonrpc":"2.0","method":"subtract","params":[42,23],"id":1}"#
+        );
+        assert_eq!(
+            messages[4].to_json().to_string(),
+            r#"{"jsonrpc":"2.0","method":"ping"}"#
+        );
+        assert_eq!(
+            messages[7].to_json().to_string(),
+            r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid Request","data":{"why":"no"}},"id":null}"#
+        );
+
+        let batch = Packet::Batch(messages[..2].to_vec());
+        assert_eq!(Packet::from_json(&batch.to_json()), Ok(batch));
+        assert_eq!(
+            Packet::from_json(&json!([])).unwrap_err().to_string(),
+            ".: empty batch"
+        );
+    }
+}
@@ -1,8 +1,29 @@
 use std::collections::HashMap;
 use std::fmt;
 use std::fs;
-
+use std::ops::Range;
+
+mod macros;
+
+pub mod codegen;
+pub mod convert;
+pub mod cst;
+#[cfg(feature = "serde")]
+pub mod de;
+pub mod diff;
+pub mod fingerprint;
+pub mod geojson;
+pub mod jcs;
+pub mod jsonrpc;
+pub mod map;
+pub mod merge;
 pub mod parsen;
+pub mod pointer;
+#[cfg(feature = "serde")]
+pub mod ser;
+pub mod schema;
+pub mod source_map;
+pub mod writer;
 
 pub fn read_file(filepath: &str) -> Result<String, std::io::Error> {
     // ? propagates error
@@ -14,7 +35,10 @@ pub fn read_file(filepath: &str) -> Result<String, std::io::Error> {
 pub struct Token {
     kind: Kind,
     line: u32,
-    // lexeme: String, future use: cool to see what we actually consumed.
+    /// Zero-based, in characters from the start of the line.
+    column: u32,
+    /// Byte range of the lexeme in the source.
+    span: Range<usize>,
 }
 
 impl Token {
@@ -22,6 +46,16 @@ impl Token {
         self.line
     }
 
+    pub fn get_column(&self) -> u32 {
+        self.column
+    }
+
+    /// The byte range of what was consumed for this token, quotes included
+    /// for strings.
+    pub fn get_span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
     pub fn get_kind(&self) -> Kind {
         self.kind.clone()
     }
@@ -37,16 +71,19 @@ impl fmt::Debug for Token {
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Kind {
-    BeginArray,     // [
-    BeginObject,    // {
-    EndArray,       // ]
-    EndObject,      // }
-    NameSeparator,  // :
-    ValueSeparator, // ,
-    Boolean(bool),  // true, false
-    Null,           // null
-    String(String), // string
-    Number(f64),    // number
+    BeginArray,         // [
+    BeginObject,        // {
+    EndArray,           // ]
+    EndObject,          // }
+    NameSeparator,      // :
+    ValueSeparator,     // ,
+    Boolean(bool),      // true, false
+    Null,               // null
+    String(String),     // string
+    Number(f64),        // number
+    Comment(String),    // JSONC comment, only when asked to keep them
+    Identifier(String), // JSON5 unquoted key
+    Error(String),      // malformed input, only when recovering
     EOF,
 }
 
@@ -62,6 +99,26 @@ impl Kind {
             }
         }
     }
+
+    /// How the token reads in an error message.
+    pub(crate) fn describe(&self) -> &'static str {
+        match self {
+            Kind::BeginArray => "`[`",
+            Kind::BeginObject => "`{`",
+            Kind::EndArray => "`]`",
+            Kind::EndObject => "`}`",
+            Kind::NameSeparator => "`:`",
+            Kind::ValueSeparator => "`,`",
+            Kind::Boolean(_) => "boolean",
+            Kind::Null => "null",
+            Kind::String(_) => "string",
+            Kind::Number(_) => "number",
+            Kind::Comment(_) => "comment",
+            Kind::Identifier(_) => "identifier",
+            Kind::Error(_) => "malformed input",
+            Kind::EOF => "end of input",
+        }
+    }
 }
 
 //
@@ -75,6 +132,7 @@ impl Kind {
 
 pub mod lexen {
     use super::*;
+    use crate::parsen::ParserOptions;
 
     #[derive(Debug)]
     pub struct Lexer {
@@ -83,62 +141,81 @@ pub mod lexen {
         pub current: usize,
         line: u32,
         tokens: Vec<Token>,
+        options: ParserOptions,
+        recover: bool,
+        // the first problem with the token being read, when recovering
+        error: Option<Problem>,
+        // where the token being read starts
+        token_line: u32,
+        token_column: u32,
+        // byte offset where the current line starts, and a known column on
+        // it so that columns are counted incrementally
+        line_start: usize,
+        mark: (usize, u32),
+    }
+
+    #[derive(Debug)]
+    struct Problem {
+        message: String,
+        line: u32,
+        column: u32,
+        span: Range<usize>,
     }
 
     impl Lexer {
         pub fn new(input: String) -> Self {
+            Lexer::with_options(input, ParserOptions::default())
+        }
+
+        pub fn with_options(input: String, options: ParserOptions) -> Self {
             Self {
                 content: input,
                 start: 0,
                 current: 0,
                 line: 0,
                 tokens: Vec::new(),
+                options,
+                recover: false,
+                error: None,
+                token_line: 0,
+                token_column: 0,
+                line_start: 0,
+                mark: (0, 0),
             }
         }
 
+        /// Turns malformed input into `Kind::Error` tokens instead of
+        /// panicking, so that a parser can carry on past it.
+        pub fn recovering(mut self) -> Self {
+            self.recover = true;
+            self
+        }
+
         fn start(&mut self) {
             match self.advance() {
                 None => {
                     if self.not_at_end() {
-                        self.dump();
                         panic!("no char found.");
                     }
                 }
                 Some(xx) => {
-                    // self.dump();
                     match xx {
-                        '{' => self.tokens.push(Token {
-                            kind: Kind::BeginObject,
-                            line: self.line,
-                        }),
-                        '[' => self.tokens.push(Token {
-                            kind: Kind::BeginArray,
-                            line: self.line,
-                        }),
-                        '}' => self.tokens.push(Token {
-                            kind: Kind::EndObject,
-                            line: self.line,
-                        }),
-                        ']' => self.tokens.push(Token {
-                            kind: Kind::EndArray,
-                            line: self.line,
-                        }),
-                        ':' => self.tokens.push(Token {
-                            kind: Kind::NameSeparator,
-                            line: self.line,
-                        }),
-                        ',' => self.tokens.push(Token {
-                            kind: Kind::ValueSeparator,
-                            line: self.line,
-                        }),
-                        '"' => self.read_string(),
+                        '{' => self.push(Kind::BeginObject),
+                        '[' => self.push(Kind::BeginArray),
+                        '}' => self.push(Kind::EndObject),
+                        ']' => self.push(Kind::EndArray),
+                        ':' => self.push(Kind::NameSeparator),
+                        ',' => self.push(Kind::ValueSeparator),
+                        '"' => self.read_string('"'),
+                        '\'' if self.options.allows_single_quotes() => self.read_string('\''),
                         '-' | '0'..='9' => self.read_number(), // numbers may be -ve
+                        '+' | '.' if self.options.allows_json5_numbers() => self.read_number(),
+                        '\u{FEFF}' if self.start == 0 && self.options.allows_leading_bom() => {}
+                        c if self.starts_identifier(c) => self.read_identifier(),
                         't' | 'f' | 'n' => self.read_literal(),
                         '\r' | '\t' | ' ' | '\n' => self.skip_whitespace(),
-                        _ => {
-                            self.dump();
-                            panic!("unknown char={xx} found at {}", self.line);
-                        }
+                        '/' if self.options.allows_comments() => self.read_comment(),
+                        _ => self.reject(format!("unexpected character `{xx}`")),
                     }
                 }
             }
@@ -151,8 +228,19 @@ pub mod lexen {
                         self.advance();
                     }
                     Some('\n') => {
-                        self.line += 1;
                         self.advance();
+                        self.newline();
+                    }
+                    // JSON5 also allows any Unicode space, and the byte
+                    // order mark; U+2028 and U+2029 end a line
+                    Some(c)
+                        if self.options.allows_json5_whitespace()
+                            && (c.is_whitespace() || c == '\u{FEFF}') =>
+                    {
+                        self.advance();
+                        if matches!(c, '\u{2028}' | '\u{2029}') {
+                            self.newline();
+                        }
                     }
                     _ => {
                         break;
@@ -171,21 +259,128 @@ pub mod lexen {
         pub fn lex(&mut self) -> Vec<Token> {
             while self.not_at_end() {
                 self.skip_whitespace();
-                self.start = self.current;
+                self.begin();
                 self.start();
             }
 
-            self.tokens.push(Token {
-                kind: Kind::EOF,
-                line: self.line,
-            });
+            self.begin();
+            self.push(Kind::EOF);
 
             // return value
-            return self.tokens.clone();
+            self.tokens.clone()
+        }
+
+        /// Lexes just the next token, returning `Kind::EOF` once the input
+        /// is used up. Lets a consumer walk the input without holding every
+        /// token at once.
+        pub fn next_token(&mut self) -> Token {
+            while self.tokens.is_empty() {
+                self.skip_whitespace();
+                if !self.not_at_end() {
+                    self.begin();
+                    return Token {
+                        kind: Kind::EOF,
+                        line: self.line,
+                        column: self.token_column,
+                        span: self.current..self.current,
+                    };
+                }
+                self.begin();
+                self.start();
+            }
+            self.tokens.remove(0)
+        }
+
+        // a token starts at the current position
+        fn begin(&mut self) {
+            self.start = self.current;
+            self.token_line = self.line;
+            self.token_column = self.column_at(self.start);
+        }
+
+        // a line terminator was just consumed
+        fn newline(&mut self) {
+            self.line += 1;
+            self.line_start = self.current;
+            self.mark = (self.current, 0);
+        }
+
+        // the column of an offset on the current line
+        fn column_at(&mut self, offset: usize) -> u32 {
+            let (from, column) = if offset >= self.mark.0 {
+                self.mark
+            } else {
+                (self.line_start, 0)
+            };
+            let column = column + self.content[from..offset].chars().count() as u32;
+            self.mark = (offset, column);
+            column
+        }
+
+        // the token runs from `start` to the current position; a malformed
+        // one is placed where its problem is
+        fn push(&mut self, kind: Kind) {
+            let token = match self.error.take() {
+                Some(problem) => Token {
+                    kind: Kind::Error(problem.message),
+                    line: problem.line,
+                    column: problem.column,
+                    span: problem.span,
+                },
+                None => Token {
+                    kind,
+                    line: self.token_line,
+                    column: self.token_column,
+                    span: self.start..self.current,
+                },
+            };
+            self.tokens.push(token);
+        }
+
+        // malformed input panics, or when recovering spoils the token being
+        // read so that it comes out as `Kind::Error`
+        fn fail(&mut self, message: String) {
+            let problem = Problem {
+                message,
+                line: self.token_line,
+                column: self.token_column,
+                span: self.start..self.current,
+            };
+            self.fail_with(problem);
+        }
+
+        // fails on what was read from `from` on the current line
+        fn fail_here(&mut self, message: String, from: usize) {
+            let problem = Problem {
+                message,
+                line: self.line,
+                column: self.column_at(from),
+                span: from..self.current,
+            };
+            self.fail_with(problem);
+        }
+
+        fn fail_with(&mut self, problem: Problem) {
+            if !self.recover {
+                panic!(
+                    "{} at line {}, column {}",
+                    problem.message,
+                    problem.line + 1,
+                    problem.column + 1
+                );
+            }
+            self.error.get_or_insert(problem);
+        }
+
+        // fails and ends the token here
+        fn reject(&mut self, message: String) {
+            self.fail(message);
+            // the kind gives way to the error
+            self.push(Kind::EOF);
         }
 
         fn not_at_end(&self) -> bool {
-            return self.current < self.content.len();
+            self.current < self.content.len()
         }
 
         fn peek(&self) -> Option<char> {
@@ -193,139 +388,317 @@ pub mod lexen {
         }
 
         fn previous(&self) -> Option<char> {
-            self.content[self.current - 1..].chars().next()
+            self.content[..self.current].chars().next_back()
         }
 
         fn advance(&mut self) -> Option<char> {
             // self.content.chars().nth();
             let ch: Option<char> = self.content[self.current..].chars().next();
-            self.current += 1;
+            self.current += ch.map_or(0, char::len_utf8);
             ch
         }
 
-        // TODO: handle escape sequences.
-        fn read_string(&mut self) {
+        fn read_string(&mut self, quote: char) {
             let mut s: String = String::new();
 
             while let Some(pat) = self.peek() {
+                let at = self.current;
                 self.advance();
-                if pat == '"' {
-                    self.tokens.push(Token {
-                        kind: Kind::String(s),
-                        line: self.line,
-                        // lexeme: (&self.content[self.start..self.current]).to_string(),
-                    });
-                    break;
+                if pat == quote {
+                    self.push(Kind::String(s));
+                    return;
+                } else if pat < ' ' && !self.options.allows_control_character(pat) {
+                    self.fail_here(
+                        format!("unescaped control character U+{:04X} in string", pat as u32),
+                        at,
+                    );
+                    if pat == '\n' {
+                        self.newline();
+                    }
                 } else {
+                    if pat == '\n' {
+                        self.newline();
+                    }
                     let st = self.resolve_string(pat);
                     s.push_str(&st);
                 }
             }
+            self.reject("unterminated string".to_string());
         }
 
         fn resolve_string(&mut self, ch: char) -> String {
-            if ch == '\\' {
-                if let Some(pat) = self.peek() {
-                    self.advance();
-                    match pat {
-                        'b' => return r"\b".to_string(),
-                        't' => return r"\t".to_string(),
-                        'n' => return r"\n".to_string(),
-                        'f' => return r"\f".to_string(),
-                        'r' => return r"\r".to_string(),
-                        'u' | 'U' => {
-                            // consume next hex didigt
-                            let hex = &(self.content[self.current..self.current + 4]);
-                            self.current += 4; // update idx;
-                            let code_pt = u32::from_str_radix(hex, 16)
-                                .expect("unable to convert hex codes {:?}");
-                            if let Some(cc) = char::from_u32(code_pt) {
-                                return cc.to_string();
-                            } else {
-                                panic!("Error converting code_pt {}", code_pt);
+            if ch == '\\'
+                && let Some(pat) = self.peek()
+            {
+                let escape = self.current - 1;
+                self.advance();
+                let json5 = self.options.allows_json5_escapes();
+                match pat {
+                    // a backslash before a line terminator continues the
+                    // string on the next line
+                    '\n' | '\u{2028}' | '\u{2029}' if json5 => {
+                        self.newline();
+                        return String::new();
+                    }
+                    '\r' if json5 => {
+                        if self.peek() == Some('\n') {
+                            self.advance();
+                        }
+                        self.newline();
+                        return String::new();
+                    }
+                    'v' if json5 => return '\u{0B}'.to_string(),
+                    '0' if json5 && !self.peek().is_some_and(|c| c.is_ascii_digit()) => {
+                        return '\0'.to_string();
+                    }
+                    // JSON5 has no octal escapes: `\0` may not be followed
+                    // by a digit, and no other digit may be escaped
+                    '0' if json5 => {
+                        self.fail_here("invalid escape `\\0` before a digit".to_string(), escape);
+                        return '\0'.to_string();
+                    }
+                    '1'..='9' if json5 => {
+                        self.fail_here(format!("invalid escape `\\{pat}`"), escape);
+                        return pat.to_string();
+                    }
+                    'x' if json5 => {
+                        let code_pt = self.read_hex(2, escape);
+                        return char::from_u32(code_pt).expect("two hex digits").to_string();
+                    }
+                    '"' | '\\' | '/' => return pat.to_string(),
+                    '\'' if self.options.allows_single_quotes() => return pat.to_string(),
+                    'b' => return '\u{08}'.to_string(),
+                    't' => return '\t'.to_string(),
+                    'n' => return '\n'.to_string(),
+                    'f' => return '\u{0C}'.to_string(),
+                    'r' => return '\r'.to_string(),
+                    'u' | 'U' => {
+                        let mut code_pt = self.read_hex(4, escape);
+                        // a high surrogate must be followed by an escaped low
+                        // surrogate; together they encode one code point.
+                        if (0xD800..0xDC00).contains(&code_pt) {
+                            match self.low_surrogate() {
+                                Some(low) => {
+                                    code_pt = 0x10000 + ((code_pt - 0xD800) << 10) + (low - 0xDC00);
+                                }
+                                None => {
+                                    self.fail_here("unpaired surrogate".to_string(), escape);
+                                    code_pt = 0xFFFD;
+                                }
                             }
+                        } else if (0xDC00..0xE000).contains(&code_pt) {
+                            self.fail_here("unpaired surrogate".to_string(), escape);
+                            code_pt = 0xFFFD;
                         }
-                        _ => return pat.to_string(),
+                        return char::from_u32(code_pt).expect("not a surrogate").to_string();
+                    }
+                    // JSON5 lets any other character stand for itself
+                    _ if json5 => return pat.to_string(),
+                    _ => {
+                        self.fail_here(format!("invalid escape `\\{pat}`"), escape);
+                        return pat.to_string();
                     }
                 }
             }
             ch.to_string()
         }
 
+        // consume a `\uDC00`..`\uDFFF` escape that completes a surrogate
+        // pair; anything else is left for the string to read on its own
+        fn low_surrogate(&mut self) -> Option<u32> {
+            let rest = self.content[self.current..].strip_prefix("\\u")?;
+            let hex = rest.get(..4).filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))?;
+            let low = u32::from_str_radix(hex, 16).ok()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return None;
+            }
+            self.current += 6;
+            Some(low)
+        }
+
+        // consume the hex digits of a \u (four) or \x (two) escape starting
+        // at `escape`, stopping early at anything that is not a hex digit
+        fn read_hex(&mut self, digits: usize, escape: usize) -> u32 {
+            let start = self.current;
+            while self.current - start < digits
+                && self.peek().is_some_and(|c| c.is_ascii_hexdigit())
+            {
+                self.advance();
+            }
+            if self.current - start < digits {
+                let kind = if digits == 4 { "unicode" } else { "hex" };
+                self.fail_here(format!("truncated {kind} escape"), escape);
+                return 0xFFFD;
+            }
+            u32::from_str_radix(&self.content[start..self.current], 16).expect("hex digits")
+        }
+
+        // number = [ minus ] int [ frac ] [ exp ]
+        // JSON5 also allows a leading `+`, hexadecimal integers, a leading or
+        // trailing decimal point, and signed `Infinity` and `NaN`
         fn read_number(&mut self) {
+            let json5 = self.options.allows_json5_numbers();
             let mut number = String::new();
             if let Some(prev) = self.previous() {
                 number.push(prev);
             }
-            // integer part
-            while let Some(pat) = self.peek() {
-                if pat.is_digit(10) {
-                    number.push(pat);
-                    self.advance();
-                } else if pat.is_ascii_whitespace() {
-                    self.skip_whitespace();
-                } else {
-                    break;
+
+            if matches!(number.as_str(), "+" | "-") {
+                match self.peek() {
+                    Some(c) if self.options.allows_non_finite() && is_identifier_start(c) => {
+                        let negative = number == "-";
+                        return self.read_non_finite(negative);
+                    }
+                    Some('0') if json5 => {
+                        self.advance();
+                        number.push('0');
+                    }
+                    _ => {}
                 }
             }
+            if json5 && number.ends_with('0') && matches!(self.peek(), Some('x' | 'X')) {
+                self.advance();
+                return self.read_hex_number(number.starts_with('-'));
+            }
+
+            // integer part; a zero cannot be followed by more digits
+            self.read_digits(&mut number);
+            let integer = number.trim_start_matches(['+', '-']);
+            if integer.len() > 1 && integer.starts_with('0') {
+                return self.reject(format!("leading zero in number `{number}`"));
+            }
+            let mut digits_around_point = !integer.is_empty();
 
             // fractional part
-            let mut exp: bool = false;
             if let Some('.') = self.peek() {
                 self.advance(); // consume .
-                while let Some(pat) = self.peek() {
-                    if pat.is_digit(10) {
-                        number.push(pat);
-                        self.advance();
-                    } else if pat == 'e' || pat == 'E' {
-                        exp = true;
-                        break;
-                    } else {
-                        break;
-                    }
-                }
+                number.push('.');
+                let point = number.len();
+                self.read_digits(&mut number);
+                digits_around_point &= number.len() > point;
             }
 
             // exponent part
-            if exp {
-                if let Some('-' | '+') = self.peek() {
+            if let Some('e' | 'E') = self.peek() {
+                self.advance();
+                number.push('e');
+                if let Some(sign @ ('-' | '+')) = self.peek() {
                     self.advance();
+                    number.push(sign);
                 }
-                while let Some(pat) = self.peek() {
-                    if pat.is_ascii_whitespace() {
-                        self.skip_whitespace();
-                        break;
-                    }
-                    if pat.is_digit(10) {
-                        number.push(pat);
-                        self.advance();
-                    } else {
-                        self.dump();
-                        panic!("unknown char {pat} found at {}", self.line);
-                    }
-                }
+                self.read_digits(&mut number);
             }
 
-            if number.ends_with("+") || number.ends_with("-") {
-                self.dump();
-                panic!("unknown sequence {number} found at {}", self.line);
+            let malformed = if json5 {
+                // `.` may end the number, but there must be a digit somewhere
+                number.ends_with(['+', '-', 'e']) || !number.contains(|c: char| c.is_ascii_digit())
+            } else {
+                // a digit on both sides of any `.`
+                number.ends_with(['+', '-', 'e']) || !digits_around_point
+            };
+            if malformed {
+                return self.reject(format!("malformed number `{number}`"));
             }
 
             let value = match number.parse::<f64>() {
                 Ok(f) => f,
                 Err(err) => {
-                    self.dump();
-                    panic!(
-                        "unable to parse {} @ ln{} with error {}",
-                        number, self.line, err
-                    );
+                    return self.reject(format!("malformed number `{number}`: {err}"));
                 }
             };
+            if !value.is_finite() && !self.options.allows_non_finite() {
+                return self.reject(format!("number `{number}` is out of range"));
+            }
 
-            self.tokens.push(Token {
-                kind: Kind::Number(value),
-                line: self.line,
-            });
+            self.push(Kind::Number(value));
+        }
+
+        // the digits after a JSON5 `0x`
+        fn read_hex_number(&mut self, negative: bool) {
+            let digits = self.current;
+            while let Some(pat) = self.peek() {
+                if pat.is_ascii_hexdigit() {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+            let hex = &self.content[digits..self.current];
+            let value = match u64::from_str_radix(hex, 16) {
+                Ok(value) => value as f64,
+                Err(err) => {
+                    return self.reject(format!("malformed number `0x{hex}`: {err}"));
+                }
+            };
+            self.push(Kind::Number(if negative { -value } else { value }));
+        }
+
+        // `Infinity` or `NaN` after a JSON5 sign
+        fn read_non_finite(&mut self, negative: bool) {
+            let word = self.current;
+            while let Some(pat) = self.peek() {
+                if is_identifier_part(pat) {
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+            let value = match &self.content[word..self.current] {
+                "Infinity" => f64::INFINITY,
+                "NaN" => f64::NAN,
+                s => {
+                    let message = format!("unknown literal `{s}`");
+                    return self.reject(message);
+                }
+            };
+            self.push(Kind::Number(if negative { -value } else { value }));
+        }
+
+        fn read_digits(&mut self, number: &mut String) {
+            while let Some(pat) = self.peek() {
+                if pat.is_ascii_digit() {
+                    number.push(pat);
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+        }
+
+        // line comments run up to the newline, block comments up to `*/`
+        fn read_comment(&mut self) {
+            match self.peek() {
+                Some('/') => {
+                    while let Some(ch) = self.peek() {
+                        if ch == '\n' {
+                            break;
+                        }
+                        self.advance();
+                    }
+                }
+                Some('*') => {
+                    self.advance();
+                    loop {
+                        match self.advance() {
+                            Some('*') if self.peek() == Some('/') => {
+                                self.advance();
+                                break;
+                            }
+                            Some('\n') => self.newline(),
+                            Some(_) => {}
+                            None => {
+                                return self.reject("unterminated block comment".to_string());
+                            }
+                        }
+                    }
+                }
+                _ => return self.reject("unexpected character `/`".to_string()),
+            }
+
+            if self.options.keep_comments {
+                let text = self.content[self.start..self.current].to_string();
+                self.push(Kind::Comment(text));
+            }
         }
 
         fn read_literal(&mut self) {
@@ -339,15 +712,61 @@ pub mod lexen {
                 "false" => Kind::Boolean(false),
                 "null" => Kind::Null,
                 _ => {
-                    self.dump();
-                    panic!("unknown literal `{s}` @ line{}", self.line);
+                    let message = format!("unknown literal `{s}`");
+                    return self.reject(message);
                 }
             };
 
-            self.tokens.push(Token {
-                kind: kd,
-                line: self.line,
-            });
+            self.push(kd);
         }
+
+        // a JSON5 unquoted key, or one of the keywords it shares a shape with
+        fn read_identifier(&mut self) {
+            let mut name = String::new();
+            if let Some(first) = self.previous() {
+                name.push(first);
+            }
+            while let Some(pat) = self.peek() {
+                if is_identifier_part(pat) {
+                    name.push(pat);
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+
+            let kd = match name.as_str() {
+                "true" => Kind::Boolean(true),
+                "false" => Kind::Boolean(false),
+                "null" => Kind::Null,
+                "Infinity" if self.options.allows_non_finite() => Kind::Number(f64::INFINITY),
+                "NaN" if self.options.allows_non_finite() => Kind::Number(f64::NAN),
+                _ if self.options.allows_unquoted_keys() => Kind::Identifier(name),
+                _ => {
+                    return self.reject(format!("unknown literal `{name}`"));
+                }
+            };
+
+            self.push(kd);
+        }
+
+        // bare words are read whole when unquoted keys or the non-finite
+        // literals are allowed; otherwise only `true`, `false` and `null`
+        fn starts_identifier(&self, c: char) -> bool {
+            if self.options.allows_unquoted_keys() {
+                is_identifier_start(c)
+            } else {
+                self.options.allows_non_finite() && matches!(c, 'I' | 'N')
+            }
+        }
+    }
+
+    fn is_identifier_start(c: char) -> bool {
+        c.is_alphabetic() || c == '$' || c == '_'
+    }
+
+    fn is_identifier_part(c: char) -> bool {
+        // U+200C and U+200D are the zero-width (non-)joiners
+        is_identifier_start(c) || c.is_alphanumeric() || matches!(c, '\u{200C}' | '\u{200D}')
     }
 }
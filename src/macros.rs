@@ -0,0 +1,212 @@
+/// Builds a `parsen::Json` from JSON-like syntax.
+///
+/// Literals `null`, `true` and `false` and nested `[...]` and `{...}` are
+/// written as in JSON. Any other value is a Rust expression of a type that
+/// implements `convert::ToJson`. Object keys are string literals or any
+/// Rust expression (a variable, `(format!(..))`, ...) and are converted
+/// with `to_string`.
+/// Trailing commas are allowed.
+///
+/// ```
+/// use json_parser::json;
+///
+/// let id = 943;
+/// let field = "Animated";
+/// let image = json!({
+///     "Width": 800,
+///     "IDs": [116, id, 234,],
+///     field: false,
+///     "Thumbnail": { "Url": null },
+/// });
+/// assert_eq!(image["IDs"][1].as_f64(), Some(943.0));
+/// ```
+#[macro_export]
+macro_rules! json {
+    ($($json:tt)+) => {
+        $crate::json_internal!($($json)+)
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! json_internal {
+    // Array elements are collected one at a time into [$($elems,)*].
+    (@array [$($elems:expr,)*]) => {
+        vec![$($elems,)*]
+    };
+    (@array [$($elems:expr,)*] null $(, $($rest:tt)*)?) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!(null),] $($($rest)*)?)
+    };
+    (@array [$($elems:expr,)*] true $(, $($rest:tt)*)?) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!(true),] $($($rest)*)?)
+    };
+    (@array [$($elems:expr,)*] false $(, $($rest:tt)*)?) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!(false),] $($($rest)*)?)
+    };
+    (@array [$($elems:expr,)*] [$($array:tt)*] $(, $($rest:tt)*)?) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!([$($array)*]),] $($($rest)*)?)
+    };
+    (@array [$($elems:expr,)*] {$($object:tt)*} $(, $($rest:tt)*)?) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!({$($object)*}),] $($($rest)*)?)
+    };
+    (@array [$($elems:expr,)*] $next:expr $(, $($rest:tt)*)?) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!($next),] $($($rest)*)?)
+    };
+
+    // Object members: the key is collected token by token up to the `:`,
+    // then the value is inserted into $map.
+    (@object $map:ident ()) => {};
+    (@object $map:ident [$($key:tt)+] (: null $(, $($rest:tt)*)?)) => {
+        $crate::json_internal!(@insert $map [$($key)+] ($crate::json_internal!(null)));
+        $crate::json_internal!(@object $map ($($($rest)*)?));
+    };
+    (@object $map:ident [$($key:tt)+] (: true $(, $($rest:tt)*)?)) => {
+        $crate::json_internal!(@insert $map [$($key)+] ($crate::json_internal!(true)));
+        $crate::json_internal!(@object $map ($($($rest)*)?));
+    };
+    (@object $map:ident [$($key:tt)+] (: false $(, $($rest:tt)*)?)) => {
+        $crate::json_internal!(@insert $map [$($key)+] ($crate::json_internal!(false)));
+        $crate::json_internal!(@object $map ($($($rest)*)?));
+    };
+    (@object $map:ident [$($key:tt)+] (: [$($array:tt)*] $(, $($rest:tt)*)?)) => {
+        $crate::json_internal!(@insert $map [$($key)+] ($crate::json_internal!([$($array)*])));
+        $crate::json_internal!(@object $map ($($($rest)*)?));
+    };
+    (@object $map:ident [$($key:tt)+] (: {$($object:tt)*} $(, $($rest:tt)*)?)) => {
+        $crate::json_internal!(@insert $map [$($key)+] ($crate::json_internal!({$($object)*})));
+        $crate::json_internal!(@object $map ($($($rest)*)?));
+    };
+    (@object $map:ident [$($key:tt)+] (: $value:expr $(, $($rest:tt)*)?)) => {
+        $crate::json_internal!(@insert $map [$($key)+] ($crate::json_internal!($value)));
+        $crate::json_internal!(@object $map ($($($rest)*)?));
+    };
+    (@object $map:ident [$($key:tt)*] ($tt:tt $($rest:tt)*)) => {
+        $crate::json_internal!(@object $map [$($key)* $tt] ($($rest)*))
+    };
+    (@object $map:ident ($($rest:tt)+)) => {
+        $crate::json_internal!(@object $map [] ($($rest)+))
+    };
+
+    (@insert $map:ident [$($key:tt)+] ($value:expr)) => {
+        let _ = $map.insert(::std::string::ToString::to_string(&($($key)+)), $value);
+    };
+
+    (null) => {
+        $crate::parsen::Json::Null
+    };
+    (true) => {
+        $crate::parsen::Json::Boolean(true)
+    };
+    (false) => {
+        $crate::parsen::Json::Boolean(false)
+    };
+    ([$($array:tt)*]) => {
+        $crate::parsen::Json::Array($crate::json_internal!(@array [] $($array)*))
+    };
+    ({$($object:tt)*}) => {
+        $crate::parsen::Json::Object({
+            #[allow(unused_mut)]
+            let mut map = $crate::map::Map::new();
+            $crate::json_internal!(@object map ($($object)*));
+            ::std::boxed::Box::new(map)
+        })
+    };
+    ($other:expr) => {
+        $crate::convert::ToJson::to_json(&$other)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map::Map;
+    use crate::parsen::Json;
+
+    fn object(members: Vec<(&str, Json)>) -> Json {
+        let map: Map = members
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        Json::Object(Box::new(map))
+    }
+
+    #[test]
+    fn literals_and_empty_containers() {
+        assert_eq!(json!(null), Json::Null);
+        assert_eq!(json!(true), Json::Boolean(true));
+        assert_eq!(json!(false), Json::Boolean(false));
+        assert_eq!(json!([]), Json::Array(Vec::new()));
+        assert_eq!(json!({}), object(Vec::new()));
+        assert_eq!(
+            json!([[], {}]),
+            Json::Array(vec![Json::Array(Vec::new()), object(Vec::new())])
+        );
+    }
+
+    #[test]
+    fn values_may_be_expressions() {
+        let v = vec![1, 2, 3];
+        let some: Option<i32> = Some(4);
+        let none: Option<i32> = None;
+        assert_eq!(
+            json!([-1, v.len(), 1 + 2, some, none, "a".to_uppercase()]),
+            Json::Array(vec![
+                Json::Number(-1.0),
+                Json::Number(3.0),
+                Json::Number(3.0),
+                Json::Number(4.0),
+                Json::Null,
+                Json::Strings("A".to_string()),
+            ])
+        );
+        assert_eq!(
+            json!({"neg": -1, "len": v.len(), "sum": 1 + 2, "opt": none, "vec": v}),
+            object(vec![
+                ("neg", Json::Number(-1.0)),
+                ("len", Json::Number(3.0)),
+                ("sum", Json::Number(3.0)),
+                ("opt", Json::Null),
+                (
+                    "vec",
+                    Json::Array(vec![
+                        Json::Number(1.0),
+                        Json::Number(2.0),
+                        Json::Number(3.0)
+                    ])
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn keys_may_be_computed() {
+        let key = "b";
+        let i = 2;
+        assert_eq!(
+            json!({"a": 1, key: 2, (format!("item{}", i)): 3, i: 4}),
+            object(vec![
+                ("a", Json::Number(1.0)),
+                ("b", Json::Number(2.0)),
+                ("item2", Json::Number(3.0)),
+                ("2", Json::Number(4.0)),
+            ])
+        );
+    }
+
+    #[test]
+    fn trailing_commas_are_allowed() {
+        assert_eq!(json!([1,]), json!([1]));
+        assert_eq!(
+            json!([null, [true,], {"a": false,},]),
+            json!([null, [true], {"a": false}])
+        );
+        assert_eq!(json!({"a": [1,], "b": {},}), json!({"a": [1], "b": {}}));
+    }
+
+    #[test]
+    fn the_last_duplicate_key_wins() {
+        let json = json!({"a": 1, "b": 2, "a": 3});
+        assert_eq!(json["a"], Json::Number(3.0));
+        assert_eq!(json.as_object().unwrap().len(), 2);
+        assert_eq!(json.to_string(), r#"{"a":3,"b":2}"#);
+    }
+}
@@ -1,7 +1,10 @@
 #![allow(dead_code)]
+use std::process::ExitCode;
+
 use json_parser::Token;
+use json_parser::codegen::{self, CodegenOptions};
 use json_parser::lexen::Lexer;
-use json_parser::parsen::Parser;
+use json_parser::parsen::{Parser, ParserOptions};
 use json_parser::read_file;
 
 /// contains the array and object samples from the RFC8259
@@ -9,7 +12,57 @@ use json_parser::read_file;
 const SAMPLE_OBJECT_FILE: &str = "json_obj.txt";
 const SAMPLE_ARRAY_FILE: &str = "array.txt";
 
-fn main() {
+const USAGE: &str = "usage: json_parser [codegen [--root NAME] FILE...]";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None => {
+            demo();
+            ExitCode::SUCCESS
+        }
+        Some("codegen") => match run_codegen(&args[1..]) {
+            Ok(code) => {
+                print!("{}", code);
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                ExitCode::FAILURE
+            }
+        },
+        Some(_) => {
+            eprintln!("{}", USAGE);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Rust types for the sample documents in the files named by `args`.
+fn run_codegen(args: &[String]) -> Result<String, String> {
+    let mut options = CodegenOptions::default();
+    let mut samples = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--root" {
+            options.root = args.next().ok_or(USAGE)?.clone();
+            continue;
+        }
+        let read = read_file(arg).map_err(|err| format!("file read failure, {}", err))?;
+        let recovered =
+            Parser::from_string_recovering(read, ParserOptions::default()).parse_recovering();
+        if let Some(err) = recovered.errors.first() {
+            return Err(format!("{}: {}", arg, err));
+        }
+        samples.push(recovered.json);
+    }
+    if samples.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    Ok(codegen::generate(&samples, &options))
+}
+
+fn demo() {
     let text = "Hello \\u03BB world";
     println!("{text}");
     let read = match read_file(SAMPLE_ARRAY_FILE) {
@@ -0,0 +1,346 @@
+//! The member storage of `Json::Object`.
+//!
+//! `Map` keeps members in insertion order, so a parsed document is written
+//! back in the order it was read and `sort_keys` has something to sort.
+//! Lookups go through a hash index; the method names follow `HashMap`.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+use crate::parsen::Json;
+
+#[derive(Clone, Default)]
+pub struct Map {
+    entries: Vec<(String, Json)>,
+    index: HashMap<String, usize>,
+}
+
+impl Map {
+    pub fn new() -> Map {
+        Map::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Map {
+        Map {
+            entries: Vec::with_capacity(capacity),
+            index: HashMap::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index.contains_key(key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        self.index.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Json> {
+        let i = *self.index.get(key)?;
+        Some(&mut self.entries[i].1)
+    }
+
+    /// Inserts or replaces the member `key`. A replaced member keeps its
+    /// position; a new one goes last. Returns the replaced value.
+    pub fn insert(&mut self, key: String, value: Json) -> Option<Json> {
+        match self.index.get(&key) {
+            Some(&i) => Some(std::mem::replace(&mut self.entries[i].1, value)),
+            None => {
+                self.index.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Removes the member `key`, keeping the order of the others.
+    pub fn remove(&mut self, key: &str) -> Option<Json> {
+        let i = self.index.remove(key)?;
+        let (_, value) = self.entries.remove(i);
+        for position in self.index.values_mut() {
+            if *position > i {
+                *position -= 1;
+            }
+        }
+        Some(value)
+    }
+
+    /// Removes and returns the last member.
+    pub fn pop(&mut self) -> Option<(String, Json)> {
+        let (key, value) = self.entries.pop()?;
+        self.index.remove(&key);
+        Some((key, value))
+    }
+
+    pub fn entry(&mut self, key: impl Into<String>) -> Entry<'_> {
+        Entry {
+            map: self,
+            key: key.into(),
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Json> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut Json> {
+        self.entries.iter_mut().map(|(_, value)| value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Json)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&String, &mut Json)> {
+        self.entries.iter_mut().map(|(key, value)| (&*key, value))
+    }
+
+    /// Keeps only the members for which `keep` returns true.
+    pub fn retain<F: FnMut(&str, &mut Json) -> bool>(&mut self, mut keep: F) {
+        self.entries.retain_mut(|(key, value)| keep(key, value));
+        self.reindex();
+    }
+
+    /// Orders the members by key.
+    pub fn sort_keys(&mut self) {
+        self.entries.sort_by(|a, b| a.0.cmp(&b.0));
+        self.reindex();
+    }
+
+    /// Orders the members with `compare`, which sees each member as a
+    /// `(key, value)` pair. The sort is stable.
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut((&str, &Json), (&str, &Json)) -> Ordering,
+    {
+        self.entries
+            .sort_by(|a, b| compare((&a.0, &a.1), (&b.0, &b.1)));
+        self.reindex();
+    }
+
+    fn reindex(&mut self) {
+        self.index.clear();
+        for (i, (key, _)) in self.entries.iter().enumerate() {
+            self.index.insert(key.clone(), i);
+        }
+    }
+}
+
+/// A member of a `Map` that may or may not be present yet.
+pub struct Entry<'a> {
+    map: &'a mut Map,
+    key: String,
+}
+
+impl<'a> Entry<'a> {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn or_insert(self, default: Json) -> &'a mut Json {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> Json>(self, default: F) -> &'a mut Json {
+        if !self.map.contains_key(&self.key) {
+            self.map.insert(self.key.clone(), default());
+        }
+        self.map
+            .get_mut(&self.key)
+            .expect("entry was just inserted")
+    }
+
+    /// Inserts `Json::Null` if the member is missing.
+    pub fn or_default(self) -> &'a mut Json {
+        self.or_insert_with(|| Json::Null)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut Json)>(self, modify: F) -> Entry<'a> {
+        if let Some(value) = self.map.get_mut(&self.key) {
+            modify(value);
+        }
+        self
+    }
+}
+
+impl fmt::Debug for Map {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// Like `Json`'s: the same members, in any order.
+impl PartialEq for Map {
+    fn eq(&self, other: &Map) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl Eq for Map {}
+
+/// Panics if the member is missing.
+impl Index<&str> for Map {
+    type Output = Json;
+
+    fn index(&self, key: &str) -> &Json {
+        match self.get(key) {
+            Some(value) => value,
+            None => panic!("no member {:?} in object", key),
+        }
+    }
+}
+
+/// Panics if the member is missing.
+impl IndexMut<&str> for Map {
+    fn index_mut(&mut self, key: &str) -> &mut Json {
+        match self.get_mut(key) {
+            Some(value) => value,
+            None => panic!("no member {:?} in object", key),
+        }
+    }
+}
+
+impl FromIterator<(String, Json)> for Map {
+    fn from_iter<I: IntoIterator<Item = (String, Json)>>(iter: I) -> Map {
+        let mut map = Map::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl Extend<(String, Json)> for Map {
+    fn extend<I: IntoIterator<Item = (String, Json)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl IntoIterator for Map {
+    type Item = (String, Json);
+    type IntoIter = std::vec::IntoIter<(String, Json)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Map {
+    type Item = (&'a String, &'a Json);
+    type IntoIter = std::iter::Map<
+        std::slice::Iter<'a, (String, Json)>,
+        fn(&'a (String, Json)) -> (&'a String, &'a Json),
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+    use crate::parsen::Parser;
+
+    fn keys(map: &Map) -> Vec<&str> {
+        map.keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn members_keep_insertion_order() {
+        let mut map = Map::new();
+        for key in ["zeta", "alpha", "mid"] {
+            map.insert(key.to_string(), json!(null));
+        }
+        assert_eq!(keys(&map), ["zeta", "alpha", "mid"]);
+
+        // replacing keeps the position, a new key goes last
+        assert_eq!(map.insert("zeta".to_string(), json!(1)), Some(json!(null)));
+        map.insert("new".to_string(), json!(2));
+        assert_eq!(keys(&map), ["zeta", "alpha", "mid", "new"]);
+    }
+
+    #[test]
+    fn parsed_documents_are_written_in_source_order() {
+        let text = r#"{"b":1,"a":{"z":true,"y":null},"c":[]}"#;
+        let json = Parser::from_string(text.to_string()).parse();
+        assert_eq!(json.to_string(), text);
+    }
+
+    #[test]
+    fn remove_keeps_the_order_and_lookups_of_the_rest() {
+        let mut map: Map = ["a", "b", "c", "d"]
+            .iter()
+            .enumerate()
+            .map(|(i, key)| (key.to_string(), json!(i)))
+            .collect();
+        assert_eq!(map.remove("b"), Some(json!(1)));
+        assert_eq!(map.remove("b"), None);
+        assert_eq!(keys(&map), ["a", "c", "d"]);
+        assert_eq!(map["c"], json!(2));
+        assert_eq!(map["d"], json!(3));
+        assert_eq!(map.pop(), Some(("d".to_string(), json!(3))));
+        assert!(!map.contains_key("d"));
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn entry_inserts_or_updates() {
+        let mut map = Map::new();
+        *map.entry("count").or_insert(json!(0)) = json!(1);
+        map.entry("count")
+            .and_modify(|count| *count = json!(2))
+            .or_insert(json!(0));
+        map.entry("empty").or_default();
+        assert_eq!(map.entry("k").key(), "k");
+        assert_eq!(Json::from(map), json!({"count": 2, "empty": null}));
+    }
+
+    #[test]
+    fn retain_and_sorting_reindex() {
+        let mut map: Map = [("c", 3), ("a", 1), ("b", 2), ("d", 4)]
+            .into_iter()
+            .map(|(key, n)| (key.to_string(), json!(n)))
+            .collect();
+        map.retain(|key, _| key != "b");
+        assert_eq!(keys(&map), ["c", "a", "d"]);
+        assert_eq!(map["a"], json!(1));
+        map.sort_keys();
+        assert_eq!(keys(&map), ["a", "c", "d"]);
+        assert_eq!(map["d"], json!(4));
+        map.sort_by(|a, b| b.1.cmp(a.1));
+        assert_eq!(keys(&map), ["d", "c", "a"]);
+        assert_eq!(map["c"], json!(3));
+    }
+
+    #[test]
+    fn equality_ignores_order() {
+        let a: Map = [("x", 1), ("y", 2)]
+            .into_iter()
+            .map(|(key, n)| (key.to_string(), json!(n)))
+            .collect();
+        let b: Map = [("y", 2), ("x", 1)]
+            .into_iter()
+            .map(|(key, n)| (key.to_string(), json!(n)))
+            .collect();
+        assert_eq!(a, b);
+    }
+}
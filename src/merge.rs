@@ -0,0 +1,294 @@
+//! Three-way merge of `Json` documents.
+//!
+//! `merge3` takes a common ancestor and two edited copies of it. A change
+//! made on only one side is taken as is; objects edited on both sides are
+//! merged key by key, so unrelated edits inside the same nested object do
+//! not collide. Arrays and scalars are merged as a whole: if both sides
+//! changed them differently the result is a conflict.
+
+use crate::diff::{DiffOptions, diff};
+use crate::map::Map;
+use crate::parsen::Json;
+use crate::pointer;
+
+/// Key of the object written in place of a conflicting value when merging
+/// with `ConflictStyle::Markers`.
+pub const CONFLICT_KEY: &str = "$conflict";
+
+/// What to put in the merged document where the two sides conflict.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictStyle {
+    /// Keep our value.
+    Ours,
+    /// Keep their value.
+    Theirs,
+    /// Write `{"$conflict": {"base": .., "ours": .., "theirs": ..}}`. A side
+    /// that deleted the value is left out of the inner object.
+    Markers,
+}
+
+#[derive(Debug, Clone)]
+pub struct MergeOptions {
+    pub style: ConflictStyle,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        Self {
+            style: ConflictStyle::Ours,
+        }
+    }
+}
+
+/// An overlapping edit. `None` means the value is absent on that side.
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub path: String,
+    pub base: Option<Json>,
+    pub ours: Option<Json>,
+    pub theirs: Option<Json>,
+}
+
+impl Conflict {
+    /// The `$conflict` object describing this conflict.
+    pub fn to_marker(&self) -> Json {
+        let mut sides = Map::new();
+        for (name, side) in [
+            ("base", &self.base),
+            ("ours", &self.ours),
+            ("theirs", &self.theirs),
+        ] {
+            if let Some(json) = side {
+                sides.insert(name.to_string(), json.clone());
+            }
+        }
+        let mut marker = Map::new();
+        marker.insert(CONFLICT_KEY.to_string(), Json::Object(Box::new(sides)));
+        Json::Object(Box::new(marker))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Merged {
+    pub value: Json,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Merged {
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Merges `ours` and `theirs`, both derived from `base`, keeping our side of
+/// any conflict.
+pub fn merge3(base: &Json, ours: &Json, theirs: &Json) -> Merged {
+    merge3_with(base, ours, theirs, &MergeOptions::default())
+}
+
+pub fn merge3_with(base: &Json, ours: &Json, theirs: &Json, options: &MergeOptions) -> Merged {
+    let mut conflicts = Vec::new();
+    let value = merge_value(
+        "",
+        Some(base),
+        Some(ours),
+        Some(theirs),
+        options,
+        &mut conflicts,
+    )
+    .unwrap_or(Json::Null);
+    Merged { value, conflicts }
+}
+
+fn same(left: Option<&Json>, right: Option<&Json>) -> bool {
+    match (left, right) {
+        (Some(l), Some(r)) => diff(l, r, &DiffOptions::default()).is_empty(),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn merge_value(
+    path: &str,
+    base: Option<&Json>,
+    ours: Option<&Json>,
+    theirs: Option<&Json>,
+    options: &MergeOptions,
+    conflicts: &mut Vec<Conflict>,
+) -> Option<Json> {
+    if same(ours, theirs) || same(base, theirs) {
+        return ours.cloned();
+    }
+    if same(base, ours) {
+        return theirs.cloned();
+    }
+
+    // Both sides changed the value. Objects can still be merged key by key,
+    // including objects added on both sides with no common ancestor.
+    if let (Some(Json::Object(o)), Some(Json::Object(t))) = (ours, theirs) {
+        let empty = Map::new();
+        let b = match base {
+            Some(Json::Object(b)) => Some(b.as_ref()),
+            None => Some(&empty),
+            _ => None,
+        };
+        if let Some(b) = b {
+            return Some(merge_objects(path, b, o, t, options, conflicts));
+        }
+    }
+
+    let conflict = Conflict {
+        path: path.to_string(),
+        base: base.cloned(),
+        ours: ours.cloned(),
+        theirs: theirs.cloned(),
+    };
+    let resolved = match options.style {
+        ConflictStyle::Ours => ours.cloned(),
+        ConflictStyle::Theirs => theirs.cloned(),
+        ConflictStyle::Markers => Some(conflict.to_marker()),
+    };
+    conflicts.push(conflict);
+    resolved
+}
+
+fn merge_objects(
+    path: &str,
+    base: &Map,
+    ours: &Map,
+    theirs: &Map,
+    options: &MergeOptions,
+    conflicts: &mut Vec<Conflict>,
+) -> Json {
+    let mut keys: Vec<&String> = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut merged = Map::new();
+    for key in keys {
+        let child = pointer::push(path, key);
+        let value = merge_value(
+            &child,
+            base.get(key),
+            ours.get(key),
+            theirs.get(key),
+            options,
+            conflicts,
+        );
+        if let Some(value) = value {
+            merged.insert(key.clone(), value);
+        }
+    }
+    Json::Object(Box::new(merged))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn one_sided_changes_are_taken() {
+        let base = json!({"a": 1, "b": 2, "c": 3});
+        let ours = json!({"a": 10, "b": 2, "c": 3});
+        let theirs = json!({"a": 1, "b": 2, "d": 4});
+        let merged = merge3(&base, &ours, &theirs);
+        assert!(merged.is_clean());
+        assert_eq!(merged.value, json!({"a": 10, "b": 2, "d": 4}));
+    }
+
+    #[test]
+    fn nested_objects_merge_key_by_key() {
+        let base = json!({"server": {"host": "localhost", "port": 80}});
+        let ours = json!({"server": {"host": "example.com", "port": 80}});
+        let theirs = json!({"server": {"host": "localhost", "port": 8080}});
+        let merged = merge3(&base, &ours, &theirs);
+        assert!(merged.is_clean());
+        assert_eq!(
+            merged.value,
+            json!({"server": {"host": "example.com", "port": 8080}})
+        );
+    }
+
+    #[test]
+    fn identical_changes_do_not_conflict() {
+        let base = json!({"tags": ["a"]});
+        let both = json!({"tags": ["a", "b"]});
+        let merged = merge3(&base, &both, &both);
+        assert!(merged.is_clean());
+        assert_eq!(merged.value, both);
+    }
+
+    #[test]
+    fn objects_added_on_both_sides_merge() {
+        let base = json!({});
+        let ours = json!({"new": {"x": 1}});
+        let theirs = json!({"new": {"y": 2}});
+        let merged = merge3(&base, &ours, &theirs);
+        assert!(merged.is_clean());
+        assert_eq!(merged.value, json!({"new": {"x": 1, "y": 2}}));
+    }
+
+    #[test]
+    fn arrays_conflict_as_a_whole() {
+        let base = json!({"list": [1, 2]});
+        let ours = json!({"list": [1, 2, 3]});
+        let theirs = json!({"list": [0, 1, 2]});
+        let merged = merge3(&base, &ours, &theirs);
+        assert_eq!(merged.conflicts.len(), 1);
+        let conflict = &merged.conflicts[0];
+        assert_eq!(conflict.path, "/list");
+        assert_eq!(conflict.base, Some(json!([1, 2])));
+        assert_eq!(conflict.ours, Some(json!([1, 2, 3])));
+        assert_eq!(conflict.theirs, Some(json!([0, 1, 2])));
+        assert_eq!(merged.value, ours);
+    }
+
+    #[test]
+    fn conflict_styles_pick_the_resolved_value() {
+        let base = json!({"v": 1});
+        let ours = json!({"v": 2});
+        let theirs = json!({"v": 3});
+        let with = |style| merge3_with(&base, &ours, &theirs, &MergeOptions { style }).value;
+        assert_eq!(with(ConflictStyle::Ours), json!({"v": 2}));
+        assert_eq!(with(ConflictStyle::Theirs), json!({"v": 3}));
+        assert_eq!(
+            with(ConflictStyle::Markers),
+            json!({"v": {"$conflict": {"base": 1, "ours": 2, "theirs": 3}}})
+        );
+    }
+
+    #[test]
+    fn delete_against_edit_conflicts() {
+        let base = json!({"k": 1});
+        let ours = json!({});
+        let theirs = json!({"k": 2});
+        let merged = merge3_with(
+            &base,
+            &ours,
+            &theirs,
+            &MergeOptions {
+                style: ConflictStyle::Markers,
+            },
+        );
+        assert_eq!(merged.conflicts.len(), 1);
+        assert_eq!(merged.conflicts[0].ours, None);
+        // the side that deleted the value is left out of the marker
+        assert_eq!(
+            merged.value,
+            json!({"k": {"$conflict": {"base": 1, "theirs": 2}}})
+        );
+    }
+
+    #[test]
+    fn keys_are_escaped_in_conflict_paths() {
+        let base = json!({"a/b": 1});
+        let ours = json!({"a/b": 2});
+        let theirs = json!({"a/b": 3});
+        assert_eq!(merge3(&base, &ours, &theirs).conflicts[0].path, "/a~1b");
+    }
+}
@@ -1,60 +1,63 @@
 #![allow(dead_code)]
 use core::panic;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Index, IndexMut, Range};
 
 use crate::HashMap;
 use crate::Kind;
 use crate::Token;
 use crate::lexen::Lexer;
+use crate::map::{Entry, Map};
+use crate::pointer;
+use crate::source_map::SourceMap;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Json {
     Null,
     Boolean(bool),
     Number(f64),
     Strings(String),
     Array(Vec<Json>),
-    Object(Box<HashMap<String, Json>>),
+    Object(Box<Map>),
 }
 
 impl Json {
      pub fn is_string(&self) -> bool {
-         return matches!(self, Json::Strings(_))
+         matches!(self, Json::Strings(_))
      }
  
      pub fn is_boolean(&self) -> bool {
-         return matches!(self, Json::Boolean(_))
+         matches!(self, Json::Boolean(_))
      }
  
      pub fn is_number(&self) -> bool {
-         return matches!(self, Json::Boolean(_))
+         matches!(self, Json::Number(_))
      }
 
      pub fn is_array(&self) -> bool {
-         return matches!(self, Json::Array(_))
+         matches!(self, Json::Array(_))
      }
  
      pub fn is_object(&self) -> bool {
-         return matches!(self, Json::Object(_))
+         matches!(self, Json::Object(_))
      }
  
      pub fn is_null_or_empty(&self) -> bool {
-         return matches!(self, Json::Null)
+         matches!(self, Json::Null)
      }
  
+     /// Strings without their quotes; every other value as compact JSON text.
      pub fn as_text(&self) -> String {
-         if matches!(self, Json::Boolean(_) | Json::Strings(_) | Json::Number(_)) {
-             match self {
-                 Json::Boolean(b) => b.to_string(),
-                 Json::Number(num) => num.to_string(),  
-                 Json::Strings(st) => st.clone(),  
-                 Json::Null => "null".to_string(),  
-                 _ => panic!("only primitive types can be converted to text")
-             }
-         } else {
-             "".to_string()
+         match self {
+             Json::Strings(st) => st.clone(),
+             _ => self.to_string(),
          }
      }
  
+     /// The member `name`, or `Json::Null` when it is missing. Use `get` to
+     /// tell a missing member from an explicit null.
     pub fn path(&self, name: &str) -> &Json {
             match self {
             Json::Object(map) => {
@@ -64,10 +67,12 @@ impl Json {
                     &Json::Null
                 }
             }
-            _ => return &Json::Null,
+            _ => &Json::Null,
         }
      }
  
+     /// The element at `index`, or `Json::Null` when it is out of bounds.
+     /// Use `get_index` to tell a missing element from an explicit null.
      pub fn at_index(&self, index: usize) -> &Json {
         match self {
             Json::Array(vec) => {
@@ -77,12 +82,975 @@ impl Json {
                     &Json::Null
                 }
             }
-            _ => return &Json::Null,
+            _ => &Json::Null,
+        }
+     }
+ }
+
+
+/// The type of a `Json` value, as named by RFC 8259.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JsonType {
+    Null,
+    Boolean,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl fmt::Display for JsonType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            JsonType::Null => "null",
+            JsonType::Boolean => "boolean",
+            JsonType::Number => "number",
+            JsonType::String => "string",
+            JsonType::Array => "array",
+            JsonType::Object => "object",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Why a `try_` accessor could not return the requested value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccessError {
+    WrongType {
+        expected: JsonType,
+        found: JsonType,
+    },
+    /// A number that is fractional or outside the range of `i64`.
+    NotAnInteger(f64),
+    MissingKey(String),
+    IndexOutOfBounds {
+        index: usize,
+        len: usize,
+    },
+}
+
+impl fmt::Display for AccessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AccessError::WrongType { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+            AccessError::NotAnInteger(num) => write!(f, "expected integer, found {}", num),
+            AccessError::MissingKey(key) => write!(f, "missing key {:?}", key),
+            AccessError::IndexOutOfBounds { index, len } => {
+                write!(
+                    f,
+                    "index {} out of bounds for array of length {}",
+                    index, len
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for AccessError {}
+
+/// Typed accessors. The plain forms return `None` when the value has a
+/// different type (or the key or index is missing); the `try_` forms say why.
+impl Json {
+    pub fn json_type(&self) -> JsonType {
+        match self {
+            Json::Null => JsonType::Null,
+            Json::Boolean(_) => JsonType::Boolean,
+            Json::Number(_) => JsonType::Number,
+            Json::Strings(_) => JsonType::String,
+            Json::Array(_) => JsonType::Array,
+            Json::Object(_) => JsonType::Object,
+        }
+    }
+
+    fn wrong_type(&self, expected: JsonType) -> AccessError {
+        AccessError::WrongType {
+            expected,
+            found: self.json_type(),
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        self.try_as_str().ok()
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        self.try_as_bool().ok()
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        self.try_as_f64().ok()
+    }
+
+    /// The number as an `i64`, if it has no fractional part and fits.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.try_as_i64().ok()
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        self.try_as_array().ok()
+    }
+
+    pub fn as_object(&self) -> Option<&Map> {
+        self.try_as_object().ok()
+    }
+
+    /// The member `key` of an object.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        self.try_get(key).ok()
+    }
+
+    /// The element at `index` of an array.
+    pub fn get_index(&self, index: usize) -> Option<&Json> {
+        self.try_get_index(index).ok()
+    }
+
+    pub fn try_as_str(&self) -> Result<&str, AccessError> {
+        match self {
+            Json::Strings(st) => Ok(st),
+            _ => Err(self.wrong_type(JsonType::String)),
+        }
+    }
+
+    pub fn try_as_bool(&self) -> Result<bool, AccessError> {
+        match self {
+            Json::Boolean(b) => Ok(*b),
+            _ => Err(self.wrong_type(JsonType::Boolean)),
+        }
+    }
+
+    pub fn try_as_f64(&self) -> Result<f64, AccessError> {
+        match self {
+            Json::Number(num) => Ok(*num),
+            _ => Err(self.wrong_type(JsonType::Number)),
+        }
+    }
+
+    pub fn try_as_i64(&self) -> Result<i64, AccessError> {
+        let num = self.try_as_f64()?;
+        // i64::MAX as f64 rounds up to 2^63, which is already out of range.
+        if num.fract() == 0.0 && num >= i64::MIN as f64 && num < i64::MAX as f64 {
+            Ok(num as i64)
+        } else {
+            Err(AccessError::NotAnInteger(num))
+        }
+    }
+
+    pub fn try_as_array(&self) -> Result<&Vec<Json>, AccessError> {
+        match self {
+            Json::Array(vec) => Ok(vec),
+            _ => Err(self.wrong_type(JsonType::Array)),
+        }
+    }
+
+    pub fn try_as_object(&self) -> Result<&Map, AccessError> {
+        match self {
+            Json::Object(map) => Ok(map),
+            _ => Err(self.wrong_type(JsonType::Object)),
+        }
+    }
+
+    pub fn try_get(&self, key: &str) -> Result<&Json, AccessError> {
+        self.try_as_object()?
+            .get(key)
+            .ok_or_else(|| AccessError::MissingKey(key.to_string()))
+    }
+
+    pub fn try_get_index(&self, index: usize) -> Result<&Json, AccessError> {
+        let vec = self.try_as_array()?;
+        vec.get(index).ok_or(AccessError::IndexOutOfBounds {
+            index,
+            len: vec.len(),
+        })
+    }
+
+    pub fn as_str_mut(&mut self) -> Option<&mut String> {
+        self.try_as_str_mut().ok()
+    }
+
+    pub fn as_bool_mut(&mut self) -> Option<&mut bool> {
+        self.try_as_bool_mut().ok()
+    }
+
+    /// Numbers are stored as `f64`, so there is no `as_i64_mut`.
+    pub fn as_f64_mut(&mut self) -> Option<&mut f64> {
+        self.try_as_f64_mut().ok()
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<Json>> {
+        self.try_as_array_mut().ok()
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut Map> {
+        self.try_as_object_mut().ok()
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Json> {
+        self.try_get_mut(key).ok()
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<&mut Json> {
+        self.try_get_index_mut(index).ok()
+    }
+
+    pub fn try_as_str_mut(&mut self) -> Result<&mut String, AccessError> {
+        match self {
+            Json::Strings(st) => Ok(st),
+            _ => Err(self.wrong_type(JsonType::String)),
+        }
+    }
+
+    pub fn try_as_bool_mut(&mut self) -> Result<&mut bool, AccessError> {
+        match self {
+            Json::Boolean(b) => Ok(b),
+            _ => Err(self.wrong_type(JsonType::Boolean)),
+        }
+    }
+
+    pub fn try_as_f64_mut(&mut self) -> Result<&mut f64, AccessError> {
+        match self {
+            Json::Number(num) => Ok(num),
+            _ => Err(self.wrong_type(JsonType::Number)),
+        }
+    }
+
+    pub fn try_as_array_mut(&mut self) -> Result<&mut Vec<Json>, AccessError> {
+        match self {
+            Json::Array(vec) => Ok(vec),
+            _ => Err(self.wrong_type(JsonType::Array)),
+        }
+    }
+
+    pub fn try_as_object_mut(&mut self) -> Result<&mut Map, AccessError> {
+        match self {
+            Json::Object(map) => Ok(map),
+            _ => Err(self.wrong_type(JsonType::Object)),
+        }
+    }
+
+    pub fn try_get_mut(&mut self, key: &str) -> Result<&mut Json, AccessError> {
+        self.try_as_object_mut()?
+            .get_mut(key)
+            .ok_or_else(|| AccessError::MissingKey(key.to_string()))
+    }
+
+    pub fn try_get_index_mut(&mut self, index: usize) -> Result<&mut Json, AccessError> {
+        let vec = self.try_as_array_mut()?;
+        let len = vec.len();
+        vec.get_mut(index)
+            .ok_or(AccessError::IndexOutOfBounds { index, len })
+    }
+}
+
+/// Shared sentinel returned by `Index` for missing values.
+static NULL: Json = Json::Null;
+
+/// `json[i]` on an array is its `i`th element. An index past the end, or
+/// indexing a null, boolean, number, string or object, yields `Json::Null`.
+impl Index<usize> for Json {
+    type Output = Json;
+
+    fn index(&self, index: usize) -> &Json {
+        match self {
+            Json::Array(vec) => vec.get(index).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+}
+
+/// `json["key"]` on an object is the member `key`. A missing member, or
+/// indexing a null, boolean, number, string or array, yields `Json::Null`.
+impl Index<&str> for Json {
+    type Output = Json;
+
+    fn index(&self, key: &str) -> &Json {
+        match self {
+            Json::Object(map) => map.get(key).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+}
+
+/// `json[i] = value` replaces the `i`th element of an array. Arrays are
+/// never grown or created by indexing; use `push` or `insert` for that.
+///
+/// # Panics
+///
+/// If `i` is out of bounds, or the value is not an array (a null included).
+/// `get_index_mut` is the non-panicking form.
+impl IndexMut<usize> for Json {
+    fn index_mut(&mut self, index: usize) -> &mut Json {
+        match self {
+            Json::Array(vec) => {
+                let len = vec.len();
+                match vec.get_mut(index) {
+                    Some(json) => json,
+                    None => panic!("index {} out of bounds for array of length {}", index, len),
+                }
+            }
+            _ => panic!("cannot index into {} with {}", self.json_type(), index),
+        }
+    }
+}
+
+/// `json["key"] = value` sets the member `key` of an object, inserting it
+/// as `Json::Null` first if it is missing. A `Json::Null` is turned into an
+/// empty object before the lookup, so `json["a"]["b"] = value` builds the
+/// nested objects it needs.
+///
+/// # Panics
+///
+/// On booleans, numbers, strings and arrays. `get_mut` is the non-panicking
+/// form.
+impl IndexMut<&str> for Json {
+    fn index_mut(&mut self, key: &str) -> &mut Json {
+        if let Json::Null = self {
+            *self = Json::Object(Box::default());
+        }
+        match self {
+            Json::Object(map) => map.entry(key.to_string()).or_insert(Json::Null),
+            _ => panic!("cannot index into {} with {:?}", self.json_type(), key),
+        }
+    }
+}
+
+/// A position in an object or array: a key (`&str`, `String`) or an index
+/// (`usize`). Lets `insert` and `remove` work on both kinds of container.
+pub trait Key {
+    fn insert_into(self, json: &mut Json, value: Json) -> Result<Option<Json>, AccessError>;
+    fn remove_from(self, json: &mut Json) -> Option<Json>;
+}
+
+impl Key for &str {
+    fn insert_into(self, json: &mut Json, value: Json) -> Result<Option<Json>, AccessError> {
+        if let Json::Null = json {
+            *json = Json::Object(Box::default());
+        }
+        Ok(json.try_as_object_mut()?.insert(self.to_string(), value))
+    }
+
+    fn remove_from(self, json: &mut Json) -> Option<Json> {
+        json.as_object_mut()?.remove(self)
+    }
+}
+
+impl Key for String {
+    fn insert_into(self, json: &mut Json, value: Json) -> Result<Option<Json>, AccessError> {
+        self.as_str().insert_into(json, value)
+    }
+
+    fn remove_from(self, json: &mut Json) -> Option<Json> {
+        self.as_str().remove_from(json)
+    }
+}
+
+impl Key for usize {
+    fn insert_into(self, json: &mut Json, value: Json) -> Result<Option<Json>, AccessError> {
+        let vec = json.try_as_array_mut()?;
+        if self > vec.len() {
+            return Err(AccessError::IndexOutOfBounds {
+                index: self,
+                len: vec.len(),
+            });
+        }
+        vec.insert(self, value);
+        Ok(None)
+    }
+
+    fn remove_from(self, json: &mut Json) -> Option<Json> {
+        let vec = json.as_array_mut()?;
+        if self < vec.len() {
+            Some(vec.remove(self))
+        } else {
+            None
+        }
+    }
+}
+
+/// `Json::Null`.
+impl Default for Json {
+    fn default() -> Json {
+        Json::Null
+    }
+}
+
+/// Editing. Methods that only make sense for one kind of container say so;
+/// those that change a tree in place leave other values untouched.
+impl Json {
+    /// With a key, sets a member of an object and returns the value it
+    /// replaced; a `Json::Null` becomes an empty object first. With an
+    /// index, inserts into an array at that position, shifting later
+    /// elements right.
+    pub fn insert<K: Key>(&mut self, at: K, value: Json) -> Result<Option<Json>, AccessError> {
+        at.insert_into(self, value)
+    }
+
+    /// Removes a member of an object or an element of an array. Returns
+    /// `None` if there was nothing to remove.
+    pub fn remove<K: Key>(&mut self, at: K) -> Option<Json> {
+        at.remove_from(self)
+    }
+
+    /// The member `key` of an object, for in-place insert or update. A
+    /// `Json::Null` becomes an empty object first.
+    pub fn entry(&mut self, key: &str) -> Result<Entry<'_>, AccessError> {
+        if let Json::Null = self {
+            *self = Json::Object(Box::default());
+        }
+        Ok(self.try_as_object_mut()?.entry(key))
+    }
+
+    /// Appends to an array. A `Json::Null` becomes an empty array first.
+    pub fn push(&mut self, value: Json) -> Result<(), AccessError> {
+        if let Json::Null = self {
+            *self = Json::Array(Vec::new());
+        }
+        self.try_as_array_mut()?.push(value);
+        Ok(())
+    }
+
+    /// Removes the last element of an array.
+    pub fn pop(&mut self) -> Option<Json> {
+        self.as_array_mut()?.pop()
+    }
+
+    /// Keeps the array elements, or object member values, for which `keep`
+    /// returns true.
+    pub fn retain<F: FnMut(&Json) -> bool>(&mut self, mut keep: F) {
+        match self {
+            Json::Array(vec) => vec.retain(|json| keep(json)),
+            Json::Object(map) => map.retain(|_, json| keep(json)),
+            _ => {}
+        }
+    }
+
+    /// Moves the value out, leaving `Json::Null` in its place.
+    pub fn take(&mut self) -> Json {
+        std::mem::take(self)
+    }
+
+    /// Puts `value` in place of this one and returns the old value.
+    pub fn replace(&mut self, value: Json) -> Json {
+        std::mem::replace(self, value)
+    }
+
+    /// Orders the members of every object in the tree by key.
+    pub fn sort_keys(&mut self) {
+        match self {
+            Json::Array(vec) => vec.iter_mut().for_each(Json::sort_keys),
+            Json::Object(map) => {
+                map.sort_keys();
+                map.values_mut().for_each(Json::sort_keys);
+            }
+            _ => {}
+        }
+    }
+
+    /// Orders the elements of an array, or the members of an object by
+    /// their values, with `compare`. The sort is stable.
+    pub fn sort_by<F: FnMut(&Json, &Json) -> Ordering>(&mut self, mut compare: F) {
+        match self {
+            Json::Array(vec) => vec.sort_by(compare),
+            Json::Object(map) => map.sort_by(|a, b| compare(a.1, b.1)),
+            _ => {}
+        }
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Json {
+        Json::Boolean(b)
+    }
+}
+
+macro_rules! from_number {
+    ($($t:ty)*) => {
+        $(
+            impl From<$t> for Json {
+                fn from(num: $t) -> Json {
+                    Json::Number(num as f64)
+                }
+            }
+        )*
+    };
+}
+
+from_number!(i8 i16 i32 i64 isize u8 u16 u32 u64 usize f32 f64);
+
+impl From<String> for Json {
+    fn from(st: String) -> Json {
+        Json::Strings(st)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(st: &str) -> Json {
+        Json::Strings(st.to_string())
+    }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(vec: Vec<Json>) -> Json {
+        Json::Array(vec)
+    }
+}
+
+impl From<Map> for Json {
+    fn from(map: Map) -> Json {
+        Json::Object(Box::new(map))
+    }
+}
+
+/// Members are added in key order, since a `HashMap` has none of its own.
+impl From<HashMap<String, Json>> for Json {
+    fn from(map: HashMap<String, Json>) -> Json {
+        let mut entries: Vec<(String, Json)> = map.into_iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Json::Object(Box::new(entries.into_iter().collect()))
+    }
+}
+
+/// Escaping beyond what JSON requires, for text that has to survive being
+/// embedded somewhere else. Every mode can be combined with the others.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Escaping {
+    /// `\uXXXX` for every non-ASCII character, as a surrogate pair above
+    /// U+FFFF, for systems that only accept ASCII. The hex digits are
+    /// lowercase, like every other escape this crate writes.
+    pub ascii_only: bool,
+    /// `<`, `>`, `&` and `'`, so the text can sit inside an HTML `<script>`
+    /// tag or attribute.
+    pub html: bool,
+    /// U+2028 and U+2029, which end a line in JavaScript before ES2019.
+    pub javascript: bool,
+}
+
+impl Escaping {
+    pub fn ascii_only() -> Escaping {
+        Escaping {
+            ascii_only: true,
+            ..Escaping::default()
+        }
+    }
+
+    pub fn html() -> Escaping {
+        Escaping {
+            html: true,
+            ..Escaping::default()
+        }
+    }
+
+    pub fn javascript() -> Escaping {
+        Escaping {
+            javascript: true,
+            ..Escaping::default()
+        }
+    }
+
+    fn needs_escape(&self, ch: char) -> bool {
+        (self.ascii_only && !ch.is_ascii())
+            || (self.html && matches!(ch, '<' | '>' | '&' | '\''))
+            || (self.javascript && matches!(ch, '\u{2028}' | '\u{2029}'))
+    }
+}
+
+/// Writes `s` as a quoted JSON string, escaping quotes, backslashes and
+/// control characters.
+pub(crate) fn write_escaped<W: fmt::Write>(out: &mut W, s: &str) -> fmt::Result {
+    write_escaped_with(out, s, Escaping::default())
+}
+
+/// `write_escaped`, plus whatever `escaping` asks for.
+pub(crate) fn write_escaped_with<W: fmt::Write>(
+    out: &mut W,
+    s: &str,
+    escaping: Escaping,
+) -> fmt::Result {
+    out.write_char('"')?;
+    for ch in s.chars() {
+        match ch {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\u{08}' => out.write_str("\\b")?,
+            '\u{0C}' => out.write_str("\\f")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            c if (c as u32) < 0x20 || escaping.needs_escape(c) => {
+                for unit in c.encode_utf16(&mut [0; 2]) {
+                    write!(out, "\\u{:04x}", unit)?;
+                }
+            }
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+/// Compact JSON text, with object members in their stored order.
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_compact(f, self, Escaping::default())
+    }
+}
+
+impl Json {
+    /// Compact JSON text like `to_string`, with extra escaping in strings
+    /// and keys.
+    pub fn to_string_escaped(&self, escaping: Escaping) -> String {
+        let mut out = String::new();
+        write_compact(&mut out, self, escaping).expect("writing to a String");
+        out
+    }
+}
+
+fn write_compact<W: fmt::Write>(out: &mut W, json: &Json, escaping: Escaping) -> fmt::Result {
+    match json {
+        Json::Null => out.write_str("null"),
+        Json::Boolean(b) => write!(out, "{}", b),
+        // JSON has no representation for NaN or the infinities.
+        Json::Number(num) if !num.is_finite() => out.write_str("null"),
+        Json::Number(num) => write!(out, "{}", num),
+        Json::Strings(st) => write_escaped_with(out, st, escaping),
+        Json::Array(vec) => {
+            out.write_str("[")?;
+            for (i, json) in vec.iter().enumerate() {
+                if i > 0 {
+                    out.write_str(",")?;
+                }
+                write_compact(out, json, escaping)?;
+            }
+            out.write_str("]")
+        }
+        Json::Object(map) => {
+            out.write_str("{")?;
+            for (i, (key, json)) in map.iter().enumerate() {
+                if i > 0 {
+                    out.write_str(",")?;
+                }
+                write_escaped_with(out, key, escaping)?;
+                out.write_str(":")?;
+                write_compact(out, json, escaping)?;
+            }
+            out.write_str("}")
+        }
+    }
+}
+
+/// How `Json::eq_by` compares two documents. Object key order never plays a
+/// part in any comparison: objects are maps, so `{"a":1,"b":2}` and
+/// `{"b":2,"a":1}` are always equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqPolicy {
+    /// The same as `==`: numbers must be bit-for-bit identical, so `-0` and
+    /// `0` differ.
+    Strict,
+    /// Numbers compare by value, so `1` equals `1.0` and `-0` equals `0`.
+    /// NaN still equals NaN, so that this stays an equivalence.
+    Numeric,
+}
+
+impl Json {
+    /// Position of the variant in the total order
+    /// null < bool < number < string < array < object.
+    fn rank(&self) -> u8 {
+        match self {
+            Json::Null => 0,
+            Json::Boolean(_) => 1,
+            Json::Number(_) => 2,
+            Json::Strings(_) => 3,
+            Json::Array(_) => 4,
+            Json::Object(_) => 5,
+        }
+    }
+
+    pub fn eq_by(&self, other: &Json, policy: EqPolicy) -> bool {
+        match (self, other) {
+            (Json::Number(a), Json::Number(b)) => match policy {
+                EqPolicy::Strict => a.total_cmp(b) == Ordering::Equal,
+                EqPolicy::Numeric => a == b || (a.is_nan() && b.is_nan()),
+            },
+            (Json::Array(a), Json::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| x.eq_by(y, policy))
+            }
+            (Json::Object(a), Json::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(key, x)| b.get(key).is_some_and(|y| x.eq_by(y, policy)))
+            }
+            _ => self == other,
+        }
+    }
+}
+
+fn sorted_entries(map: &Map) -> Vec<(&String, &Json)> {
+    let mut entries: Vec<(&String, &Json)> = map.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    entries
+}
+
+impl PartialEq for Json {
+    fn eq(&self, other: &Json) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Json {}
+
+impl PartialOrd for Json {
+    fn partial_cmp(&self, other: &Json) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Values of different types order as null < bool < number < string < array
+/// < object. Numbers use `f64::total_cmp`, arrays compare element by element
+/// and objects compare their entries sorted by key.
+impl Ord for Json {
+    fn cmp(&self, other: &Json) -> Ordering {
+        match (self, other) {
+            (Json::Null, Json::Null) => Ordering::Equal,
+            (Json::Boolean(a), Json::Boolean(b)) => a.cmp(b),
+            (Json::Number(a), Json::Number(b)) => a.total_cmp(b),
+            (Json::Strings(a), Json::Strings(b)) => a.cmp(b),
+            (Json::Array(a), Json::Array(b)) => a.cmp(b),
+            (Json::Object(a), Json::Object(b)) => sorted_entries(a).cmp(&sorted_entries(b)),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+/// Consistent with `Eq`: object entries are hashed in key order, so the hash
+/// does not depend on the map's iteration order.
+impl Hash for Json {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rank().hash(state);
+        match self {
+            Json::Null => {}
+            Json::Boolean(b) => b.hash(state),
+            Json::Number(num) => num.to_bits().hash(state),
+            Json::Strings(st) => st.hash(state),
+            Json::Array(vec) => vec.hash(state),
+            Json::Object(map) => sorted_entries(map).hash(state),
+        }
+    }
+}
+
+/// The flavour of JSON to accept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dialect {
+    /// Strict RFC 8259.
+    #[default]
+    Json,
+    /// JSON with `//` line and `/* */` block comments, as in VS Code
+    /// settings files.
+    Jsonc,
+    /// JSON5: comments, unquoted keys, single-quoted and multi-line
+    /// strings, trailing commas, hexadecimal numbers, leading and trailing
+    /// decimal points, `+` signs, `Infinity` and `NaN`.
+    Json5,
+}
+
+/// How text is lexed and parsed. The default is strict RFC 8259.
+///
+/// Beyond picking a dialect, each relaxation can be switched on by itself;
+/// JSON5 implies all of them.
+///
+/// ```
+/// use json_parser::parsen::{Json, Parser, ParserOptions};
+///
+/// let options = ParserOptions {
+///     allow_trailing_commas: true,
+///     allow_unquoted_keys: true,
+///     ..ParserOptions::default()
+/// };
+/// let json = Parser::from_string_with("{retries: 3,}".to_string(), options).parse();
+/// assert_eq!(json.get("retries"), Some(&Json::Number(3.0)));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParserOptions {
+    pub dialect: Dialect,
+    /// Emit comments as `Kind::Comment` tokens rather than dropping them.
+    /// `Parser` skips them either way; they are there for tools that want
+    /// the trivia.
+    pub keep_comments: bool,
+    /// `[1, 2,]` and `{"a": 1,}`.
+    pub allow_trailing_commas: bool,
+    /// `NaN`, `Infinity` and `-Infinity` as numbers, and numbers too large
+    /// for an `f64`, such as `1e999`, read as infinite.
+    pub allow_non_finite: bool,
+    /// A byte order mark before the document.
+    pub allow_leading_bom: bool,
+    /// `'strings'` as well as `"strings"`.
+    pub allow_single_quotes: bool,
+    /// Raw U+0000 to U+001F inside strings, e.g. a literal tab.
+    pub allow_control_characters: bool,
+    /// `{key: 1}`; keys must still look like identifiers.
+    pub allow_unquoted_keys: bool,
+    /// Have `Parser::parse` and `Parser::parse_recovering` record where each
+    /// value came from; see `Parser::source_map`.
+    pub source_map: bool,
+}
+
+impl ParserOptions {
+    pub fn jsonc() -> ParserOptions {
+        ParserOptions {
+            dialect: Dialect::Jsonc,
+            ..ParserOptions::default()
+        }
+    }
+
+    pub fn json5() -> ParserOptions {
+        ParserOptions {
+            dialect: Dialect::Json5,
+            ..ParserOptions::default()
+        }
+    }
+
+    fn is_json5(&self) -> bool {
+        self.dialect == Dialect::Json5
+    }
+
+    pub(crate) fn allows_comments(&self) -> bool {
+        matches!(self.dialect, Dialect::Jsonc | Dialect::Json5)
+    }
+
+    pub(crate) fn allows_trailing_commas(&self) -> bool {
+        self.allow_trailing_commas || self.is_json5()
+    }
+
+    /// `Infinity` and `NaN`, optionally signed.
+    pub(crate) fn allows_non_finite(&self) -> bool {
+        self.allow_non_finite || self.is_json5()
+    }
+
+    pub(crate) fn allows_leading_bom(&self) -> bool {
+        self.allow_leading_bom || self.is_json5()
+    }
+
+    pub(crate) fn allows_single_quotes(&self) -> bool {
+        self.allow_single_quotes || self.is_json5()
+    }
+
+    /// JSON5 takes any raw character in a string except a line break.
+    pub(crate) fn allows_control_character(&self, ch: char) -> bool {
+        self.allow_control_characters || (self.is_json5() && !matches!(ch, '\n' | '\r'))
+    }
+
+    pub(crate) fn allows_unquoted_keys(&self) -> bool {
+        self.allow_unquoted_keys || self.is_json5()
+    }
+
+    /// `+1`, `0x1F`, `.5` and `5.`.
+    pub(crate) fn allows_json5_numbers(&self) -> bool {
+        self.is_json5()
+    }
+
+    /// `\v`, `\0`, `\xHH` and line continuations.
+    pub(crate) fn allows_json5_escapes(&self) -> bool {
+        self.is_json5()
+    }
+
+    pub(crate) fn allows_json5_whitespace(&self) -> bool {
+        self.is_json5()
+    }
+}
+
+/// A problem found by `Parser::parse_recovering`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    message: String,
+    /// Zero-based line and column of the problem.
+    line: u32,
+    column: u32,
+    span: Range<usize>,
+    pointer: String,
+}
+
+impl ParseError {
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The one-based line the error was found on.
+    pub fn line(&self) -> u32 {
+        self.line + 1
+    }
+
+    /// The one-based column the error was found at, in characters.
+    pub fn column(&self) -> u32 {
+        self.column + 1
+    }
+
+    /// Byte range of the offending token, or of the malformed part of it,
+    /// e.g. a bad escape in a string.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// JSON Pointer to the value the error is in. When a value itself was
+    /// broken, this is the `null` standing in for it.
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+
+    /// Moves an error found in a piece of a larger text to where the piece
+    /// starts. The piece must start a line, so columns stay as they are.
+    pub(crate) fn shifted(mut self, lines: u32, bytes: usize) -> ParseError {
+        self.line += lines;
+        self.span = self.span.start + bytes..self.span.end + bytes;
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message,
+            self.line(),
+            self.column()
+        )?;
+        if !self.pointer.is_empty() {
+            write!(f, " (in {})", self.pointer)?;
         }
-     }
- }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// What `Parser::parse_recovering` made of a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recovered {
+    /// Everything that could be read; each broken value is `Json::Null`.
+    pub json: Json,
+    pub errors: Vec<ParseError>,
+    /// Where the broken values are, in document order, so that the `null`s
+    /// standing in for them can be told from real ones.
+    pub broken: Vec<BrokenValue>,
+}
+
+impl Recovered {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
 
+    /// Whether the value at `pointer` is a stand-in for a broken one.
+    pub fn is_broken(&self, pointer: &str) -> bool {
+        self.broken.iter().any(|broken| broken.pointer == pointer)
+    }
+}
 
+/// A value `Parser::parse_recovering` could not read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrokenValue {
+    pub pointer: String,
+    /// What was skipped in its place; empty when the value was missing.
+    pub span: Range<usize>,
+}
 
 /// Parsers job is to consume tokens, make sure that they adhere to the
 /// language's grammar and produces a Json result.
@@ -91,24 +1059,100 @@ pub struct Parser {
     tokens: Vec<Token>,
     pub start: usize,
     pub current: usize,
+    options: ParserOptions,
+    source_map: Option<SourceMap>,
+    // where the value being parsed sits, while building the source map
+    pointer: String,
+    key_span: Option<Range<usize>>,
+    // while recovering: the closing brackets of the open containers, the
+    // last token reported and the values given up on
+    closers: Vec<Kind>,
+    reported: Option<usize>,
+    broken: Vec<BrokenValue>,
 }
 
 impl Parser {
     pub fn new(_tokens: Vec<Token>) -> Self {
+        // comments are trivia and never part of the grammar
+        let _tokens = _tokens
+            .into_iter()
+            .filter(|token| !matches!(token.kind, Kind::Comment(_)))
+            .collect();
         Self {
             tokens: _tokens,
             start: 0,
             current: 0,
+            options: ParserOptions::default(),
+            source_map: None,
+            pointer: String::new(),
+            key_span: None,
+            closers: Vec::new(),
+            reported: None,
+            broken: Vec::new(),
         }
     }
 
     pub fn from_string(s: String) -> Self {
         let mut lexer = Lexer::new(s);
-        return Parser::new(lexer.lex());
+        Parser::new(lexer.lex())
+    }
+
+    pub fn from_string_with(s: String, options: ParserOptions) -> Self {
+        let mut lexer = Lexer::with_options(s, options);
+        let tokens = lexer.lex();
+        let source_map = options
+            .source_map
+            .then(|| SourceMap::new(std::mem::take(&mut lexer.content)));
+        Parser {
+            options,
+            source_map,
+            ..Parser::new(tokens)
+        }
+    }
+
+    /// Spans of every value read by `parse` or `parse_recovering`, when
+    /// asked for with `ParserOptions::source_map`.
+    pub fn source_map(&self) -> Option<&SourceMap> {
+        self.source_map.as_ref()
+    }
+
+    /// Like `from_string_with`, but malformed tokens are kept for
+    /// `parse_recovering` to report instead of panicking in the lexer.
+    /// The source map, if asked for, covers broken values too.
+    pub fn from_string_recovering(s: String, options: ParserOptions) -> Self {
+        let mut lexer = Lexer::with_options(s, options).recovering();
+        let tokens = lexer.lex();
+        let source_map = options
+            .source_map
+            .then(|| SourceMap::new(std::mem::take(&mut lexer.content)));
+        Parser {
+            options,
+            source_map,
+            ..Parser::new(tokens)
+        }
+    }
+
+    /// A recovering parser over `tokens`, already lexed from `source`, that
+    /// always builds a source map; `cst::Document` keeps the tokens for
+    /// itself.
+    pub(crate) fn mapped(source: String, tokens: Vec<Token>, options: ParserOptions) -> Self {
+        Parser {
+            options,
+            source_map: Some(SourceMap::new(source)),
+            ..Parser::new(tokens)
+        }
     }
 
     // member = string name-separator value
     pub fn parse(&mut self) -> crate::parsen::Json {
+        if self.source_map.is_some() {
+            self.parse_mapped()
+        } else {
+            self.parse_value()
+        }
+    }
+
+    fn parse_value(&mut self) -> Json {
         match self.peek() {
             Some(x) => match x.get_kind() {
                 Kind::BeginObject => self.parse_object(),
@@ -116,9 +1160,15 @@ impl Parser {
                 Kind::String(_) | Kind::Boolean(_) | Kind::Null | Kind::Number(_) => {
                     let json = self.resolve_type();
                     self.advance();
-                    return json;
+                    json
                 }
-                _ => panic!(),
+                // e.g. a JSON5 identifier, which may only name a key
+                found => panic!(
+                    "expected a value, found {} at line {}, column {}",
+                    found.describe(),
+                    x.get_line() + 1,
+                    x.get_column() + 1
+                ),
             },
             None => panic!(),
         }
@@ -127,27 +1177,29 @@ impl Parser {
     // object = begin-object [ member *( value-separator member ) ]
     //  end-object
     fn parse_object(&mut self) -> crate::parsen::Json {
-        let mut heap_map: Box<HashMap<String, Json>> = Box::new(HashMap::<String, Json>::new());
+        let mut heap_map: Box<Map> = Box::default();
         // consume expected {
         self.consume(Kind::BeginObject);
 
         while self.not_exhausted() {
             match self.peek() {
-                Some(ref token) => {
+                Some(token) => {
                     if token.get_kind().clone() == Kind::EndObject {
                         break;
                     } else {
                         // we must find a string here.
-                        match token.get_kind() {
-                            Kind::String(s) => {
-                                let s = s.clone();
+                        match self.key_name(token) {
+                            Some(s) => {
+                                let key_span = token.get_span();
                                 self.advance();
                                 self.consume(Kind::NameSeparator); 
                                 
-                                let json: Json = self.parse();
+                                let json: Json = self.member(&s, key_span);
                                 heap_map.insert(s, json);
                             }
-                            _ => panic!("expected string as key found {:?} instead", token.get_kind()),
+                            None => {
+                                panic!("expected string as key found {:?} instead", token.kind)
+                            }
                         }
                     }
                 },
@@ -158,6 +1210,7 @@ impl Parser {
                 break;
             } else {
                 self.advance(); // consume  valueSep(,)
+                self.check_trailing_comma(Kind::EndObject);
             }
         }
 
@@ -173,11 +1226,11 @@ impl Parser {
 
         while self.not_exhausted() {
             match self.peek() {
-                Some(ref token) => {
+                Some(token) => {
                     if token.get_kind().clone() == Kind::EndArray {
                         break;
                     } else {
-                        let json: Json = self.parse();
+                        let json: Json = self.element(v.len());
                         v.push(json);
                     };
                 }
@@ -189,12 +1242,70 @@ impl Parser {
                 break;
             } else {
                 self.advance(); 
+                self.check_trailing_comma(Kind::EndArray);
             }
         }
         self.consume(Kind::EndArray);
         Json::Array(v)
     }
 
+    // parse, noting the spans of the value on the way
+    fn parse_mapped(&mut self) -> Json {
+        let key = self.key_span.take();
+        let start = self.peek().map_or(0, |token| token.get_span().start);
+        let idx = match self.source_map.as_mut() {
+            Some(map) => map.open(&self.pointer, key, start),
+            None => unreachable!("only called while building a source map"),
+        };
+        let json = self.parse_value();
+        let end = self.previous().map_or(start, |token| token.get_span().end);
+        if let Some(map) = self.source_map.as_mut() {
+            map.close(idx, end);
+        }
+        json
+    }
+
+    fn member(&mut self, key: &str, key_span: Range<usize>) -> Json {
+        if self.source_map.is_none() {
+            return self.parse();
+        }
+        let len = self.pointer.len();
+        self.pointer = pointer::push(&self.pointer, key);
+        self.key_span = Some(key_span);
+        let json = self.parse();
+        self.pointer.truncate(len);
+        json
+    }
+
+    fn element(&mut self, idx: usize) -> Json {
+        if self.source_map.is_none() {
+            return self.parse();
+        }
+        let len = self.pointer.len();
+        self.pointer = pointer::push(&self.pointer, &idx.to_string());
+        let json = self.parse();
+        self.pointer.truncate(len);
+        json
+    }
+
+    // the name of a member whose key is `token`: a string, or with unquoted
+    // keys an identifier, which may also be a keyword such as `null` or
+    // `NaN`
+    fn key_name(&self, token: &Token) -> Option<String> {
+        let name = match &token.kind {
+            Kind::String(s) | Kind::Identifier(s) => return Some(s.clone()),
+            _ if !self.options.allows_unquoted_keys() => return None,
+            Kind::Null => "null",
+            Kind::Boolean(true) => "true",
+            Kind::Boolean(false) => "false",
+            Kind::Number(num) if *num == f64::INFINITY => "Infinity",
+            Kind::Number(num) if num.is_nan() => "NaN",
+            _ => return None,
+        };
+        // not `+Infinity` or `-NaN`
+        (token.get_span().len() == name.len()).then(|| name.to_string())
+    }
+
     fn resolve_type(&self) -> crate::parsen::Json {
         match self.peek() {
             Some(token) => {
@@ -210,6 +1321,13 @@ impl Parser {
         }
     }
 
+    // a `,` straight before the closing bracket
+    fn check_trailing_comma(&self, end: Kind) {
+        if self.expect_to_find(end) && !self.options.allows_trailing_commas() {
+            panic!("trailing comma before {:?}", self.peek());
+        }
+    }
+
     fn consume(&mut self, check: Kind) {
         let b: bool = self.expect_to_find(check);
         if b {
@@ -220,21 +1338,21 @@ impl Parser {
     }
 
     fn expect_to_find(&self, check: Kind) -> bool {
-        if let Some(ref kd) = self.peek() {
-            if kd.get_kind() == check { true } else { false }
+        if let Some(kd) = self.peek() {
+            kd.get_kind() == check
         } else {
             // exhausted input
-            return false;
+            false
         }
     }
 
     fn not_exhausted(&self) -> bool {
-        return self
+        self
             .tokens
             .get(self.current)
             .expect("Illegal state. Tokens exhausted;")
             .get_kind()
-            != Kind::EOF;
+            != Kind::EOF
     }
 
     fn peek(&self) -> Option<&Token> {
@@ -252,3 +1370,869 @@ impl Parser {
     }
 }
 
+/// Recovery mode: on an error the parser records it, skips ahead to the
+/// next `,`, `}` or `]` and carries on, so one pass finds every problem.
+/// Each token is reported at most once.
+impl Parser {
+    /// Parses the whole document without stopping at the first problem.
+    /// Broken values are left as `null` in the returned tree, each with an
+    /// error pointing at it, and listed in `Recovered::broken`.
+    ///
+    /// ```
+    /// use json_parser::parsen::{Json, Parser, ParserOptions};
+    ///
+    /// let text = r#"{"name": "tile", "size": @, "tags": ["a" "b"], "note": null}"#;
+    /// let recovered =
+    ///     Parser::from_string_recovering(text.to_string(), ParserOptions::default())
+    ///         .parse_recovering();
+    /// assert_eq!(recovered.json["name"], Json::from("tile"));
+    /// assert_eq!(recovered.json["size"], Json::Null);
+    /// assert!(recovered.is_broken("/size"));
+    /// assert!(!recovered.is_broken("/note"));
+    /// let pointers: Vec<&str> = recovered.errors.iter().map(|e| e.pointer()).collect();
+    /// assert_eq!(pointers, ["/size", "/tags"]);
+    /// assert_eq!(
+    ///     recovered.errors[0].to_string(),
+    ///     "unexpected character `@` at line 1, column 26 (in /size)"
+    /// );
+    /// ```
+    pub fn parse_recovering(&mut self) -> Recovered {
+        let mut errors = Vec::new();
+        let json = self.recover("", &mut errors);
+        if *self.kind_here() != Kind::EOF {
+            let found = self.kind_here().describe();
+            self.report(
+                &mut errors,
+                "",
+                format!("unexpected {} after the document", found),
+            );
+        }
+        Recovered {
+            json,
+            errors,
+            broken: std::mem::take(&mut self.broken),
+        }
+    }
+
+    fn recover(&mut self, pointer: &str, errors: &mut Vec<ParseError>) -> Json {
+        if self.source_map.is_some() {
+            self.recover_mapped(pointer, errors)
+        } else {
+            self.recover_value(pointer, errors)
+        }
+    }
+
+    // recover, noting the spans of the value on the way
+    fn recover_mapped(&mut self, pointer: &str, errors: &mut Vec<ParseError>) -> Json {
+        let key = self.key_span.take();
+        let start = self.span_here().start;
+        let idx = match self.source_map.as_mut() {
+            Some(map) => map.open(pointer, key, start),
+            None => unreachable!("only called while building a source map"),
+        };
+        let json = self.recover_value(pointer, errors);
+        // a value given up on before any token of it was taken is empty
+        let end = match self.current {
+            0 => start,
+            _ => self.previous().map_or(start, |token| token.get_span().end),
+        };
+        if let Some(map) = self.source_map.as_mut() {
+            map.close(idx, end.max(start));
+        }
+        json
+    }
+
+    fn recover_value(&mut self, pointer: &str, errors: &mut Vec<ParseError>) -> Json {
+        // a closing bracket nothing is open for is skipped, and stands for
+        // the missing value
+        let start = self.span_here().start;
+        let mut skipped = false;
+        while self.at_stray_closer() {
+            self.report_found(errors, pointer, "a value");
+            self.advance();
+            skipped = true;
+        }
+        match self.kind_here() {
+            Kind::BeginObject => self.recover_object(pointer, errors),
+            Kind::BeginArray => self.recover_array(pointer, errors),
+            Kind::String(_) | Kind::Boolean(_) | Kind::Null | Kind::Number(_) => {
+                let json = self.resolve_type();
+                self.advance();
+                json
+            }
+            // leave a separator or closing bracket for the enclosing
+            // container to resynchronize on
+            Kind::ValueSeparator | Kind::EndObject | Kind::EndArray | Kind::EOF => {
+                if !skipped {
+                    self.report_found(errors, pointer, "a value");
+                }
+                let end = if skipped { self.span_here().start } else { start };
+                self.give_up(pointer, start..end)
+            }
+            _ => {
+                self.report_found(errors, pointer, "a value");
+                let span = self.span_here();
+                self.advance();
+                self.give_up(pointer, span)
+            }
+        }
+    }
+
+    fn recover_object(&mut self, pointer: &str, errors: &mut Vec<ParseError>) -> Json {
+        let mut heap_map: Box<Map> = Box::default();
+        self.advance(); // consume {
+        if *self.kind_here() == Kind::EndObject {
+            self.advance();
+            return Json::Object(heap_map);
+        }
+
+        self.closers.push(Kind::EndObject);
+        loop {
+            match self.peek().and_then(|token| self.key_name(token)) {
+                Some(key) => {
+                    let member = pointer::push(pointer, &key);
+                    let key_span = self.span_here();
+                    self.advance();
+                    let json = if *self.kind_here() == Kind::NameSeparator {
+                        self.advance();
+                        self.key_span = Some(key_span);
+                        self.recover(&member, errors)
+                    } else {
+                        self.report_found(errors, &member, "`:`");
+                        // `{"a" 1}` still has a value worth reading
+                        if starts_value(self.kind_here()) {
+                            self.key_span = Some(key_span);
+                            self.recover(&member, errors)
+                        } else {
+                            let at = self.span_here().start;
+                            self.give_up(&member, at..at)
+                        }
+                    };
+                    heap_map.insert(key, json);
+                }
+                None => {
+                    self.report_found(errors, pointer, "string as key");
+                    self.synchronize(pointer, errors);
+                }
+            }
+
+            if !self.recover_separator(Kind::EndObject, pointer, errors) {
+                break;
+            }
+        }
+        self.closers.pop();
+        Json::Object(heap_map)
+    }
+
+    fn recover_array(&mut self, pointer: &str, errors: &mut Vec<ParseError>) -> Json {
+        let mut v = Vec::<Json>::new();
+        self.advance(); // consume [
+        if *self.kind_here() == Kind::EndArray {
+            self.advance();
+            return Json::Array(v);
+        }
+
+        self.closers.push(Kind::EndArray);
+        loop {
+            let element = pointer::push(pointer, &v.len().to_string());
+            let json = self.recover(&element, errors);
+            v.push(json);
+
+            if !self.recover_separator(Kind::EndArray, pointer, errors) {
+                break;
+            }
+        }
+        self.closers.pop();
+        Json::Array(v)
+    }
+
+    // after a member or element: true when another one follows, false once
+    // the container is closed or given up on
+    fn recover_separator(
+        &mut self,
+        end: Kind,
+        pointer: &str,
+        errors: &mut Vec<ParseError>,
+    ) -> bool {
+        let expected = format!("`,` or {}", end.describe());
+        loop {
+            match self.kind_here() {
+                Kind::ValueSeparator => {
+                    self.advance();
+                    if *self.kind_here() != end {
+                        return true;
+                    }
+                    if !self.options.allows_trailing_commas() {
+                        let message = format!("trailing comma before {}", end.describe());
+                        self.report(errors, pointer, message);
+                    }
+                    self.advance();
+                    return false;
+                }
+                kind if *kind == end => {
+                    self.advance();
+                    return false;
+                }
+                // a bracket nothing is open for is skipped
+                Kind::EndObject | Kind::EndArray if self.at_stray_closer() => {
+                    self.report_found(errors, pointer, &expected);
+                    self.advance();
+                }
+                // the container was never closed; the other bracket belongs
+                // to whoever opened it
+                Kind::EndObject | Kind::EndArray | Kind::EOF => {
+                    self.report_found(errors, pointer, &expected);
+                    return false;
+                }
+                _ => {
+                    self.report_found(errors, pointer, &expected);
+                    self.synchronize(pointer, errors);
+                }
+            }
+        }
+    }
+
+    // skips the token just reported and everything up to the next `,`, `}`
+    // or `]` at this depth, stepping over whole nested containers but
+    // reporting any further malformed tokens on the way
+    fn synchronize(&mut self, pointer: &str, errors: &mut Vec<ParseError>) {
+        let mut depth = 0usize;
+        loop {
+            match self.kind_here() {
+                Kind::EOF => return,
+                Kind::ValueSeparator | Kind::EndObject | Kind::EndArray if depth == 0 => return,
+                Kind::BeginObject | Kind::BeginArray => depth += 1,
+                Kind::EndObject | Kind::EndArray => depth -= 1,
+                Kind::Error(message) => {
+                    let message = message.clone();
+                    self.report(errors, pointer, message);
+                }
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+
+    fn kind_here(&self) -> &Kind {
+        self.peek().map_or(&Kind::EOF, |token| &token.kind)
+    }
+
+    fn span_here(&self) -> Range<usize> {
+        self.peek().map_or(0..0, Token::get_span)
+    }
+
+    // a `}` or `]` that closes none of the containers being read
+    fn at_stray_closer(&self) -> bool {
+        let kind = self.kind_here();
+        matches!(kind, Kind::EndObject | Kind::EndArray) && !self.closers.contains(kind)
+    }
+
+    // a broken value, standing in as `null`
+    fn give_up(&mut self, pointer: &str, span: Range<usize>) -> Json {
+        self.broken.push(BrokenValue {
+            pointer: pointer.to_string(),
+            span,
+        });
+        Json::Null
+    }
+
+    // a malformed token speaks for itself; anything else was unexpected
+    fn report_found(&mut self, errors: &mut Vec<ParseError>, pointer: &str, expected: &str) {
+        let message = match self.kind_here() {
+            Kind::Error(message) => message.clone(),
+            found => format!("expected {}, found {}", expected, found.describe()),
+        };
+        self.report(errors, pointer, message);
+    }
+
+    // records an error at the current token, unless it was reported already
+    fn report(&mut self, errors: &mut Vec<ParseError>, pointer: &str, message: String) {
+        if self.reported == Some(self.current) {
+            return;
+        }
+        self.reported = Some(self.current);
+        let (line, column, span) = match self.peek() {
+            Some(token) => (token.line, token.get_column(), token.get_span()),
+            None => (0, 0, 0..0),
+        };
+        errors.push(ParseError {
+            message,
+            line,
+            column,
+            span,
+            pointer: pointer.to_string(),
+        });
+    }
+}
+
+fn starts_value(kind: &Kind) -> bool {
+    matches!(
+        kind,
+        Kind::BeginObject
+            | Kind::BeginArray
+            | Kind::String(_)
+            | Kind::Boolean(_)
+            | Kind::Null
+            | Kind::Number(_)
+            | Kind::Error(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    fn recover(text: &str) -> Recovered {
+        Parser::from_string_recovering(text.to_string(), ParserOptions::default())
+            .parse_recovering()
+    }
+
+    fn messages(recovered: &Recovered) -> Vec<String> {
+        recovered.errors.iter().map(ParseError::to_string).collect()
+    }
+
+    #[test]
+    fn recovery_collects_every_error() {
+        let recovered = recover(r#"[1 2, @, {"a" 1}, tru]"#);
+        assert_eq!(
+            messages(&recovered),
+            [
+                "expected `,` or `]`, found number at line 1, column 4",
+                "unexpected character `@` at line 1, column 7 (in /1)",
+                "expected `:`, found number at line 1, column 15 (in /2/a)",
+                "unknown literal `tru` at line 1, column 19 (in /3)",
+            ]
+        );
+        assert_eq!(recovered.json, json!([1, null, {"a": 1}, null]));
+    }
+
+    #[test]
+    fn a_stray_bracket_is_reported_once() {
+        let recovered = recover(r#"{"a": ]}"#);
+        assert_eq!(
+            messages(&recovered),
+            ["expected a value, found `]` at line 1, column 7 (in /a)"]
+        );
+        assert_eq!(recovered.json, json!({"a": null}));
+
+        let recovered = recover(r#"{"a": [1, }"#);
+        assert_eq!(recovered.errors.len(), 1);
+        assert_eq!(recovered.json, json!({"a": [1, null]}));
+
+        let recovered = recover(r#"[1] ]"#);
+        assert_eq!(
+            messages(&recovered),
+            ["unexpected `]` after the document at line 1, column 5"]
+        );
+    }
+
+    #[test]
+    fn broken_values_are_told_from_real_nulls() {
+        let recovered = recover("{\"a\": null, \"b\": nul, \"c\": }");
+        assert_eq!(recovered.json, json!({"a": null, "b": null, "c": null}));
+        assert!(!recovered.is_broken("/a"));
+        assert!(recovered.is_broken("/b"));
+        assert!(recovered.is_broken("/c"));
+        assert_eq!(recovered.broken[0].span, 17..20);
+        assert_eq!(recovered.broken[1].span, 27..27);
+    }
+
+    #[test]
+    fn positions_are_one_based_with_columns() {
+        let recovered = recover("{\n  \"a\": \"x\ty\",\n  \"b\": [1,]\n}");
+        assert_eq!(
+            messages(&recovered),
+            [
+                "unescaped control character U+0009 in string at line 2, column 10 (in /a)",
+                "trailing comma before `]` at line 3, column 11 (in /b)",
+            ]
+        );
+        assert_eq!(recovered.errors[0].span(), 11..12);
+        assert_eq!(recovered.errors[1].line(), 3);
+        assert_eq!(recovered.errors[1].column(), 11);
+    }
+
+    #[test]
+    fn a_short_unicode_escape_stops_at_the_quote() {
+        let recovered = recover(r#"["\u12", "ok"]"#);
+        assert_eq!(
+            messages(&recovered),
+            ["truncated unicode escape at line 1, column 3 (in /0)"]
+        );
+        assert_eq!(recovered.errors[0].span(), 2..6);
+        assert_eq!(recovered.json, json!([null, "ok"]));
+    }
+
+    #[test]
+    fn unterminated_input() {
+        let recovered = recover(r#"{"a": "open"#);
+        assert_eq!(
+            messages(&recovered),
+            [
+                "unterminated string at line 1, column 7 (in /a)",
+                "expected `,` or `}`, found end of input at line 1, column 12",
+            ]
+        );
+        let recovered = recover("[1, [2");
+        assert_eq!(
+            messages(&recovered),
+            ["expected `,` or `]`, found end of input at line 1, column 7 (in /1)"]
+        );
+        assert_eq!(recovered.json, json!([1, [2]]));
+    }
+
+    fn hash_of(json: &Json) -> u64 {
+        use std::collections::hash_map::DefaultHasher;
+        let mut hasher = DefaultHasher::new();
+        json.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    // `==`, `cmp` and `hash` must agree with each other, and `eq_by` with
+    // the strict policy must agree with `==`
+    fn assert_consistent(a: &Json, b: &Json) {
+        let equal = a == b;
+        assert_eq!(equal, a.cmp(b) == Ordering::Equal, "{} vs {}", a, b);
+        assert_eq!(a.cmp(b), b.cmp(a).reverse(), "{} vs {}", a, b);
+        assert_eq!(equal, a.eq_by(b, EqPolicy::Strict), "{} vs {}", a, b);
+        if equal {
+            assert_eq!(hash_of(a), hash_of(b), "{} vs {}", a, b);
+        }
+    }
+
+    fn samples() -> Vec<Json> {
+        vec![
+            json!(null),
+            json!(false),
+            json!(true),
+            Json::Number(-0.0),
+            Json::Number(0.0),
+            Json::Number(f64::NAN),
+            Json::Number(-f64::NAN),
+            json!(1),
+            json!(1.5),
+            json!(""),
+            json!("a"),
+            json!([]),
+            json!([0.0]),
+            json!([-0.0]),
+            json!({}),
+            json!({"a": 1, "b": 2}),
+            json!({"b": 2, "a": 1}),
+            json!({"a": 1, "b": 3}),
+        ]
+    }
+
+    #[test]
+    fn ord_eq_and_hash_agree() {
+        let samples = samples();
+        for a in &samples {
+            for b in &samples {
+                assert_consistent(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn types_order_null_bool_number_string_array_object() {
+        let order = [
+            json!(null),
+            json!(true),
+            json!(-1e300),
+            json!(""),
+            json!([]),
+            json!({}),
+        ];
+        for pair in order.windows(2) {
+            assert!(pair[0] < pair[1], "{} < {}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn strict_policy_tells_zeros_apart_and_matches_nan() {
+        let zero = Json::Number(0.0);
+        let negative_zero = Json::Number(-0.0);
+        assert_ne!(zero, negative_zero);
+        assert!(!zero.eq_by(&negative_zero, EqPolicy::Strict));
+        assert!(negative_zero < zero);
+
+        let nan = Json::Number(f64::NAN);
+        assert_eq!(nan, nan.clone());
+        assert!(nan.eq_by(&nan, EqPolicy::Strict));
+        assert_eq!(hash_of(&nan), hash_of(&nan.clone()));
+    }
+
+    #[test]
+    fn numeric_policy_compares_by_value() {
+        let zero = Json::Number(0.0);
+        let negative_zero = Json::Number(-0.0);
+        assert!(zero.eq_by(&negative_zero, EqPolicy::Numeric));
+        let zeros = json!([0.0, {"a": -0.0}]);
+        assert!(zeros.eq_by(&json!([-0.0, {"a": 0.0}]), EqPolicy::Numeric));
+        assert!(!json!(1).eq_by(&json!(1.5), EqPolicy::Numeric));
+
+        // still an equivalence: reflexive on NaN, symmetric everywhere
+        let nan = Json::Number(f64::NAN);
+        assert!(nan.eq_by(&nan, EqPolicy::Numeric));
+        assert!(!nan.eq_by(&zero, EqPolicy::Numeric));
+        let samples = samples();
+        for a in &samples {
+            assert!(a.eq_by(a, EqPolicy::Numeric), "{}", a);
+            for b in &samples {
+                assert_eq!(
+                    a.eq_by(b, EqPolicy::Numeric),
+                    b.eq_by(a, EqPolicy::Numeric),
+                    "{} vs {}",
+                    a,
+                    b
+                );
+                // numeric equality is coarser than strict equality
+                if a == b {
+                    assert!(a.eq_by(b, EqPolicy::Numeric), "{} vs {}", a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn key_order_never_matters() {
+        let a = json!({"x": [1, {"p": 1, "q": 2}], "y": null});
+        let b = json!({"y": null, "x": [1, {"q": 2, "p": 1}]});
+        assert_eq!(a, b);
+        assert_eq!(a.cmp(&b), Ordering::Equal);
+        assert_eq!(hash_of(&a), hash_of(&b));
+        assert!(a.eq_by(&b, EqPolicy::Strict));
+        assert!(a.eq_by(&b, EqPolicy::Numeric));
+        // but element order in arrays does
+        assert_ne!(json!([1, 2]), json!([2, 1]));
+    }
+
+    #[test]
+    fn type_predicates_match_their_variant() {
+        assert!(json!(1).is_number());
+        assert!(!json!(true).is_number());
+        assert!(json!(true).is_boolean());
+        assert!(json!("a").is_string());
+        assert!(json!([]).is_array());
+        assert!(json!({}).is_object());
+        assert!(json!(null).is_null_or_empty());
+        assert_eq!(json!([1, "a"]).as_text(), r#"[1,"a"]"#);
+        assert_eq!(json!("a").as_text(), "a");
+    }
+
+    #[test]
+    fn typed_accessors_return_none_for_other_types() {
+        let doc = json!({"s": "x", "b": true, "n": 2.5, "i": -3, "a": [1], "o": {}, "z": null});
+        assert_eq!(doc["s"].as_str(), Some("x"));
+        assert_eq!(doc["b"].as_bool(), Some(true));
+        assert_eq!(doc["n"].as_f64(), Some(2.5));
+        assert_eq!(doc["i"].as_i64(), Some(-3));
+        assert_eq!(doc["a"].as_array(), Some(&vec![json!(1)]));
+        assert!(doc["o"].as_object().is_some_and(Map::is_empty));
+
+        assert_eq!(doc["n"].as_str(), None);
+        assert_eq!(doc["s"].as_bool(), None);
+        assert_eq!(doc["b"].as_f64(), None);
+        assert_eq!(doc["n"].as_i64(), None);
+        assert_eq!(doc["o"].as_array(), None);
+        assert!(doc["a"].as_object().is_none());
+        assert_eq!(doc["z"].as_str(), None);
+    }
+
+    #[test]
+    fn get_tells_a_missing_member_from_null() {
+        let doc = json!({"z": null, "list": [null]});
+        assert_eq!(doc.get("z"), Some(&Json::Null));
+        assert_eq!(doc.get("missing"), None);
+        assert_eq!(doc["list"].get_index(0), Some(&Json::Null));
+        assert_eq!(doc["list"].get_index(1), None);
+        // the lenient forms cannot tell them apart
+        assert_eq!(doc.path("missing"), &Json::Null);
+        assert_eq!(doc["list"].at_index(1), &Json::Null);
+    }
+
+    #[test]
+    fn try_accessors_say_what_went_wrong() {
+        let doc = json!({"n": 1.5, "big": 1e19, "list": [1, 2]});
+        assert_eq!(
+            doc["n"].try_as_str(),
+            Err(AccessError::WrongType {
+                expected: JsonType::String,
+                found: JsonType::Number,
+            })
+        );
+        assert_eq!(doc["n"].try_as_i64(), Err(AccessError::NotAnInteger(1.5)));
+        assert_eq!(doc["big"].try_as_i64(), Err(AccessError::NotAnInteger(1e19)));
+        assert_eq!(
+            doc.try_get("missing"),
+            Err(AccessError::MissingKey("missing".to_string()))
+        );
+        assert_eq!(
+            doc["list"].try_get_index(5),
+            Err(AccessError::IndexOutOfBounds { index: 5, len: 2 })
+        );
+        assert_eq!(
+            doc["list"].try_get("a").unwrap_err().to_string(),
+            "expected object, found array"
+        );
+        assert_eq!(
+            doc.try_get_index(0).unwrap_err().to_string(),
+            "expected array, found object"
+        );
+        assert_eq!(
+            doc["list"].try_get_index(5).unwrap_err().to_string(),
+            "index 5 out of bounds for array of length 2"
+        );
+        assert_eq!(
+            doc.try_get("missing").unwrap_err().to_string(),
+            r#"missing key "missing""#
+        );
+        assert_eq!(
+            doc["n"].try_as_i64().unwrap_err().to_string(),
+            "expected integer, found 1.5"
+        );
+    }
+
+    #[test]
+    fn i64_conversion_stays_in_range() {
+        assert_eq!(Json::Number(-9.223372036854776e18).as_i64(), Some(i64::MIN));
+        assert_eq!(Json::Number(9.223372036854776e18).as_i64(), None);
+        assert_eq!(Json::Number(f64::NAN).as_i64(), None);
+        assert_eq!(Json::Number(4.0).as_i64(), Some(4));
+    }
+
+    #[test]
+    fn mutable_accessors_edit_in_place() {
+        let mut doc = json!({"s": "a", "b": false, "n": 1, "list": [1], "o": {}});
+        doc.get_mut("s").unwrap().as_str_mut().unwrap().push('b');
+        *doc.get_mut("b").unwrap().as_bool_mut().unwrap() = true;
+        *doc.get_mut("n").unwrap().as_f64_mut().unwrap() += 1.0;
+        doc.get_mut("list").unwrap().as_array_mut().unwrap().push(json!(2));
+        *doc.get_mut("list").unwrap().get_index_mut(0).unwrap() = json!(0);
+        doc.get_mut("o")
+            .unwrap()
+            .as_object_mut()
+            .unwrap()
+            .insert("k".to_string(), json!(null));
+        assert_eq!(
+            doc,
+            json!({"s": "ab", "b": true, "n": 2, "list": [0, 2], "o": {"k": null}})
+        );
+
+        assert!(doc.get_mut("missing").is_none());
+        assert!(doc.as_array_mut().is_none());
+        assert_eq!(
+            doc.try_get_mut("s").unwrap().try_as_bool_mut(),
+            Err(AccessError::WrongType {
+                expected: JsonType::Boolean,
+                found: JsonType::String,
+            })
+        );
+        assert_eq!(
+            doc["list"].clone().try_get_index_mut(2),
+            Err(AccessError::IndexOutOfBounds { index: 2, len: 2 })
+        );
+    }
+
+    #[test]
+    fn indexing_a_missing_value_yields_null() {
+        let doc = json!({"Image": {"IDs": [116, 943]}, "z": null});
+        assert_eq!(doc["Image"]["IDs"][1], json!(943));
+        assert_eq!(doc["Image"]["IDs"][2], Json::Null);
+        assert_eq!(doc["missing"]["deeper"][0], Json::Null);
+        assert_eq!(doc["z"], Json::Null);
+        // the wrong kind of index on each variant
+        assert_eq!(doc[0], Json::Null);
+        assert_eq!(doc["Image"]["IDs"]["0"], Json::Null);
+        for scalar in [json!(null), json!(true), json!(1), json!("s")] {
+            assert_eq!(scalar[0], Json::Null);
+            assert_eq!(scalar["k"], Json::Null);
+        }
+    }
+
+    #[test]
+    fn index_mut_assigns_and_autovivifies() {
+        let mut doc = json!({"Image": {"IDs": [116, 943, 234]}});
+        doc["Image"]["IDs"][2] = Json::from(5);
+        doc["Image"]["Title"] = Json::from("View");
+        doc["new"]["nested"]["deep"] = json!(true);
+        assert_eq!(
+            doc,
+            json!({
+                "Image": {"IDs": [116, 943, 5], "Title": "View"},
+                "new": {"nested": {"deep": true}},
+            })
+        );
+
+        let mut null = Json::Null;
+        null["k"] = json!(1);
+        assert_eq!(null, json!({"k": 1}));
+    }
+
+    #[test]
+    #[should_panic(expected = "index 3 out of bounds for array of length 3")]
+    fn index_mut_past_the_end_panics() {
+        let mut doc = json!([1, 2, 3]);
+        doc[3] = json!(4);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot index into null with 0")]
+    fn index_mut_does_not_create_arrays() {
+        let mut doc = Json::Null;
+        doc[0] = json!(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot index into array with \"k\"")]
+    fn index_mut_by_key_on_an_array_panics() {
+        let mut doc = json!([]);
+        doc["k"] = json!(1);
+    }
+
+    #[test]
+    fn insert_and_remove_take_keys_or_indices() {
+        let mut doc = json!({"a": 1, "list": [1, 3]});
+        assert_eq!(doc.insert("a", json!(10)), Ok(Some(json!(1))));
+        assert_eq!(doc.insert("b".to_string(), json!(2)), Ok(None));
+        let list = doc.get_mut("list").unwrap();
+        assert_eq!(list.insert(1, json!(2)), Ok(None));
+        assert_eq!(list.insert(3, json!(4)), Ok(None));
+        assert_eq!(
+            list.insert(9, json!(0)),
+            Err(AccessError::IndexOutOfBounds { index: 9, len: 4 })
+        );
+        assert_eq!(list.remove(0), Some(json!(1)));
+        assert_eq!(list.remove(7), None);
+        assert_eq!(doc.remove("a"), Some(json!(10)));
+        assert_eq!(doc.remove("missing"), None);
+        assert_eq!(doc.remove(0), None);
+        assert_eq!(doc, json!({"list": [2, 3, 4], "b": 2}));
+
+        // a null becomes the container; anything else is the wrong type
+        let mut null = Json::Null;
+        null.insert("k", json!(true)).unwrap();
+        assert_eq!(null, json!({"k": true}));
+        assert_eq!(
+            json!("s").insert("k", json!(1)),
+            Err(AccessError::WrongType {
+                expected: JsonType::Object,
+                found: JsonType::String,
+            })
+        );
+        assert!(json!({}).insert(0, json!(1)).is_err());
+    }
+
+    #[test]
+    fn entry_push_and_pop() {
+        let mut doc = Json::Null;
+        *doc.entry("hits").unwrap().or_insert(json!(0)) = json!(1);
+        doc.entry("tags").unwrap().or_default().push(json!("a")).unwrap();
+        doc["tags"].push(json!("b")).unwrap();
+        assert_eq!(doc, json!({"hits": 1, "tags": ["a", "b"]}));
+        assert_eq!(doc["tags"].pop(), Some(json!("b")));
+        assert_eq!(doc["hits"].pop(), None);
+        assert!(doc["hits"].push(json!(1)).is_err());
+        assert!(json!([]).entry("k").is_err());
+    }
+
+    #[test]
+    fn retain_keeps_matching_elements_and_members() {
+        let mut list = json!([1, "a", 2, null]);
+        list.retain(Json::is_number);
+        assert_eq!(list, json!([1, 2]));
+        let mut doc = json!({"a": 1, "b": null, "c": 3});
+        doc.retain(|json| !json.is_null_or_empty());
+        assert_eq!(doc.to_string(), r#"{"a":1,"c":3}"#);
+    }
+
+    #[test]
+    fn take_and_replace_leave_a_value_behind() {
+        let mut doc = json!({"payload": {"big": [1, 2, 3]}});
+        let payload = doc["payload"].take();
+        assert_eq!(payload, json!({"big": [1, 2, 3]}));
+        assert_eq!(doc, json!({"payload": null}));
+        assert_eq!(doc["payload"].replace(json!(7)), Json::Null);
+        assert_eq!(doc, json!({"payload": 7}));
+    }
+
+    #[test]
+    fn sort_keys_is_deep_and_sort_by_is_stable() {
+        let mut doc = json!({"b": [{"y": 1, "x": 2}], "a": {"d": 1, "c": 2}});
+        doc.sort_keys();
+        assert_eq!(doc.to_string(), r#"{"a":{"c":2,"d":1},"b":[{"x":2,"y":1}]}"#);
+
+        let mut list = json!([{"n": 2, "id": "a"}, {"n": 1, "id": "b"}, {"n": 2, "id": "c"}]);
+        list.sort_by(|a, b| a["n"].cmp(&b["n"]));
+        assert_eq!(
+            list,
+            json!([{"n": 1, "id": "b"}, {"n": 2, "id": "a"}, {"n": 2, "id": "c"}])
+        );
+
+        let mut doc = json!({"x": 3, "y": 1, "z": 2});
+        doc.sort_by(|a, b| a.cmp(b));
+        assert_eq!(doc.to_string(), r#"{"y":1,"z":2,"x":3}"#);
+    }
+
+    #[test]
+    fn clones_are_deep() {
+        let original = json!({"list": [1, {"k": "v"}]});
+        let mut copy = original.clone();
+        copy["list"][1]["k"] = json!("changed");
+        assert_eq!(original["list"][1]["k"], json!("v"));
+    }
+
+    #[test]
+    fn default_escaping_leaves_non_ascii_alone() {
+        let json = json!({"<é>": "😀\u{2028}&'"});
+        assert_eq!(json.to_string_escaped(Escaping::default()), json.to_string());
+        assert_eq!(json.to_string(), "{\"<é>\":\"😀\u{2028}&'\"}");
+    }
+
+    #[test]
+    fn ascii_only_escapes_astral_characters_as_surrogate_pairs() {
+        let escaped = json!("😀").to_string_escaped(Escaping::ascii_only());
+        assert_eq!(escaped, r#""\ud83d\ude00""#);
+        let escaped = json!({"é": "a\u{7f}ü"}).to_string_escaped(Escaping::ascii_only());
+        assert_eq!(escaped, "{\"\\u00e9\":\"a\u{7f}\\u00fc\"}");
+        // the escaped text reads back as the original
+        let json = Parser::from_string(r#"["\ud83d\ude00", "\u00e9"]"#.to_string()).parse();
+        assert_eq!(json, json!(["😀", "é"]));
+    }
+
+    #[test]
+    fn html_escapes_markup_characters() {
+        let escaped = json!({"<a href='x'>": "1 & 2"}).to_string_escaped(Escaping::html());
+        assert_eq!(escaped, r#"{"\u003ca href=\u0027x\u0027\u003e":"1 \u0026 2"}"#);
+        assert_eq!(json!("é\"").to_string_escaped(Escaping::html()), r#""é\"""#);
+    }
+
+    #[test]
+    fn javascript_escapes_line_and_paragraph_separators() {
+        let escaped = json!("a\u{2028}b\u{2029}c").to_string_escaped(Escaping::javascript());
+        assert_eq!(escaped, r#""a\u2028b\u2029c""#);
+        let escaped = json!("<é>").to_string_escaped(Escaping::javascript());
+        assert_eq!(escaped, r#""<é>""#);
+    }
+
+    #[test]
+    fn escaping_modes_combine() {
+        let all = Escaping {
+            ascii_only: true,
+            html: true,
+            javascript: true,
+        };
+        let escaped = json!("<😀\u{2028}>").to_string_escaped(all);
+        assert_eq!(escaped, r#""\u003c\ud83d\ude00\u2028\u003e""#);
+        // ascii_only covers the separators on its own
+        let escaped = json!("\u{2028}").to_string_escaped(Escaping::ascii_only());
+        assert_eq!(escaped, r#""\u2028""#);
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown literal `tru` at line 2, column 3")]
+    fn strict_lexing_panics_with_the_position() {
+        Parser::from_string("[\n  tru]".to_string()).parse();
+    }
+}
@@ -0,0 +1,109 @@
+//! JSON Pointer (RFC 6901) helpers. A pointer addresses a value inside a
+//! `Json` tree, e.g. `/Image/IDs/2`; the empty string is the whole document.
+
+use crate::parsen::Json;
+
+/// Escapes one reference token: `~` becomes `~0` and `/` becomes `~1`.
+pub fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Reverses `escape`.
+pub fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// Appends `token` to `pointer` as a new reference token.
+pub fn push(pointer: &str, token: &str) -> String {
+    format!("{}/{}", pointer, escape(token))
+}
+
+/// Splits a pointer into its unescaped reference tokens. Returns `None` when
+/// the pointer is neither empty nor starts with `/`.
+pub fn tokens(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+    let rest = pointer.strip_prefix('/')?;
+    Some(rest.split('/').map(unescape).collect())
+}
+
+/// The value `pointer` addresses inside `root`, if there is one.
+pub fn get<'a>(root: &'a Json, pointer: &str) -> Option<&'a Json> {
+    tokens(pointer)?
+        .iter()
+        .try_fold(root, |json, token| match json {
+            Json::Object(map) => map.get(token),
+            // array indices are plain decimals, without leading zeros
+            Json::Array(vec) if token == "0" || !token.starts_with('0') => {
+                vec.get(token.parse::<usize>().ok()?)
+            }
+            _ => None,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn tokens_are_escaped_and_unescaped() {
+        assert_eq!(escape("a/b~c"), "a~1b~0c");
+        assert_eq!(unescape("a~1b~0c"), "a/b~c");
+        // `~01` is `~1` escaped, not `/`
+        assert_eq!(unescape("~01"), "~1");
+        assert_eq!(push("/a", "b/c"), "/a/b~1c");
+        assert_eq!(tokens(""), Some(Vec::new()));
+        assert_eq!(
+            tokens("/a~1b//0"),
+            Some(vec!["a/b".to_string(), String::new(), "0".to_string()])
+        );
+        assert_eq!(tokens("a/b"), None);
+    }
+
+    /// RFC 6901, section 5.
+    #[test]
+    fn rfc_6901_examples() {
+        let doc = json!({
+            "foo": ["bar", "baz"],
+            "": 0,
+            "a/b": 1,
+            "c%d": 2,
+            "e^f": 3,
+            "g|h": 4,
+            "i\\j": 5,
+            "k\"l": 6,
+            " ": 7,
+            "m~n": 8,
+        });
+        assert_eq!(get(&doc, ""), Some(&doc));
+        assert_eq!(get(&doc, "/foo"), Some(&json!(["bar", "baz"])));
+        assert_eq!(get(&doc, "/foo/0"), Some(&json!("bar")));
+        let expected = [
+            ("/", 0),
+            ("/a~1b", 1),
+            ("/c%d", 2),
+            ("/e^f", 3),
+            ("/g|h", 4),
+            ("/i\\j", 5),
+            ("/k\"l", 6),
+            ("/ ", 7),
+            ("/m~0n", 8),
+        ];
+        for (pointer, value) in expected {
+            assert_eq!(get(&doc, pointer), Some(&json!(value)), "{}", pointer);
+        }
+    }
+
+    #[test]
+    fn missing_values_and_bad_indices() {
+        let doc = json!({"list": [1, 2], "n": 1});
+        assert_eq!(get(&doc, "/list/2"), None);
+        assert_eq!(get(&doc, "/list/01"), None);
+        assert_eq!(get(&doc, "/list/-"), None);
+        assert_eq!(get(&doc, "/n/0"), None);
+        assert_eq!(get(&doc, "/missing"), None);
+        assert_eq!(get(&doc, "list"), None);
+    }
+}
@@ -0,0 +1,1233 @@
+//! JSON Schema validation, covering the core of draft 2020-12.
+//!
+//! A schema is itself a `Json` value; `Schema::compile` checks it once and
+//! resolves its references, after which `validate` can be run against any
+//! number of instances. Every error names the offending value by its
+//! instance path and the failing keyword by its schema path, both JSON
+//! Pointers.
+//!
+//! ```
+//! use json_parser::json;
+//! use json_parser::schema::Schema;
+//!
+//! let schema = Schema::compile(&json!({
+//!     "type": "object",
+//!     "properties": {
+//!         "id": {"type": "integer", "minimum": 1},
+//!         "tags": {"type": "array", "items": {"$ref": "#/$defs/tag"}}
+//!     },
+//!     "required": ["id"],
+//!     "$defs": {"tag": {"type": "string", "pattern": "^[a-z]+$"}}
+//! }))?;
+//!
+//! assert!(schema.is_valid(&json!({"id": 7, "tags": ["new"]})));
+//!
+//! let errors = schema.validate(&json!({"id": 0, "tags": ["New"]})).unwrap_err();
+//! assert_eq!(errors[0].instance_path, "/id");
+//! assert_eq!(errors[0].schema_path, "/properties/id/minimum");
+//! assert_eq!(errors[1].instance_path, "/tags/0");
+//! assert_eq!(errors[1].schema_path, "/$defs/tag/pattern");
+//! # Ok::<(), json_parser::schema::SchemaError>(())
+//! ```
+//!
+//! Supported keywords: `type`, `enum`, `const`, `properties`,
+//! `patternProperties`, `additionalProperties`, `propertyNames`,
+//! `required`, `minProperties`, `maxProperties`, `prefixItems`, `items`,
+//! `contains` (with `minContains` and `maxContains`), `minItems`,
+//! `maxItems`, `uniqueItems`, `minimum`, `maximum`, `exclusiveMinimum`,
+//! `exclusiveMaximum`, `multipleOf`, `minLength`, `maxLength`, `pattern`,
+//! `allOf`, `anyOf`, `oneOf`, `not`, `if`/`then`/`else`, `$ref` with
+//! `$defs`, `$id` and `$anchor`, and `format`, which is only checked when
+//! `Schema::check_formats` asks for it. Other keywords are ignored.
+//!
+//! `infer` goes the other way, writing a schema that fits a set of sample
+//! documents.
+
+mod format;
+pub mod infer;
+mod regex;
+
+use std::collections::HashMap;
+use std::fmt;
+
+use self::regex::Regex;
+pub use self::regex::RegexError;
+use crate::parsen::{EqPolicy, Json, JsonType};
+use crate::pointer;
+
+/// A schema that could not be compiled.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SchemaError {
+    /// A keyword whose value has the wrong shape.
+    InvalidKeyword {
+        pointer: String,
+        expected: &'static str,
+    },
+    InvalidPattern {
+        pointer: String,
+        error: RegexError,
+    },
+    /// A `$ref` that does not lead anywhere in the schema.
+    UnresolvedRef {
+        pointer: String,
+        reference: String,
+    },
+    /// A `$ref` that leads back to a schema applying to the same value, so
+    /// validating would never end.
+    RefCycle {
+        pointer: String,
+    },
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchemaError::InvalidKeyword { pointer, expected } => {
+                write!(f, "#{}: expected {}", pointer, expected)
+            }
+            SchemaError::InvalidPattern { pointer, error } => {
+                write!(f, "#{}: invalid pattern: {}", pointer, error)
+            }
+            SchemaError::UnresolvedRef { pointer, reference } => {
+                write!(f, "#{}: cannot resolve {:?}", pointer, reference)
+            }
+            SchemaError::RefCycle { pointer } => {
+                write!(f, "#{}: reference cycle never reaches a value", pointer)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// An instance that does not satisfy its schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// The offending value in the instance.
+    pub instance_path: String,
+    /// The keyword that failed, where it is written in the schema; after a
+    /// `$ref` this is inside the schema it points to.
+    pub schema_path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "#{}: {} (#{})",
+            self.instance_path, self.message, self.schema_path
+        )
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+#[derive(Debug)]
+pub struct Schema {
+    /// Every subschema in use, the root first. Keywords refer to each other
+    /// by index, so recursive references are fine.
+    nodes: Vec<Node>,
+    check_formats: bool,
+}
+
+#[derive(Debug)]
+struct Node {
+    pointer: String,
+    keywords: Vec<Keyword>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Type {
+    Json(JsonType),
+    /// A number with no fractional part.
+    Integer,
+}
+
+impl Type {
+    fn matches(self, json: &Json) -> bool {
+        match (self, json) {
+            (Type::Integer, Json::Number(num)) => num.fract() == 0.0,
+            (Type::Integer, _) => false,
+            (Type::Json(ty), json) => json.json_type() == ty,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Json(ty) => write!(f, "{}", ty),
+            Type::Integer => f.write_str("integer"),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Keyword {
+    /// The `false` schema.
+    False,
+    Type(Vec<Type>),
+    Enum(Vec<Json>),
+    Const(Json),
+    Properties(Vec<(String, usize)>),
+    PatternProperties(Vec<(Regex, usize)>),
+    AdditionalProperties {
+        node: usize,
+        /// Names and patterns covered by `properties` and
+        /// `patternProperties`.
+        known: Vec<String>,
+        patterns: Vec<Regex>,
+    },
+    PropertyNames(usize),
+    Required(Vec<String>),
+    MinProperties(usize),
+    MaxProperties(usize),
+    PrefixItems(Vec<usize>),
+    /// Applies to the items after those covered by `prefixItems`.
+    Items {
+        node: usize,
+        skip: usize,
+    },
+    Contains {
+        node: usize,
+        min: usize,
+        max: Option<usize>,
+    },
+    MinItems(usize),
+    MaxItems(usize),
+    UniqueItems,
+    Minimum(f64),
+    Maximum(f64),
+    ExclusiveMinimum(f64),
+    ExclusiveMaximum(f64),
+    MultipleOf(f64),
+    MinLength(usize),
+    MaxLength(usize),
+    Pattern(Regex),
+    AllOf(Vec<usize>),
+    AnyOf(Vec<usize>),
+    OneOf(Vec<usize>),
+    Not(usize),
+    If {
+        condition: usize,
+        then: Option<usize>,
+        otherwise: Option<usize>,
+    },
+    Ref(usize),
+    Format(String),
+}
+
+impl Keyword {
+    fn name(&self) -> &'static str {
+        match self {
+            Keyword::False => "",
+            Keyword::Type(_) => "type",
+            Keyword::Enum(_) => "enum",
+            Keyword::Const(_) => "const",
+            Keyword::Properties(_) => "properties",
+            Keyword::PatternProperties(_) => "patternProperties",
+            Keyword::AdditionalProperties { .. } => "additionalProperties",
+            Keyword::PropertyNames(_) => "propertyNames",
+            Keyword::Required(_) => "required",
+            Keyword::MinProperties(_) => "minProperties",
+            Keyword::MaxProperties(_) => "maxProperties",
+            Keyword::PrefixItems(_) => "prefixItems",
+            Keyword::Items { .. } => "items",
+            Keyword::Contains { .. } => "contains",
+            Keyword::MinItems(_) => "minItems",
+            Keyword::MaxItems(_) => "maxItems",
+            Keyword::UniqueItems => "uniqueItems",
+            Keyword::Minimum(_) => "minimum",
+            Keyword::Maximum(_) => "maximum",
+            Keyword::ExclusiveMinimum(_) => "exclusiveMinimum",
+            Keyword::ExclusiveMaximum(_) => "exclusiveMaximum",
+            Keyword::MultipleOf(_) => "multipleOf",
+            Keyword::MinLength(_) => "minLength",
+            Keyword::MaxLength(_) => "maxLength",
+            Keyword::Pattern(_) => "pattern",
+            Keyword::AllOf(_) => "allOf",
+            Keyword::AnyOf(_) => "anyOf",
+            Keyword::OneOf(_) => "oneOf",
+            Keyword::Not(_) => "not",
+            Keyword::If { .. } => "if",
+            Keyword::Ref(_) => "$ref",
+            Keyword::Format(_) => "format",
+        }
+    }
+
+    /// The subschemas applied to the same value as the keyword.
+    fn in_place(&self) -> Vec<usize> {
+        match self {
+            Keyword::AllOf(nodes) | Keyword::AnyOf(nodes) | Keyword::OneOf(nodes) => nodes.clone(),
+            Keyword::Not(node) | Keyword::Ref(node) => vec![*node],
+            Keyword::If {
+                condition,
+                then,
+                otherwise,
+            } => std::iter::once(*condition)
+                .chain(*then)
+                .chain(*otherwise)
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Schema {
+    pub fn compile(schema: &Json) -> Result<Schema, SchemaError> {
+        let mut compiler = Compiler {
+            root: schema,
+            nodes: Vec::new(),
+            compiled: HashMap::new(),
+            ids: HashMap::new(),
+            anchors: HashMap::new(),
+        };
+        compiler.scan(schema, "");
+        compiler.compile(schema, "")?;
+        cycles(&compiler.nodes)?;
+        Ok(Schema {
+            nodes: compiler.nodes,
+            check_formats: false,
+        })
+    }
+
+    /// Whether `format` is checked. By default the keyword is only an
+    /// annotation, as 2020-12 has it; with checking on, a string that is not
+    /// a valid instance of a known format fails.
+    pub fn check_formats(mut self, check: bool) -> Schema {
+        self.check_formats = check;
+        self
+    }
+
+    /// Every way `instance` fails the schema.
+    pub fn validate(&self, instance: &Json) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        self.check(0, instance, "", &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn is_valid(&self, instance: &Json) -> bool {
+        self.validate(instance).is_ok()
+    }
+
+    fn passes(&self, node: usize, instance: &Json, path: &str) -> bool {
+        let mut errors = Vec::new();
+        self.check(node, instance, path, &mut errors);
+        errors.is_empty()
+    }
+
+    fn check(&self, idx: usize, instance: &Json, path: &str, errors: &mut Vec<ValidationError>) {
+        let node = &self.nodes[idx];
+        for keyword in &node.keywords {
+            let fail = |errors: &mut Vec<ValidationError>, message: String| {
+                errors.push(ValidationError {
+                    instance_path: path.to_string(),
+                    schema_path: pointer::push(&node.pointer, keyword.name()),
+                    message,
+                })
+            };
+            match (keyword, instance) {
+                (Keyword::False, _) => errors.push(ValidationError {
+                    instance_path: path.to_string(),
+                    schema_path: node.pointer.clone(),
+                    message: "no value is allowed here".to_string(),
+                }),
+                (Keyword::Type(types), _) if !types.iter().any(|ty| ty.matches(instance)) => {
+                    let expected: Vec<String> = types.iter().map(Type::to_string).collect();
+                    fail(
+                        errors,
+                        format!(
+                            "expected {}, found {}",
+                            expected.join(" or "),
+                            instance.json_type()
+                        ),
+                    );
+                }
+                (Keyword::Enum(values), _)
+                    if !values
+                        .iter()
+                        .any(|value| value.eq_by(instance, EqPolicy::Numeric)) =>
+                {
+                    fail(
+                        errors,
+                        format!("{} is not one of the allowed values", show(instance)),
+                    );
+                }
+                (Keyword::Const(value), _) if !value.eq_by(instance, EqPolicy::Numeric) => {
+                    fail(
+                        errors,
+                        format!("expected {}, found {}", show(value), show(instance)),
+                    );
+                }
+                (Keyword::Properties(properties), Json::Object(map)) => {
+                    for (name, child) in properties {
+                        if let Some(json) = map.get(name) {
+                            self.check(*child, json, &pointer::push(path, name), errors);
+                        }
+                    }
+                }
+                (Keyword::PatternProperties(patterns), Json::Object(map)) => {
+                    for (key, json) in map.iter() {
+                        for (regex, child) in patterns {
+                            if regex.is_match(key) {
+                                self.check(*child, json, &pointer::push(path, key), errors);
+                            }
+                        }
+                    }
+                }
+                (
+                    Keyword::AdditionalProperties {
+                        node: child,
+                        known,
+                        patterns,
+                    },
+                    Json::Object(map),
+                ) => {
+                    let additional = map.iter().filter(|(key, _)| {
+                        !known.contains(key) && !patterns.iter().any(|regex| regex.is_match(key))
+                    });
+                    for (key, json) in additional {
+                        if matches!(self.nodes[*child].keywords[..], [Keyword::False]) {
+                            fail(
+                                errors,
+                                format!("additional property {:?} is not allowed", key),
+                            );
+                        } else {
+                            self.check(*child, json, &pointer::push(path, key), errors);
+                        }
+                    }
+                }
+                (Keyword::PropertyNames(child), Json::Object(map)) => {
+                    for key in map.keys() {
+                        let name = Json::Strings(key.clone());
+                        self.check(*child, &name, &pointer::push(path, key), errors);
+                    }
+                }
+                (Keyword::Required(names), Json::Object(map)) => {
+                    for name in names {
+                        if !map.contains_key(name) {
+                            fail(errors, format!("missing required property {:?}", name));
+                        }
+                    }
+                }
+                (Keyword::MinProperties(min), Json::Object(map)) if map.len() < *min => {
+                    fail(
+                        errors,
+                        format!("expected at least {} properties, found {}", min, map.len()),
+                    );
+                }
+                (Keyword::MaxProperties(max), Json::Object(map)) if map.len() > *max => {
+                    fail(
+                        errors,
+                        format!("expected at most {} properties, found {}", max, map.len()),
+                    );
+                }
+                (Keyword::PrefixItems(children), Json::Array(vec)) => {
+                    for (i, (child, json)) in children.iter().zip(vec).enumerate() {
+                        self.check(*child, json, &pointer::push(path, &i.to_string()), errors);
+                    }
+                }
+                (Keyword::Items { node: child, skip }, Json::Array(vec)) => {
+                    for (i, json) in vec.iter().enumerate().skip(*skip) {
+                        self.check(*child, json, &pointer::push(path, &i.to_string()), errors);
+                    }
+                }
+                (
+                    Keyword::Contains {
+                        node: child,
+                        min,
+                        max,
+                    },
+                    Json::Array(vec),
+                ) => {
+                    let count = vec
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, json)| {
+                            self.passes(*child, json, &pointer::push(path, &i.to_string()))
+                        })
+                        .count();
+                    if count < *min {
+                        fail(
+                            errors,
+                            format!("expected at least {} matching items, found {}", min, count),
+                        );
+                    } else if max.is_some_and(|max| count > max) {
+                        fail(
+                            errors,
+                            format!(
+                                "expected at most {} matching items, found {}",
+                                max.unwrap_or_default(),
+                                count
+                            ),
+                        );
+                    }
+                }
+                (Keyword::MinItems(min), Json::Array(vec)) if vec.len() < *min => {
+                    fail(
+                        errors,
+                        format!("expected at least {} items, found {}", min, vec.len()),
+                    );
+                }
+                (Keyword::MaxItems(max), Json::Array(vec)) if vec.len() > *max => {
+                    fail(
+                        errors,
+                        format!("expected at most {} items, found {}", max, vec.len()),
+                    );
+                }
+                (Keyword::UniqueItems, Json::Array(vec)) => {
+                    let duplicate = (0..vec.len()).find_map(|i| {
+                        (i + 1..vec.len())
+                            .find(|&j| vec[i].eq_by(&vec[j], EqPolicy::Numeric))
+                            .map(|j| (i, j))
+                    });
+                    if let Some((i, j)) = duplicate {
+                        fail(errors, format!("items {} and {} are equal", i, j));
+                    }
+                }
+                (Keyword::Minimum(min), Json::Number(num)) if num < min => {
+                    fail(
+                        errors,
+                        format!(
+                            "{} is less than the minimum of {}",
+                            show(instance),
+                            number(*min)
+                        ),
+                    );
+                }
+                (Keyword::Maximum(max), Json::Number(num)) if num > max => {
+                    fail(
+                        errors,
+                        format!(
+                            "{} is greater than the maximum of {}",
+                            show(instance),
+                            number(*max)
+                        ),
+                    );
+                }
+                (Keyword::ExclusiveMinimum(min), Json::Number(num)) if num <= min => {
+                    fail(
+                        errors,
+                        format!("{} is not greater than {}", show(instance), number(*min)),
+                    );
+                }
+                (Keyword::ExclusiveMaximum(max), Json::Number(num)) if num >= max => {
+                    fail(
+                        errors,
+                        format!("{} is not less than {}", show(instance), number(*max)),
+                    );
+                }
+                (Keyword::MultipleOf(factor), Json::Number(num)) => {
+                    let quotient = num / factor;
+                    // allow for the rounding error in e.g. 0.3 / 0.1
+                    let error = (quotient - quotient.round()).abs();
+                    if !quotient.is_finite() || error > 1e-9 * quotient.abs().max(1.0) {
+                        fail(
+                            errors,
+                            format!(
+                                "{} is not a multiple of {}",
+                                show(instance),
+                                number(*factor)
+                            ),
+                        );
+                    }
+                }
+                (Keyword::MinLength(min), Json::Strings(st)) if st.chars().count() < *min => {
+                    fail(
+                        errors,
+                        format!(
+                            "expected at least {} characters, found {}",
+                            min,
+                            st.chars().count()
+                        ),
+                    );
+                }
+                (Keyword::MaxLength(max), Json::Strings(st)) if st.chars().count() > *max => {
+                    fail(
+                        errors,
+                        format!(
+                            "expected at most {} characters, found {}",
+                            max,
+                            st.chars().count()
+                        ),
+                    );
+                }
+                (Keyword::Pattern(regex), Json::Strings(st)) if !regex.is_match(st) => {
+                    fail(
+                        errors,
+                        format!("{:?} does not match {:?}", st, regex.as_str()),
+                    );
+                }
+                (Keyword::AllOf(children), _) => {
+                    for child in children {
+                        self.check(*child, instance, path, errors);
+                    }
+                }
+                (Keyword::AnyOf(children), _)
+                    if !children
+                        .iter()
+                        .any(|&child| self.passes(child, instance, path)) =>
+                {
+                    fail(
+                        errors,
+                        "does not match any of the schemas in anyOf".to_string(),
+                    );
+                }
+                (Keyword::OneOf(children), _) => {
+                    let count = children
+                        .iter()
+                        .filter(|&&child| self.passes(child, instance, path))
+                        .count();
+                    if count != 1 {
+                        fail(
+                            errors,
+                            format!(
+                                "matches {} of the schemas in oneOf, expected exactly one",
+                                count
+                            ),
+                        );
+                    }
+                }
+                (Keyword::Not(child), _) if self.passes(*child, instance, path) => {
+                    fail(errors, "must not match the schema in not".to_string());
+                }
+                (
+                    Keyword::If {
+                        condition,
+                        then,
+                        otherwise,
+                    },
+                    _,
+                ) => {
+                    let branch = if self.passes(*condition, instance, path) {
+                        then
+                    } else {
+                        otherwise
+                    };
+                    if let Some(child) = branch {
+                        self.check(*child, instance, path, errors);
+                    }
+                }
+                (Keyword::Ref(child), _) => self.check(*child, instance, path, errors),
+                (Keyword::Format(name), Json::Strings(st))
+                    if self.check_formats && format::check(name, st) == Some(false) =>
+                {
+                    fail(errors, format!("{:?} is not a valid {}", st, name));
+                }
+                // the keyword does not apply to this type, or it holds
+                _ => {}
+            }
+        }
+    }
+}
+
+/// A value as quoted in an error: scalars in full, containers by type.
+fn show(json: &Json) -> String {
+    match json {
+        Json::Array(_) => "an array".to_string(),
+        Json::Object(_) => "an object".to_string(),
+        scalar => scalar.to_string(),
+    }
+}
+
+fn number(num: f64) -> String {
+    Json::Number(num).to_string()
+}
+
+struct Compiler<'a> {
+    root: &'a Json,
+    nodes: Vec<Node>,
+    /// Node index of each subschema compiled so far, by pointer.
+    compiled: HashMap<String, usize>,
+    /// Pointers to the subschemas with an `$id` or `$anchor`.
+    ids: HashMap<String, String>,
+    anchors: HashMap<String, String>,
+}
+
+impl<'a> Compiler<'a> {
+    // notes every `$id` and `$anchor`, so that references can find them
+    fn scan(&mut self, schema: &'a Json, pointer: &str) {
+        let Json::Object(map) = schema else {
+            return;
+        };
+        if let Some(id) = map.get("$id").and_then(Json::as_str) {
+            let id = id.trim_end_matches('#');
+            self.ids.insert(id.to_string(), pointer.to_string());
+        }
+        if let Some(anchor) = map.get("$anchor").and_then(Json::as_str) {
+            self.anchors.insert(anchor.to_string(), pointer.to_string());
+        }
+        for (child, json) in subschemas(schema, pointer) {
+            self.scan(json, &child);
+        }
+    }
+
+    fn compile(&mut self, schema: &'a Json, pointer: &str) -> Result<usize, SchemaError> {
+        if let Some(&idx) = self.compiled.get(pointer) {
+            return Ok(idx);
+        }
+        let idx = self.nodes.len();
+        self.nodes.push(Node {
+            pointer: pointer.to_string(),
+            keywords: Vec::new(),
+        });
+        self.compiled.insert(pointer.to_string(), idx);
+        self.nodes[idx].keywords = self.keywords(schema, pointer)?;
+        Ok(idx)
+    }
+
+    fn keywords(&mut self, schema: &'a Json, pointer: &str) -> Result<Vec<Keyword>, SchemaError> {
+        let map = match schema {
+            Json::Boolean(true) => return Ok(Vec::new()),
+            Json::Boolean(false) => return Ok(vec![Keyword::False]),
+            Json::Object(map) => map,
+            _ => return Err(invalid(pointer, "an object or a boolean")),
+        };
+
+        let mut keywords = Vec::new();
+        for (name, value) in map.iter() {
+            let at = pointer::push(pointer, name);
+            let keyword = match name.as_str() {
+                "type" => Keyword::Type(types(value, &at)?),
+                "enum" => Keyword::Enum(array(value, &at)?.clone()),
+                "const" => Keyword::Const(value.clone()),
+                "properties" => Keyword::Properties(self.schema_map(value, &at)?),
+                "patternProperties" => {
+                    let mut patterns = Vec::new();
+                    for (pattern, child) in self.schema_map(value, &at)? {
+                        patterns.push((regex(&pattern, &at)?, child));
+                    }
+                    Keyword::PatternProperties(patterns)
+                }
+                "additionalProperties" => {
+                    let known = match map.get("properties") {
+                        Some(Json::Object(properties)) => properties.keys().cloned().collect(),
+                        _ => Vec::new(),
+                    };
+                    let mut patterns = Vec::new();
+                    if let Some(Json::Object(properties)) = map.get("patternProperties") {
+                        for pattern in properties.keys() {
+                            let at = pointer::push(pointer, "patternProperties");
+                            patterns.push(regex(pattern, &at)?);
+                        }
+                    }
+                    Keyword::AdditionalProperties {
+                        node: self.compile(value, &at)?,
+                        known,
+                        patterns,
+                    }
+                }
+                "propertyNames" => Keyword::PropertyNames(self.compile(value, &at)?),
+                "required" => Keyword::Required(strings(value, &at)?),
+                "minProperties" => Keyword::MinProperties(count(value, &at)?),
+                "maxProperties" => Keyword::MaxProperties(count(value, &at)?),
+                "prefixItems" => Keyword::PrefixItems(self.schema_list(value, &at)?),
+                "items" => Keyword::Items {
+                    node: self.compile(value, &at)?,
+                    skip: map
+                        .get("prefixItems")
+                        .and_then(Json::as_array)
+                        .map_or(0, Vec::len),
+                },
+                "contains" => {
+                    let bound = |name: &str| {
+                        let at = pointer::push(pointer, name);
+                        map.get(name).map(|value| count(value, &at)).transpose()
+                    };
+                    Keyword::Contains {
+                        node: self.compile(value, &at)?,
+                        min: bound("minContains")?.unwrap_or(1),
+                        max: bound("maxContains")?,
+                    }
+                }
+                "minItems" => Keyword::MinItems(count(value, &at)?),
+                "maxItems" => Keyword::MaxItems(count(value, &at)?),
+                "uniqueItems" => match value {
+                    Json::Boolean(true) => Keyword::UniqueItems,
+                    Json::Boolean(false) => continue,
+                    _ => return Err(invalid(&at, "a boolean")),
+                },
+                "minimum" => Keyword::Minimum(num(value, &at)?),
+                "maximum" => Keyword::Maximum(num(value, &at)?),
+                "exclusiveMinimum" => Keyword::ExclusiveMinimum(num(value, &at)?),
+                "exclusiveMaximum" => Keyword::ExclusiveMaximum(num(value, &at)?),
+                "multipleOf" => match num(value, &at)? {
+                    factor if factor > 0.0 => Keyword::MultipleOf(factor),
+                    _ => return Err(invalid(&at, "a number greater than 0")),
+                },
+                "minLength" => Keyword::MinLength(count(value, &at)?),
+                "maxLength" => Keyword::MaxLength(count(value, &at)?),
+                "pattern" => match value {
+                    Json::Strings(pattern) => Keyword::Pattern(regex(pattern, &at)?),
+                    _ => return Err(invalid(&at, "a string")),
+                },
+                "allOf" => Keyword::AllOf(self.schema_list(value, &at)?),
+                "anyOf" => Keyword::AnyOf(self.schema_list(value, &at)?),
+                "oneOf" => Keyword::OneOf(self.schema_list(value, &at)?),
+                "not" => Keyword::Not(self.compile(value, &at)?),
+                "if" => {
+                    let mut branch = |name: &str| match map.get(name) {
+                        Some(json) => self.compile(json, &pointer::push(pointer, name)).map(Some),
+                        None => Ok(None),
+                    };
+                    let then = branch("then")?;
+                    let otherwise = branch("else")?;
+                    Keyword::If {
+                        condition: self.compile(value, &at)?,
+                        then,
+                        otherwise,
+                    }
+                }
+                "$ref" => {
+                    let reference = value.as_str().ok_or_else(|| invalid(&at, "a string"))?;
+                    let unresolved = || SchemaError::UnresolvedRef {
+                        pointer: at.clone(),
+                        reference: reference.to_string(),
+                    };
+                    let target = self.resolve(reference).ok_or_else(unresolved)?;
+                    let json = pointer::get(self.root, &target).ok_or_else(unresolved)?;
+                    Keyword::Ref(self.compile(json, &target)?)
+                }
+                "format" => match value {
+                    Json::Strings(name) => Keyword::Format(name.clone()),
+                    _ => return Err(invalid(&at, "a string")),
+                },
+                // annotations, and keywords outside this subset
+                _ => continue,
+            };
+            keywords.push(keyword);
+        }
+        Ok(keywords)
+    }
+
+    /// The pointer a `$ref` leads to: `#/json/pointer` or `#anchor`,
+    /// optionally after the `$id` of a subschema.
+    fn resolve(&self, reference: &str) -> Option<String> {
+        let (base, fragment) = reference.split_once('#').unwrap_or((reference, ""));
+        let base = if base.is_empty() {
+            ""
+        } else {
+            // a relative reference matches an `$id` it ends with
+            self.ids
+                .iter()
+                .find(|(id, _)| {
+                    *id == base
+                        || id
+                            .strip_suffix(base)
+                            .is_some_and(|prefix| prefix.ends_with('/'))
+                })
+                .map(|(_, pointer)| pointer.as_str())?
+        };
+        let fragment = percent_decode(fragment)?;
+        if fragment.is_empty() || fragment.starts_with('/') {
+            Some(format!("{}{}", base, fragment))
+        } else {
+            self.anchors.get(&fragment).cloned()
+        }
+    }
+
+    fn schema_map(
+        &mut self,
+        value: &'a Json,
+        pointer: &str,
+    ) -> Result<Vec<(String, usize)>, SchemaError> {
+        let map = value
+            .as_object()
+            .ok_or_else(|| invalid(pointer, "an object"))?;
+        let mut schemas = Vec::new();
+        for (name, json) in map.iter() {
+            let child = self.compile(json, &pointer::push(pointer, name))?;
+            schemas.push((name.clone(), child));
+        }
+        Ok(schemas)
+    }
+
+    fn schema_list(&mut self, value: &'a Json, pointer: &str) -> Result<Vec<usize>, SchemaError> {
+        let vec = array(value, pointer)?;
+        if vec.is_empty() {
+            return Err(invalid(pointer, "a non-empty array"));
+        }
+        let mut schemas = Vec::new();
+        for (i, json) in vec.iter().enumerate() {
+            schemas.push(self.compile(json, &pointer::push(pointer, &i.to_string()))?);
+        }
+        Ok(schemas)
+    }
+}
+
+/// Fails on a cycle of subschemas that apply to the same value, which
+/// only a `$ref` can close. Keywords like `properties` move on to a part of
+/// the value, so cycles through them end with the value.
+fn cycles(nodes: &[Node]) -> Result<(), SchemaError> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        New,
+        Open,
+        Done,
+    }
+    let mut state = vec![State::New; nodes.len()];
+    for root in 0..nodes.len() {
+        if state[root] != State::New {
+            continue;
+        }
+        state[root] = State::Open;
+        // each node on the path with the keyword and target to try next
+        let mut path = vec![(root, 0, 0)];
+        while let Some((node, keyword, target)) = path.last_mut() {
+            let Some(found) = nodes[*node].keywords.get(*keyword) else {
+                state[*node] = State::Done;
+                path.pop();
+                continue;
+            };
+            let Some(&child) = found.in_place().get(*target) else {
+                *keyword += 1;
+                *target = 0;
+                continue;
+            };
+            *target += 1;
+            match state[child] {
+                State::New => {
+                    state[child] = State::Open;
+                    path.push((child, 0, 0));
+                }
+                State::Open => {
+                    return Err(SchemaError::RefCycle {
+                        pointer: pointer::push(&nodes[*node].pointer, found.name()),
+                    });
+                }
+                State::Done => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The subschemas directly inside `schema`, with their pointers.
+fn subschemas<'a>(schema: &'a Json, pointer: &str) -> Vec<(String, &'a Json)> {
+    let Json::Object(map) = schema else {
+        return Vec::new();
+    };
+    let mut children = Vec::new();
+    for (name, value) in map.iter() {
+        let at = pointer::push(pointer, name);
+        match (name.as_str(), value) {
+            (
+                "additionalProperties"
+                | "propertyNames"
+                | "items"
+                | "contains"
+                | "not"
+                | "if"
+                | "then"
+                | "else",
+                _,
+            ) => children.push((at, value)),
+            (
+                "properties" | "patternProperties" | "$defs" | "definitions" | "dependentSchemas",
+                Json::Object(schemas),
+            ) => {
+                for (key, json) in schemas.iter() {
+                    children.push((pointer::push(&at, key), json));
+                }
+            }
+            ("prefixItems" | "allOf" | "anyOf" | "oneOf", Json::Array(schemas)) => {
+                for (i, json) in schemas.iter().enumerate() {
+                    children.push((pointer::push(&at, &i.to_string()), json));
+                }
+            }
+            _ => {}
+        }
+    }
+    children
+}
+
+fn invalid(pointer: &str, expected: &'static str) -> SchemaError {
+    SchemaError::InvalidKeyword {
+        pointer: pointer.to_string(),
+        expected,
+    }
+}
+
+fn types(value: &Json, pointer: &str) -> Result<Vec<Type>, SchemaError> {
+    let names = match value {
+        Json::Strings(name) => vec![name.clone()],
+        _ => strings(value, pointer)?,
+    };
+    names
+        .iter()
+        .map(|name| {
+            Ok(match name.as_str() {
+                "null" => Type::Json(JsonType::Null),
+                "boolean" => Type::Json(JsonType::Boolean),
+                "number" => Type::Json(JsonType::Number),
+                "string" => Type::Json(JsonType::String),
+                "array" => Type::Json(JsonType::Array),
+                "object" => Type::Json(JsonType::Object),
+                "integer" => Type::Integer,
+                _ => return Err(invalid(pointer, "a JSON Schema type name")),
+            })
+        })
+        .collect()
+}
+
+fn array<'a>(value: &'a Json, pointer: &str) -> Result<&'a Vec<Json>, SchemaError> {
+    value.as_array().ok_or_else(|| invalid(pointer, "an array"))
+}
+
+fn strings(value: &Json, pointer: &str) -> Result<Vec<String>, SchemaError> {
+    array(value, pointer)?
+        .iter()
+        .map(|json| {
+            json.as_str()
+                .map(str::to_string)
+                .ok_or_else(|| invalid(pointer, "an array of strings"))
+        })
+        .collect()
+}
+
+fn num(value: &Json, pointer: &str) -> Result<f64, SchemaError> {
+    value.as_f64().ok_or_else(|| invalid(pointer, "a number"))
+}
+
+fn count(value: &Json, pointer: &str) -> Result<usize, SchemaError> {
+    match value.as_f64() {
+        Some(num) if num >= 0.0 && num.fract() == 0.0 => Ok(num as usize),
+        _ => Err(invalid(pointer, "a non-negative integer")),
+    }
+}
+
+fn regex(pattern: &str, pointer: &str) -> Result<Regex, SchemaError> {
+    Regex::new(pattern).map_err(|error| SchemaError::InvalidPattern {
+        pointer: pointer.to_string(),
+        error,
+    })
+}
+
+// `%XX` escapes in a URI fragment
+fn percent_decode(fragment: &str) -> Option<String> {
+    let bytes = fragment.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = fragment.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    fn paths(schema: &Json, instance: &Json) -> Vec<(String, String)> {
+        let schema = Schema::compile(schema).unwrap();
+        match schema.validate(instance) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .into_iter()
+                .map(|error| (error.instance_path, error.schema_path))
+                .collect(),
+        }
+    }
+
+    fn path(instance: &str, schema: &str) -> (String, String) {
+        (instance.to_string(), schema.to_string())
+    }
+
+    #[test]
+    fn type_enum_and_const() {
+        let schema = Schema::compile(&json!({"type": ["integer", "null"]})).unwrap();
+        assert!(schema.is_valid(&json!(3)));
+        assert!(schema.is_valid(&json!(null)));
+        assert!(!schema.is_valid(&json!(3.5)));
+        assert!(!schema.is_valid(&json!("3")));
+
+        let schema = Schema::compile(&json!({"enum": [1, "a"], "const": 1})).unwrap();
+        assert!(schema.is_valid(&json!(1.0)));
+        assert_eq!(paths(&json!({"const": 1}), &json!(2)), [path("", "/const")]);
+    }
+
+    #[test]
+    fn object_keywords() {
+        let schema = json!({
+            "properties": {"a": {"type": "string"}},
+            "patternProperties": {"^x-": {"type": "integer"}},
+            "additionalProperties": false,
+            "required": ["a"],
+            "propertyNames": {"maxLength": 4},
+            "maxProperties": 3
+        });
+        assert!(paths(&schema, &json!({"a": "", "x-b": 1})).is_empty());
+        assert_eq!(
+            paths(&schema, &json!({"a": 1, "x-b": "", "other": 0})),
+            [
+                path("/a", "/properties/a/type"),
+                path("/x-b", "/patternProperties/^x-/type"),
+                path("", "/additionalProperties"),
+                path("/other", "/propertyNames/maxLength"),
+            ]
+        );
+        assert_eq!(paths(&schema, &json!({})), [path("", "/required")]);
+    }
+
+    #[test]
+    fn array_keywords() {
+        let schema = json!({
+            "prefixItems": [{"type": "string"}],
+            "items": {"type": "integer"},
+            "contains": {"const": 0},
+            "maxContains": 1,
+            "uniqueItems": true,
+            "minItems": 2
+        });
+        assert!(paths(&schema, &json!(["a", 0, 1])).is_empty());
+        assert_eq!(
+            paths(&schema, &json!(["a", 0, 0, "b"])),
+            [
+                path("/3", "/items/type"),
+                path("", "/contains"),
+                path("", "/uniqueItems"),
+            ]
+        );
+        assert_eq!(
+            paths(&schema, &json!(["a"])),
+            [path("", "/contains"), path("", "/minItems")]
+        );
+    }
+
+    #[test]
+    fn number_and_string_keywords() {
+        let schema = Schema::compile(&json!({
+            "minimum": 0, "exclusiveMaximum": 10, "multipleOf": 0.5,
+            "minLength": 2, "pattern": "^[a-z]+$"
+        }))
+        .unwrap();
+        assert!(schema.is_valid(&json!(9.5)));
+        assert!(!schema.is_valid(&json!(10)));
+        assert!(!schema.is_valid(&json!(-1)));
+        assert!(!schema.is_valid(&json!(0.3)));
+        assert!(schema.is_valid(&json!("ab")));
+        assert!(!schema.is_valid(&json!("a")));
+        assert!(!schema.is_valid(&json!("AB")));
+    }
+
+    #[test]
+    fn combinators() {
+        let schema = Schema::compile(&json!({
+            "anyOf": [{"type": "string"}, {"type": "number"}],
+            "oneOf": [{"type": "string"}, {"type": "number", "minimum": 1}],
+            "not": {"const": "no"},
+            "if": {"type": "string"},
+            "then": {"minLength": 2},
+            "else": {"multipleOf": 2}
+        }))
+        .unwrap();
+        assert!(schema.is_valid(&json!("ok")));
+        assert!(schema.is_valid(&json!(4)));
+        assert!(!schema.is_valid(&json!(0)));
+        assert!(!schema.is_valid(&json!("no")));
+        assert!(!schema.is_valid(&json!("a")));
+        assert!(!schema.is_valid(&json!(3)));
+        assert!(!schema.is_valid(&json!(true)));
+    }
+
+    #[test]
+    fn references() {
+        let schema = json!({
+            "$id": "https://example.com/tree",
+            "type": "object",
+            "properties": {
+                "value": {"$ref": "#leaf"},
+                "children": {"type": "array", "items": {"$ref": "https://example.com/tree"}}
+            },
+            "$defs": {"leaf": {"$anchor": "leaf", "type": "integer"}}
+        });
+        let tree = json!({"value": 1, "children": [{"value": 2, "children": [{"value": "x"}]}]});
+        assert_eq!(
+            paths(&schema, &tree),
+            [path("/children/0/children/0/value", "/$defs/leaf/type")]
+        );
+    }
+
+    #[test]
+    fn formats_are_annotations_unless_checked() {
+        let schema = json!({"properties": {"day": {"format": "date"}}});
+        let bad = json!({"day": "2024-02-30"});
+        let good = json!({"day": "2024-02-29"});
+
+        let annotating = Schema::compile(&schema).unwrap();
+        assert!(annotating.is_valid(&bad));
+        assert!(annotating.is_valid(&good));
+
+        let checking = Schema::compile(&schema).unwrap().check_formats(true);
+        let errors = checking.validate(&bad).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].instance_path, "/day");
+        assert_eq!(errors[0].schema_path, "/properties/day/format");
+        assert!(checking.is_valid(&good));
+        // formats only apply to strings, and unknown ones always pass
+        assert!(checking.is_valid(&json!({"day": 20240230})));
+        let unknown = Schema::compile(&json!({"format": "shoe-size"})).unwrap();
+        assert!(unknown.check_formats(true).is_valid(&json!("xl")));
+
+        let off_again = Schema::compile(&schema)
+            .unwrap()
+            .check_formats(true)
+            .check_formats(false);
+        assert!(off_again.is_valid(&bad));
+    }
+
+    #[test]
+    fn invalid_schemas() {
+        assert_eq!(
+            Schema::compile(&json!({"minLength": "2"})).unwrap_err(),
+            SchemaError::InvalidKeyword {
+                pointer: "/minLength".to_string(),
+                expected: "a non-negative integer",
+            }
+        );
+        assert!(matches!(
+            Schema::compile(&json!({"pattern": "("})),
+            Err(SchemaError::InvalidPattern { .. })
+        ));
+        assert_eq!(
+            Schema::compile(&json!({"$ref": "#/$defs/missing"})).unwrap_err(),
+            SchemaError::UnresolvedRef {
+                pointer: "/$ref".to_string(),
+                reference: "#/$defs/missing".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn reference_cycles_on_the_same_value_are_rejected() {
+        let error = Schema::compile(&json!({
+            "$defs": {"a": {"$ref": "#/$defs/a"}},
+            "$ref": "#/$defs/a"
+        }))
+        .unwrap_err();
+        assert_eq!(
+            error,
+            SchemaError::RefCycle {
+                pointer: "/$defs/a/$ref".to_string()
+            }
+        );
+        assert!(Schema::compile(&json!({"anyOf": [{"type": "null"}, {"$ref": "#"}]})).is_err());
+
+        // recursion through a member moves on to a smaller value
+        let schema = Schema::compile(&json!({"properties": {"next": {"$ref": "#"}}})).unwrap();
+        assert!(schema.is_valid(&json!({"next": {"next": {}}})));
+    }
+}
@@ -0,0 +1,305 @@
+//! Checks for the common `format` values. Unknown formats pass.
+
+use super::regex::Regex;
+use crate::pointer;
+
+/// Whether `value` is valid for `format`; `None` when the format is not
+/// one this module knows.
+pub fn check(format: &str, value: &str) -> Option<bool> {
+    Some(match format {
+        "date-time" => is_date_time(value),
+        "date" => is_date(value),
+        "time" => is_time(value),
+        "duration" => is_duration(value),
+        "email" => is_email(value),
+        "hostname" => is_hostname(value),
+        "ipv4" => is_ipv4(value),
+        "ipv6" => is_ipv6(value),
+        "uri" => is_uri(value),
+        "uri-reference" => !value.chars().any(|ch| ch.is_whitespace()),
+        "uuid" => is_uuid(value),
+        "json-pointer" => value.is_empty() || pointer::tokens(value).is_some() && !bad_tilde(value),
+        "regex" => Regex::new(value).is_ok(),
+        _ => return None,
+    })
+}
+
+fn digits(s: &str, len: usize) -> Option<u32> {
+    if s.len() == len && s.bytes().all(|b| b.is_ascii_digit()) {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
+// full-date = YYYY-MM-DD (RFC 3339)
+fn is_date(value: &str) -> bool {
+    let mut parts = value.split('-');
+    let (Some(year), Some(month), Some(day), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+    let (Some(year), Some(month), Some(day)) = (digits(year, 4), digits(month, 2), digits(day, 2))
+    else {
+        return false;
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days).contains(&day)
+}
+
+// full-time = HH:MM:SS[.frac](Z / +HH:MM / -HH:MM)
+fn is_time(value: &str) -> bool {
+    let value = value.to_ascii_uppercase();
+    let (time, offset) = if let Some(time) = value.strip_suffix('Z') {
+        (time, None)
+    } else {
+        match value.rfind(['+', '-']) {
+            Some(at) => (&value[..at], Some(&value[at + 1..])),
+            None => return false,
+        }
+    };
+    if let Some(offset) = offset {
+        let Some((hours, minutes)) = offset.split_once(':') else {
+            return false;
+        };
+        if digits(hours, 2).is_none_or(|h| h >= 24) || digits(minutes, 2).is_none_or(|m| m >= 60) {
+            return false;
+        }
+    }
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) => (time, Some(fraction)),
+        None => (time, None),
+    };
+    if fraction.is_some_and(|f| f.is_empty() || !f.bytes().all(|b| b.is_ascii_digit())) {
+        return false;
+    }
+    let mut parts = time.split(':');
+    let (Some(hour), Some(minute), Some(second), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+    // 60 allows for a leap second
+    digits(hour, 2).is_some_and(|h| h < 24)
+        && digits(minute, 2).is_some_and(|m| m < 60)
+        && digits(second, 2).is_some_and(|s| s <= 60)
+}
+
+fn is_date_time(value: &str) -> bool {
+    match value.split_once(['T', 't']) {
+        Some((date, time)) => is_date(date) && is_time(time),
+        None => false,
+    }
+}
+
+// ISO 8601 durations such as P3Y6M4DT12H30M5S or P2W
+fn is_duration(value: &str) -> bool {
+    let Some(rest) = value.strip_prefix('P') else {
+        return false;
+    };
+    if rest.is_empty() {
+        return false;
+    }
+    if let Some(weeks) = rest.strip_suffix('W') {
+        return !weeks.is_empty() && weeks.bytes().all(|b| b.is_ascii_digit());
+    }
+    let (date, time) = match rest.split_once('T') {
+        Some((_, "")) => return false,
+        Some((date, time)) => (date, Some(time)),
+        None => (rest, None),
+    };
+    units(date, "YMD") && time.is_none_or(|time| units(time, "HMS"))
+}
+
+// digits followed by unit letters, each unit at most once and in order
+fn units(s: &str, order: &str) -> bool {
+    let mut allowed = order;
+    let mut number = false;
+    for ch in s.chars() {
+        if ch.is_ascii_digit() {
+            number = true;
+        } else {
+            match allowed.find(ch) {
+                Some(at) if number => allowed = &allowed[at + 1..],
+                _ => return false,
+            }
+            number = false;
+        }
+    }
+    !number
+}
+
+fn is_email(value: &str) -> bool {
+    match value.rsplit_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && local.len() <= 64
+                && !local.chars().any(|ch| ch.is_whitespace() || ch == '@')
+                && !local.starts_with('.')
+                && !local.ends_with('.')
+                && !local.contains("..")
+                && (is_hostname(domain)
+                    || domain
+                        .strip_prefix('[')
+                        .and_then(|d| d.strip_suffix(']'))
+                        .is_some_and(|ip| {
+                            is_ipv4(ip) || ip.strip_prefix("IPv6:").is_some_and(is_ipv6)
+                        }))
+        }
+        None => false,
+    }
+}
+
+fn is_hostname(value: &str) -> bool {
+    let value = value.strip_suffix('.').unwrap_or(value);
+    !value.is_empty()
+        && value.len() <= 253
+        && value.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
+
+fn is_ipv4(value: &str) -> bool {
+    let parts: Vec<&str> = value.split('.').collect();
+    parts.len() == 4
+        && parts.iter().all(|part| {
+            !part.is_empty()
+                && part.len() <= 3
+                && part.bytes().all(|b| b.is_ascii_digit())
+                // no leading zeros
+                && (part.len() == 1 || !part.starts_with('0'))
+                && part.parse::<u32>().is_ok_and(|n| n <= 255)
+        })
+}
+
+fn is_ipv6(value: &str) -> bool {
+    // an embedded IPv4 address takes the place of the last two groups
+    let (value, max_groups) = match value.rsplit_once(':') {
+        Some((head, last)) if last.contains('.') => {
+            if !is_ipv4(last) {
+                return false;
+            }
+            // drop the `:` before the address unless it ends a `::`
+            let end = if value[..=head.len()].ends_with("::") {
+                head.len() + 1
+            } else {
+                head.len()
+            };
+            (&value[..end], 6)
+        }
+        _ => (value, 8),
+    };
+    fn groups(s: &str) -> Vec<&str> {
+        if s.is_empty() {
+            Vec::new()
+        } else {
+            s.split(':').collect()
+        }
+    }
+    let (groups, compressed) = match value.split_once("::") {
+        Some((head, tail)) => ([groups(head), groups(tail)].concat(), true),
+        None => (value.split(':').collect(), false),
+    };
+    let hex = |g: &&str| !g.is_empty() && g.len() <= 4 && g.bytes().all(|b| b.is_ascii_hexdigit());
+    // `::` stands for at least one group
+    (if compressed {
+        groups.len() < max_groups
+    } else {
+        groups.len() == max_groups
+    }) && groups.iter().all(hex)
+}
+
+// scheme ":" hier-part, with no spaces
+fn is_uri(value: &str) -> bool {
+    match value.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.starts_with(|ch: char| ch.is_ascii_alphabetic())
+                && scheme
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'-' | b'.'))
+                && !value
+                    .chars()
+                    .any(|ch| ch.is_whitespace() || ch.is_control())
+        }
+        None => false,
+    }
+}
+
+fn is_uuid(value: &str) -> bool {
+    let groups: Vec<&str> = value.split('-').collect();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+// `~` must be followed by 0 or 1
+fn bad_tilde(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes
+        .iter()
+        .enumerate()
+        .any(|(i, &b)| b == b'~' && !matches!(bytes.get(i + 1), Some(b'0' | b'1')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+
+    #[test]
+    fn known_formats() {
+        let cases = [
+            (
+                "date-time",
+                "2024-02-29T12:30:00.5+01:00",
+                "2024-02-29 12:30:00",
+            ),
+            ("date", "2024-02-29", "2023-02-29"),
+            ("time", "23:59:60Z", "24:00:00Z"),
+            ("duration", "P1Y2M3DT4H", "P1Y2M3DT"),
+            ("email", "a.b@example.com", "a@@example.com"),
+            ("hostname", "example.com", "-example.com"),
+            ("ipv4", "192.168.0.1", "192.168.0.256"),
+            ("ipv6", "::ffff:192.168.0.1", "1::2::3"),
+            ("uri", "https://example.com/a?b#c", "/relative"),
+            ("uri-reference", "/relative", "a b"),
+            (
+                "uuid",
+                "123e4567-e89b-12d3-a456-426614174000",
+                "123e4567e89b12d3",
+            ),
+            ("json-pointer", "/a~1b/0", "a/b"),
+            ("regex", "^[a-z]+$", "(unclosed"),
+        ];
+        for (format, valid, invalid) in cases {
+            assert_eq!(check(format, valid), Some(true), "{} {:?}", format, valid);
+            assert_eq!(
+                check(format, invalid),
+                Some(false),
+                "{} {:?}",
+                format,
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_formats_are_not_checked() {
+        assert_eq!(check("color", "red"), None);
+    }
+}
@@ -0,0 +1,405 @@
+//! Inferring a schema from sample documents.
+//!
+//! Every sample is folded into one summary of what was seen at each
+//! position: which types, which object members and how often, the range of
+//! the numbers and the distinct strings. The summary is then written out as
+//! a schema that all the samples satisfy.
+//!
+//! ```
+//! use json_parser::json;
+//! use json_parser::schema::Schema;
+//! use json_parser::schema::infer::infer;
+//!
+//! let samples = [
+//!     json!({"city": "SUNNYVALE", "state": "CA", "zip": 94085}),
+//!     json!({"city": "SAN FRANCISCO", "state": "CA", "zip": 94107, "note": null}),
+//! ];
+//! let schema = infer(&samples);
+//!
+//! assert_eq!(schema["required"], json!(["city", "state", "zip"]));
+//! assert_eq!(schema["properties"]["state"]["enum"], json!(["CA"]));
+//! assert_eq!(schema["properties"]["zip"]["type"], json!("integer"));
+//! assert_eq!(schema["properties"]["zip"]["minimum"], json!(94085));
+//!
+//! let schema = Schema::compile(&schema).unwrap();
+//! assert!(samples.iter().all(|sample| schema.is_valid(sample)));
+//! ```
+
+use super::format;
+use crate::map::Map;
+use crate::parsen::{Json, ParseError, Parser, ParserOptions};
+
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Formats worth guessing: ones an arbitrary word is unlikely to pass.
+const FORMATS: [&str; 7] = ["date-time", "date", "time", "email", "uuid", "ipv4", "ipv6"];
+
+#[derive(Debug, Clone)]
+pub struct InferOptions {
+    /// The most distinct values a string may take and still be written as
+    /// an `enum`; 0 never infers one. Each value must also have been seen
+    /// at least twice on average, so a handful of unique names is not taken
+    /// for an enum.
+    pub enum_limit: usize,
+    /// Add `minimum` and `maximum` from the numbers seen.
+    pub ranges: bool,
+    /// Add a `format` when every string seen has one of the common formats.
+    pub formats: bool,
+}
+
+impl Default for InferOptions {
+    fn default() -> Self {
+        Self {
+            enum_limit: 8,
+            ranges: true,
+            formats: true,
+        }
+    }
+}
+
+/// A schema describing all of `samples`, with the default options.
+pub fn infer<'a>(samples: impl IntoIterator<Item = &'a Json>) -> Json {
+    let mut inference = Inference::new(InferOptions::default());
+    for sample in samples {
+        inference.add(sample);
+    }
+    inference.schema()
+}
+
+/// Samples folded in so far.
+#[derive(Debug, Clone)]
+pub struct Inference {
+    options: InferOptions,
+    root: Shape,
+}
+
+impl Inference {
+    pub fn new(options: InferOptions) -> Inference {
+        Inference {
+            options,
+            root: Shape::default(),
+        }
+    }
+
+    pub fn add(&mut self, sample: &Json) {
+        self.root.add(sample, &self.options);
+    }
+
+    /// Adds every line of newline-delimited JSON, skipping blank lines.
+    /// Returns how many samples were added. A line that does not parse
+    /// stops the stream, keeping the lines before it; the error gives its
+    /// place in `text`.
+    pub fn add_ndjson(&mut self, text: &str) -> Result<usize, ParseError> {
+        let mut added = 0;
+        let mut offset = 0;
+        for (number, line) in text.split_inclusive('\n').enumerate() {
+            if !line.trim().is_empty() {
+                let mut parser =
+                    Parser::from_string_recovering(line.to_string(), ParserOptions::default());
+                let recovered = parser.parse_recovering();
+                if let Some(error) = recovered.errors.into_iter().next() {
+                    return Err(error.shifted(number as u32, offset));
+                }
+                self.add(&recovered.json);
+                added += 1;
+            }
+            offset += line.len();
+        }
+        Ok(added)
+    }
+
+    /// How many samples were added.
+    pub fn len(&self) -> usize {
+        self.root.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.count == 0
+    }
+
+    /// The inferred schema. With no samples it has no `type` and allows
+    /// anything.
+    pub fn schema(&self) -> Json {
+        let mut schema = Map::new();
+        schema.insert("$schema".to_string(), Json::Strings(DIALECT.to_string()));
+        if let Json::Object(map) = self.root.schema(&self.options) {
+            for (key, value) in map.iter() {
+                schema.insert(key.clone(), value.clone());
+            }
+        }
+        Json::Object(Box::new(schema))
+    }
+}
+
+/// Everything seen at one position of the samples.
+#[derive(Debug, Clone, Default)]
+struct Shape {
+    count: usize,
+    nulls: usize,
+    booleans: usize,
+    numbers: Option<Numbers>,
+    strings: Option<Strings>,
+    /// The shape of every item of every array.
+    items: Option<Box<Shape>>,
+    object: Option<Members>,
+}
+
+#[derive(Debug, Clone)]
+struct Numbers {
+    min: f64,
+    max: f64,
+    integer: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Strings {
+    count: usize,
+    /// Distinct values in the order first seen; `None` once there are too
+    /// many for an enum.
+    values: Option<Vec<String>>,
+    /// The formats every string so far has.
+    formats: Vec<&'static str>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Members {
+    count: usize,
+    /// In the order first seen.
+    properties: Vec<(String, Shape)>,
+}
+
+impl Shape {
+    fn add(&mut self, json: &Json, options: &InferOptions) {
+        self.count += 1;
+        match json {
+            Json::Null => self.nulls += 1,
+            Json::Boolean(_) => self.booleans += 1,
+            Json::Number(num) => {
+                let numbers = self.numbers.get_or_insert(Numbers {
+                    min: *num,
+                    max: *num,
+                    integer: true,
+                });
+                numbers.min = numbers.min.min(*num);
+                numbers.max = numbers.max.max(*num);
+                numbers.integer &= num.fract() == 0.0;
+            }
+            Json::Strings(st) => {
+                let strings = self.strings.get_or_insert_with(|| Strings {
+                    count: 0,
+                    values: Some(Vec::new()),
+                    formats: if options.formats {
+                        FORMATS.to_vec()
+                    } else {
+                        Vec::new()
+                    },
+                });
+                strings.count += 1;
+                if let Some(values) = &mut strings.values {
+                    if !values.contains(st) {
+                        values.push(st.clone());
+                    }
+                    if values.len() > options.enum_limit {
+                        strings.values = None;
+                    }
+                }
+                strings
+                    .formats
+                    .retain(|name| format::check(name, st) == Some(true));
+            }
+            Json::Array(vec) => {
+                let items = self.items.get_or_insert_with(Box::default);
+                for item in vec {
+                    items.add(item, options);
+                }
+            }
+            Json::Object(map) => {
+                let members = self.object.get_or_insert_with(Members::default);
+                members.count += 1;
+                for (key, value) in map.iter() {
+                    let at = match members.properties.iter().position(|(name, _)| name == key) {
+                        Some(at) => at,
+                        None => {
+                            members.properties.push((key.clone(), Shape::default()));
+                            members.properties.len() - 1
+                        }
+                    };
+                    members.properties[at].1.add(value, options);
+                }
+            }
+        }
+    }
+
+    fn schema(&self, options: &InferOptions) -> Json {
+        let mut types = Vec::new();
+        let mut keywords: Vec<(&str, Json)> = Vec::new();
+        if self.nulls > 0 {
+            types.push("null");
+        }
+        if self.booleans > 0 {
+            types.push("boolean");
+        }
+        if let Some(numbers) = &self.numbers {
+            types.push(if numbers.integer { "integer" } else { "number" });
+            if options.ranges {
+                keywords.push(("minimum", Json::Number(numbers.min)));
+                keywords.push(("maximum", Json::Number(numbers.max)));
+            }
+        }
+        if let Some(strings) = &self.strings {
+            types.push("string");
+            if let Some(format) = strings.formats.first() {
+                keywords.push(("format", Json::Strings(format.to_string())));
+            }
+        }
+        if let Some(items) = &self.items {
+            types.push("array");
+            // only empty arrays were seen, so nothing is known of the items
+            if items.count > 0 {
+                keywords.push(("items", items.schema(options)));
+            }
+        }
+        if let Some(members) = &self.object {
+            types.push("object");
+            let mut properties = Map::new();
+            let mut required = Vec::new();
+            for (name, shape) in &members.properties {
+                properties.insert(name.clone(), shape.schema(options));
+                if shape.count == members.count {
+                    required.push(Json::Strings(name.clone()));
+                }
+            }
+            if !properties.is_empty() {
+                keywords.push(("properties", Json::Object(Box::new(properties))));
+            }
+            if !required.is_empty() {
+                keywords.push(("required", Json::Array(required)));
+            }
+        }
+        // an enum restricts every type, so it only fits strings and nulls
+        if let Some(values) = self.enumerable(options)
+            && types.iter().all(|ty| matches!(*ty, "string" | "null"))
+        {
+            let mut values: Vec<Json> = values.iter().cloned().map(Json::Strings).collect();
+            if self.nulls > 0 {
+                values.push(Json::Null);
+            }
+            keywords.retain(|(name, _)| *name != "format");
+            keywords.push(("enum", Json::Array(values)));
+        }
+
+        let mut schema = Map::new();
+        match types[..] {
+            [] => {}
+            [ty] => {
+                schema.insert("type".to_string(), Json::Strings(ty.to_string()));
+            }
+            _ => {
+                let types = types.iter().map(|ty| Json::Strings(ty.to_string()));
+                schema.insert("type".to_string(), Json::Array(types.collect()));
+            }
+        }
+        for (name, value) in keywords {
+            schema.insert(name.to_string(), value);
+        }
+        Json::Object(Box::new(schema))
+    }
+
+    /// The distinct strings, if they are few and repeat.
+    fn enumerable(&self, options: &InferOptions) -> Option<&Vec<String>> {
+        let strings = self.strings.as_ref()?;
+        let values = strings.values.as_ref()?;
+        (values.len() <= options.enum_limit && values.len() * 2 <= strings.count).then_some(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    fn from_ndjson(text: &str) -> Inference {
+        let mut inference = Inference::new(InferOptions::default());
+        inference.add_ndjson(text).unwrap();
+        inference
+    }
+
+    #[test]
+    fn each_line_is_a_sample() {
+        let mut inference = Inference::new(InferOptions::default());
+        assert_eq!(inference.add_ndjson("1\n\n  \n2\r\n3"), Ok(3));
+        assert_eq!(inference.add_ndjson("4\n"), Ok(1));
+        assert_eq!(inference.len(), 4);
+        let schema = inference.schema();
+        assert_eq!(schema["type"], json!("integer"));
+        assert_eq!(
+            (&schema["minimum"], &schema["maximum"]),
+            (&json!(1), &json!(4))
+        );
+        assert_eq!(
+            Inference::new(InferOptions::default()).add_ndjson(""),
+            Ok(0)
+        );
+    }
+
+    #[test]
+    fn conflicting_types_across_lines_are_merged() {
+        let schema =
+            from_ndjson("{\"a\": 1}\n{\"a\": \"x\"}\n{\"a\": null}\n{\"a\": 2.5}\n").schema();
+        let a = &schema["properties"]["a"];
+        assert_eq!(a["type"], json!(["null", "number", "string"]));
+        assert_eq!((&a["minimum"], &a["maximum"]), (&json!(1), &json!(2.5)));
+
+        let schema = from_ndjson("[1]\n{\"k\": true}\n").schema();
+        assert_eq!(schema["type"], json!(["array", "object"]));
+        assert_eq!(schema["items"]["type"], json!("integer"));
+        assert_eq!(schema["properties"]["k"]["type"], json!("boolean"));
+    }
+
+    #[test]
+    fn members_missing_from_some_lines_are_optional() {
+        let text = r#"{"id": 1, "tags": ["a"], "owner": {"name": "x", "mail": "x@example.com"}}
+{"id": 2, "owner": {"name": "y"}}
+{"id": 3, "tags": [], "note": null, "owner": {"name": "z"}}
+"#;
+        let schema = from_ndjson(text).schema();
+        assert_eq!(schema["required"], json!(["id", "owner"]));
+        let properties = schema["properties"].as_object().unwrap();
+        let names: Vec<&String> = properties.keys().collect();
+        assert_eq!(names, ["id", "tags", "owner", "note"]);
+        assert_eq!(schema["properties"]["owner"]["required"], json!(["name"]));
+        assert_eq!(schema["properties"]["note"]["type"], json!("null"));
+    }
+
+    #[test]
+    fn a_bad_line_reports_its_line_number() {
+        let mut inference = Inference::new(InferOptions::default());
+        let text = "{\"a\": 1}\n\n{\"a\": @}\n{\"a\": 2}\n";
+        let err = inference.add_ndjson(text).unwrap_err();
+        assert_eq!((err.line(), err.column()), (3, 7));
+        assert_eq!(&text[err.span()], "@");
+        assert_eq!(err.pointer(), "/a");
+        // the lines before it were kept, the ones after it were not
+        assert_eq!(inference.len(), 1);
+
+        let err = inference.add_ndjson("1\n[1,").unwrap_err();
+        assert_eq!(err.line(), 2);
+        assert_eq!(inference.len(), 2);
+        // one document per line
+        let err = inference.add_ndjson("1 2\n").unwrap_err();
+        assert_eq!((err.line(), err.column()), (1, 3));
+    }
+
+    #[test]
+    fn the_schema_accepts_every_line() {
+        let text = "{\"when\": \"2024-01-02\", \"n\": 1}\n{\"when\": \"2025-12-31\", \"n\": -3}\n";
+        let schema = from_ndjson(text).schema();
+        assert_eq!(schema["properties"]["when"]["format"], json!("date"));
+        let compiled = crate::schema::Schema::compile(&schema)
+            .unwrap()
+            .check_formats(true);
+        for line in text.lines() {
+            assert!(compiled.is_valid(&Parser::from_string(line.to_string()).parse()));
+        }
+    }
+}
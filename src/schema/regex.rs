@@ -0,0 +1,857 @@
+//! A small engine for the ECMA-262 patterns used by `pattern`,
+//! `patternProperties` and the `regex` format.
+//!
+//! Supported: literals, `.`, classes with ranges and negation, the `\d \w
+//! \s` escapes and their negations, `\b \B`, `^ $`, groups (capturing,
+//! `(?:)` and named, though nothing is captured), lookahead, alternation,
+//! and the `* + ? {n} {n,} {n,m}` quantifiers, greedy or lazy. Escapes
+//! the engine does not implement, such as `\p{L}` or a backreference, are
+//! errors rather than literals; only syntax characters like `\.` may be
+//! escaped to stand for themselves. Matching is a search anywhere in the
+//! input, as JSON Schema requires. Patterns run on a Pike VM, and each
+//! lookahead is answered for every position of the input in one pass, so
+//! matching time is linear in the input and no pattern can make it
+//! backtrack.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegexError {
+    /// Character offset into the pattern.
+    pub position: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for RegexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for RegexError {}
+
+#[derive(Debug, Clone)]
+pub struct Regex {
+    source: String,
+    program: Program,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Empty,
+    Char(char),
+    /// `.`, anything but a line terminator.
+    Any,
+    Class(Class),
+    Start,
+    End,
+    /// `\b`, or `\B` when false.
+    WordBoundary(bool),
+    /// `(?=...)`, or `(?!...)` when negated.
+    Lookahead(Box<Node>, bool),
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: u32,
+        max: Option<u32>,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Perl {
+    Digit,
+    Word,
+    Space,
+}
+
+impl Perl {
+    fn matches(self, ch: char) -> bool {
+        match self {
+            Perl::Digit => ch.is_ascii_digit(),
+            Perl::Word => is_word(ch),
+            Perl::Space => ch.is_whitespace() || ch == '\u{FEFF}',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Class {
+    negated: bool,
+    ranges: Vec<(char, char)>,
+    /// `\d`-style escapes, each possibly negated.
+    perls: Vec<(Perl, bool)>,
+}
+
+impl Class {
+    fn matches(&self, ch: char) -> bool {
+        let found = self.ranges.iter().any(|&(lo, hi)| lo <= ch && ch <= hi)
+            || self
+                .perls
+                .iter()
+                .any(|&(perl, negated)| perl.matches(ch) != negated);
+        found != self.negated
+    }
+}
+
+fn is_word(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_'
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Regex, RegexError> {
+        let mut parser = RegexParser {
+            chars: pattern.chars().collect(),
+            pos: 0,
+            depth: 0,
+        };
+        let node = parser.alternation()?;
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error("unmatched `)`"));
+        }
+        let program = Program::compile(&node).ok_or(RegexError {
+            position: 0,
+            message: "pattern too large",
+        })?;
+        Ok(Regex {
+            source: pattern.to_string(),
+            program,
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether the pattern matches anywhere in `text`.
+    pub fn is_match(&self, text: &str) -> bool {
+        let input: Vec<char> = text.chars().collect();
+        self.program.run(&input)
+    }
+}
+
+/// The most instructions a pattern may compile to; counted repeats are
+/// unrolled, so `(a{1000}){1000}` would otherwise take a million.
+const PROGRAM_LIMIT: usize = 100_000;
+
+/// How deep groups may nest in a pattern.
+const DEPTH_LIMIT: usize = 256;
+
+#[derive(Debug, Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Class),
+    Start,
+    End,
+    WordBoundary(bool),
+    Lookahead(Box<Program>, bool),
+    /// Continue at both targets.
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+/// A pattern compiled for a Pike VM. Every way the pattern can go is a
+/// thread, and all threads advance over the input one character at a
+/// time, so matching takes time proportional to the input length times the
+/// program length and never backtracks.
+#[derive(Debug, Clone)]
+struct Program {
+    insts: Vec<Inst>,
+}
+
+impl Program {
+    fn compile(node: &Node) -> Option<Program> {
+        let mut program = Program { insts: Vec::new() };
+        program.emit(node)?;
+        program.push(Inst::Match)?;
+        Some(program)
+    }
+
+    fn push(&mut self, inst: Inst) -> Option<usize> {
+        if self.insts.len() >= PROGRAM_LIMIT {
+            return None;
+        }
+        self.insts.push(inst);
+        Some(self.insts.len() - 1)
+    }
+
+    // patches the targets of a `Split` or `Jump` emitted before its
+    // targets were known
+    fn patch(&mut self, at: usize, target: usize) {
+        match &mut self.insts[at] {
+            Inst::Split(_, second) => *second = target,
+            Inst::Jump(to) => *to = target,
+            _ => unreachable!("only splits and jumps are patched"),
+        }
+    }
+
+    fn emit(&mut self, node: &Node) -> Option<()> {
+        match node {
+            Node::Empty => {}
+            Node::Char(ch) => {
+                self.push(Inst::Char(*ch))?;
+            }
+            Node::Any => {
+                self.push(Inst::Any)?;
+            }
+            Node::Class(class) => {
+                self.push(Inst::Class(class.clone()))?;
+            }
+            Node::Start => {
+                self.push(Inst::Start)?;
+            }
+            Node::End => {
+                self.push(Inst::End)?;
+            }
+            Node::WordBoundary(wanted) => {
+                self.push(Inst::WordBoundary(*wanted))?;
+            }
+            Node::Lookahead(node, negated) => {
+                let program = Program::compile(node)?;
+                self.push(Inst::Lookahead(Box::new(program), *negated))?;
+            }
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    self.emit(node)?;
+                }
+            }
+            Node::Alternation(nodes) => {
+                let mut jumps = Vec::new();
+                for (i, node) in nodes.iter().enumerate() {
+                    if i + 1 == nodes.len() {
+                        self.emit(node)?;
+                    } else {
+                        let split = self.push(Inst::Split(self.insts.len() + 1, 0))?;
+                        self.emit(node)?;
+                        jumps.push(self.push(Inst::Jump(0))?);
+                        self.patch(split, self.insts.len());
+                    }
+                }
+                for jump in jumps {
+                    self.patch(jump, self.insts.len());
+                }
+            }
+            Node::Repeat { node, min, max } => {
+                for _ in 0..*min {
+                    self.emit(node)?;
+                }
+                match max {
+                    None => {
+                        let split = self.push(Inst::Split(self.insts.len() + 1, 0))?;
+                        self.emit(node)?;
+                        self.push(Inst::Jump(split))?;
+                        self.patch(split, self.insts.len());
+                    }
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.push(Inst::Split(self.insts.len() + 1, 0))?);
+                            self.emit(node)?;
+                        }
+                        for split in splits {
+                            self.patch(split, self.insts.len());
+                        }
+                    }
+                }
+            }
+        }
+        Some(())
+    }
+
+    /// Whether the program matches anywhere in `input`.
+    fn run(&self, input: &[char]) -> bool {
+        let mut current = Threads::new(self.insts.len());
+        let mut next = Threads::new(self.insts.len());
+        let mut lookaheads = vec![None; self.insts.len()];
+        for pos in 0..=input.len() {
+            if self.add(&mut current, 0, input, pos, &mut lookaheads) {
+                return true;
+            }
+            let Some(&ch) = input.get(pos) else {
+                break;
+            };
+            for i in 0..current.pcs.len() {
+                let pc = current.pcs[i];
+                if self.insts[pc].accepts(ch)
+                    && self.add(&mut next, pc + 1, input, pos + 1, &mut lookaheads)
+                {
+                    return true;
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+        }
+        false
+    }
+
+    /// Adds the thread at `pc` to `threads`, following jumps, splits and
+    /// assertions to the instructions that consume a character. Returns
+    /// true on reaching `Match`.
+    fn add(
+        &self,
+        threads: &mut Threads,
+        pc: usize,
+        input: &[char],
+        pos: usize,
+        lookaheads: &mut Lookaheads,
+    ) -> bool {
+        let mut stack = vec![pc];
+        while let Some(pc) = stack.pop() {
+            if !threads.visit(pc) {
+                continue;
+            }
+            match &self.insts[pc] {
+                Inst::Match => return true,
+                Inst::Jump(to) => stack.push(*to),
+                Inst::Split(first, second) => {
+                    stack.push(*second);
+                    stack.push(*first);
+                }
+                Inst::Char(_) | Inst::Any | Inst::Class(_) => threads.pcs.push(pc),
+                _ => {
+                    if self.passes(pc, input, pos, lookaheads) {
+                        stack.push(pc + 1);
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Whether the assertion at `pc` holds at `pos`.
+    fn passes(&self, pc: usize, input: &[char], pos: usize, lookaheads: &mut Lookaheads) -> bool {
+        match &self.insts[pc] {
+            Inst::Start => pos == 0,
+            Inst::End => pos == input.len(),
+            Inst::WordBoundary(wanted) => {
+                let before = pos > 0 && is_word(input[pos - 1]);
+                let after = input.get(pos).is_some_and(|&ch| is_word(ch));
+                (before != after) == *wanted
+            }
+            Inst::Lookahead(program, negated) => {
+                let matches = lookaheads[pc].get_or_insert_with(|| program.matches_from(input));
+                matches[pos] != *negated
+            }
+            _ => unreachable!("only assertions are checked"),
+        }
+    }
+
+    /// Whether the program matches starting at each position of `input`,
+    /// for a lookahead. A thread's fate depends only on its instruction
+    /// and position, not on where it started, so the positions are worked
+    /// out from the end, each from the one after it, and every start is
+    /// answered in one pass instead of one run each.
+    fn matches_from(&self, input: &[char]) -> Vec<bool> {
+        let len = self.insts.len();
+        // the instructions that continue at each one without consuming
+        let mut before = vec![Vec::new(); len];
+        for (pc, inst) in self.insts.iter().enumerate() {
+            match inst {
+                Inst::Jump(to) => before[*to].push(pc),
+                Inst::Split(first, second) => {
+                    before[*first].push(pc);
+                    before[*second].push(pc);
+                }
+                Inst::Start | Inst::End | Inst::WordBoundary(_) | Inst::Lookahead(..) => {
+                    before[pc + 1].push(pc)
+                }
+                Inst::Char(_) | Inst::Any | Inst::Class(_) | Inst::Match => {}
+            }
+        }
+
+        let mut lookaheads = vec![None; len];
+        let mut matches = vec![false; input.len() + 1];
+        // whether a thread at each instruction matches, at `pos` and at
+        // the position after it
+        let mut here = vec![false; len];
+        let mut next = vec![false; len];
+        let mut stack = Vec::new();
+        for pos in (0..=input.len()).rev() {
+            here.fill(false);
+            for (pc, inst) in self.insts.iter().enumerate() {
+                let matched = match inst {
+                    Inst::Match => true,
+                    _ => input
+                        .get(pos)
+                        .is_some_and(|&ch| inst.accepts(ch) && next[pc + 1]),
+                };
+                if matched {
+                    here[pc] = true;
+                    stack.push(pc);
+                }
+            }
+            while let Some(pc) = stack.pop() {
+                for &from in &before[pc] {
+                    if !here[from]
+                        && (matches!(self.insts[from], Inst::Jump(_) | Inst::Split(..))
+                            || self.passes(from, input, pos, &mut lookaheads))
+                    {
+                        here[from] = true;
+                        stack.push(from);
+                    }
+                }
+            }
+            matches[pos] = here[0];
+            std::mem::swap(&mut here, &mut next);
+        }
+        matches
+    }
+}
+
+/// The starts at which each lookahead of a program matches, by the pc of
+/// its instruction, worked out on first use for the current input.
+type Lookaheads = Vec<Option<Vec<bool>>>;
+
+impl Inst {
+    /// Whether the instruction consumes `ch`.
+    fn accepts(&self, ch: char) -> bool {
+        match self {
+            Inst::Char(expected) => ch == *expected,
+            Inst::Any => !matches!(ch, '\n' | '\r' | '\u{2028}' | '\u{2029}'),
+            Inst::Class(class) => class.matches(ch),
+            _ => false,
+        }
+    }
+}
+
+/// The threads at one input position, each at most once.
+struct Threads {
+    pcs: Vec<usize>,
+    seen: Vec<bool>,
+}
+
+impl Threads {
+    fn new(len: usize) -> Threads {
+        Threads {
+            pcs: Vec::new(),
+            seen: vec![false; len],
+        }
+    }
+
+    fn visit(&mut self, pc: usize) -> bool {
+        !std::mem::replace(&mut self.seen[pc], true)
+    }
+
+    fn clear(&mut self) {
+        self.pcs.clear();
+        self.seen.fill(false);
+    }
+}
+
+struct RegexParser {
+    chars: Vec<char>,
+    pos: usize,
+    /// How many groups enclose the current position.
+    depth: usize,
+}
+
+impl RegexParser {
+    fn error(&self, message: &'static str) -> RegexError {
+        RegexError {
+            position: self.pos,
+            message,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, ch: char) -> bool {
+        if self.peek() == Some(ch) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn next(&mut self) -> Result<char, RegexError> {
+        let ch = self
+            .peek()
+            .ok_or_else(|| self.error("unexpected end of pattern"))?;
+        self.pos += 1;
+        Ok(ch)
+    }
+
+    // alternation = concat *( "|" concat )
+    fn alternation(&mut self) -> Result<Node, RegexError> {
+        let mut branches = vec![self.concat()?];
+        while self.eat('|') {
+            branches.push(self.concat()?);
+        }
+        Ok(if branches.len() == 1 {
+            branches.pop().unwrap()
+        } else {
+            Node::Alternation(branches)
+        })
+    }
+
+    fn concat(&mut self) -> Result<Node, RegexError> {
+        let mut nodes = Vec::new();
+        while let Some(ch) = self.peek() {
+            if ch == '|' || ch == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap(),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    fn quantified(&mut self, atom: Node) -> Result<Node, RegexError> {
+        let start = self.pos;
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => match self.braces() {
+                Some(bounds) => bounds,
+                None => return Ok(atom),
+            },
+            _ => return Ok(atom),
+        };
+        if self.pos == start {
+            self.pos += 1;
+        }
+        if max.is_some_and(|max| max < min) {
+            return Err(self.error("numbers out of order in `{}` quantifier"));
+        }
+        if matches!(
+            atom,
+            Node::Start | Node::End | Node::WordBoundary(_) | Node::Lookahead(..)
+        ) {
+            return Err(self.error("nothing to repeat"));
+        }
+        // a lazy repeat matches the same texts as a greedy one
+        self.eat('?');
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+        })
+    }
+
+    // `{n}`, `{n,}` or `{n,m}`; anything else leaves `{` as a literal
+    fn braces(&mut self) -> Option<(u32, Option<u32>)> {
+        let start = self.pos;
+        self.pos += 1;
+        let bounds = (|| {
+            let min = self.number()?;
+            if !self.eat(',') {
+                return Some((min, Some(min)));
+            }
+            if self.peek() == Some('}') {
+                return Some((min, None));
+            }
+            Some((min, Some(self.number()?)))
+        })();
+        if bounds.is_some() && self.eat('}') {
+            bounds
+        } else {
+            self.pos = start;
+            None
+        }
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        let start = self.pos;
+        while self.peek().is_some_and(|ch| ch.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        digits.parse().ok()
+    }
+
+    fn atom(&mut self) -> Result<Node, RegexError> {
+        let ch = self.next()?;
+        Ok(match ch {
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '(' => self.group()?,
+            '[' => Node::Class(self.class()?),
+            '\\' => self.escape()?,
+            '*' | '+' | '?' => {
+                self.pos -= 1;
+                return Err(self.error("nothing to repeat"));
+            }
+            '{' if self.peek().is_some_and(|ch| ch.is_ascii_digit()) => {
+                self.pos -= 1;
+                if self.braces().is_some() {
+                    return Err(self.error("nothing to repeat"));
+                }
+                self.pos += 1;
+                Node::Char('{')
+            }
+            ch => Node::Char(ch),
+        })
+    }
+
+    fn group(&mut self) -> Result<Node, RegexError> {
+        let lookahead = if self.eat('?') {
+            match self.next()? {
+                ':' => None,
+                '=' => Some(false),
+                '!' => Some(true),
+                '<' if self.peek().is_some_and(|ch| ch != '=' && ch != '!') => {
+                    // a named group; the name is of no use without captures
+                    while self.next()? != '>' {}
+                    None
+                }
+                _ => return Err(self.error("unsupported group")),
+            }
+        } else {
+            None
+        };
+        if self.depth == DEPTH_LIMIT {
+            return Err(self.error("groups nested too deeply"));
+        }
+        self.depth += 1;
+        let node = self.alternation()?;
+        self.depth -= 1;
+        if !self.eat(')') {
+            return Err(self.error("missing `)`"));
+        }
+        Ok(match lookahead {
+            Some(negated) => Node::Lookahead(Box::new(node), negated),
+            None => node,
+        })
+    }
+
+    fn class(&mut self) -> Result<Class, RegexError> {
+        let mut class = Class {
+            negated: self.eat('^'),
+            ..Class::default()
+        };
+        loop {
+            let lo = match self.next()? {
+                ']' => return Ok(class),
+                '\\' => match self.class_escape()? {
+                    Ok(ch) => ch,
+                    Err(perl) => {
+                        class.perls.push(perl);
+                        continue;
+                    }
+                },
+                ch => ch,
+            };
+            // `a-z`, unless the `-` is last
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1) != Some(&']') {
+                self.pos += 1;
+                let hi = match self.next()? {
+                    '\\' => match self.class_escape()? {
+                        Ok(ch) => ch,
+                        Err(_) => return Err(self.error("class escape in a range")),
+                    },
+                    ch => ch,
+                };
+                if hi < lo {
+                    return Err(self.error("range out of order in character class"));
+                }
+                class.ranges.push((lo, hi));
+            } else {
+                class.ranges.push((lo, lo));
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<Node, RegexError> {
+        match self.peek() {
+            Some('b') => {
+                self.pos += 1;
+                Ok(Node::WordBoundary(true))
+            }
+            Some('B') => {
+                self.pos += 1;
+                Ok(Node::WordBoundary(false))
+            }
+            _ => Ok(match self.class_escape()? {
+                Ok(ch) => Node::Char(ch),
+                Err(perl) => Node::Class(Class {
+                    perls: vec![perl],
+                    ..Class::default()
+                }),
+            }),
+        }
+    }
+
+    // after a `\`: a single character, or a `\d`-style set
+    fn class_escape(&mut self) -> Result<Result<char, (Perl, bool)>, RegexError> {
+        let ch = self.next()?;
+        Ok(Ok(match ch {
+            'd' => return Ok(Err((Perl::Digit, false))),
+            'D' => return Ok(Err((Perl::Digit, true))),
+            'w' => return Ok(Err((Perl::Word, false))),
+            'W' => return Ok(Err((Perl::Word, true))),
+            's' => return Ok(Err((Perl::Space, false))),
+            'S' => return Ok(Err((Perl::Space, true))),
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'f' => '\u{0C}',
+            'v' => '\u{0B}',
+            '0' if !self.peek().is_some_and(|ch| ch.is_ascii_digit()) => '\0',
+            'b' => '\u{08}',
+            'x' => self.hex(2)?,
+            'u' if self.eat('{') => {
+                let start = self.pos;
+                while self.peek().is_some_and(|ch| ch != '}') {
+                    self.pos += 1;
+                }
+                let digits: String = self.chars[start..self.pos].iter().collect();
+                self.next()?;
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| self.error("invalid unicode escape"))?
+            }
+            'u' => self.hex(4)?,
+            // identity escapes, as unicode-mode patterns allow them; any
+            // other escape, such as `\p{L}` or a backreference, would
+            // silently match something else if taken literally
+            '^' | '$' | '\\' | '.' | '*' | '+' | '?' | '(' | ')' | '[' | ']' | '{' | '}' | '|'
+            | '/' | '-' => ch,
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("unsupported escape"));
+            }
+        }))
+    }
+
+    fn hex(&mut self, digits: usize) -> Result<char, RegexError> {
+        let hex: String = self
+            .chars
+            .get(self.pos..self.pos + digits)
+            .ok_or_else(|| self.error("truncated escape"))?
+            .iter()
+            .collect();
+        self.pos += digits;
+        u32::from_str_radix(&hex, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error("invalid hex escape"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_match(pattern: &str, text: &str) -> bool {
+        Regex::new(pattern).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn searches_anywhere_unless_anchored() {
+        assert!(is_match("b", "abc"));
+        assert!(!is_match("^b", "abc"));
+        assert!(is_match("c$", "abc"));
+        assert!(!is_match("^ab$", "abc"));
+        assert!(is_match("", "anything"));
+    }
+
+    #[test]
+    fn classes_and_escapes() {
+        assert!(is_match(r"^[a-c]+$", "abcab"));
+        assert!(!is_match(r"^[^a-c]+$", "xa"));
+        assert!(is_match(r"^\d{3}-\d{4}$", "555-1234"));
+        assert!(!is_match(r"^\d{3}-\d{4}$", "555-123"));
+        assert!(is_match(r"^\w+\s\W$", "ab_1 !"));
+        assert!(is_match(r"^[\d.]+$", "1.5"));
+        assert!(is_match(r"^é\x41$", "éA"));
+        assert!(!is_match("^.$", "\n"));
+    }
+
+    #[test]
+    fn alternation_groups_and_quantifiers() {
+        assert!(is_match("^(cat|dog)s?$", "dogs"));
+        assert!(!is_match("^(cat|dog)s?$", "cow"));
+        assert!(is_match("^(?:ab){2,3}$", "ababab"));
+        assert!(!is_match("^(?:ab){2,3}$", "abababab"));
+        assert!(is_match("^a{2,}$", "aaaa"));
+        assert!(is_match("^a+?b$", "aaab"));
+        assert!(is_match("^(?<year>\\d{4})$", "2024"));
+        assert!(is_match("^(a*)*$", "aaa"));
+    }
+
+    #[test]
+    fn assertions() {
+        assert!(is_match(r"\bcat\b", "a cat sat"));
+        assert!(!is_match(r"\bcat\b", "concatenate"));
+        assert!(is_match(r"\Bcat\B", "concatenate"));
+        assert!(is_match("^(?=.*\\d)(?=.*[a-z]).{6,}$", "abc123"));
+        assert!(!is_match("^(?=.*\\d)(?=.*[a-z]).{6,}$", "abcdef"));
+        assert!(is_match("^(?!admin$).*$", "user"));
+        assert!(!is_match("^(?!admin$).*$", "admin"));
+    }
+
+    #[test]
+    fn long_input_does_not_overflow_the_stack() {
+        let text = "a".repeat(50_000);
+        assert!(is_match("^[a-z]*$", &text));
+        assert!(!is_match("^[a-z]*$", &(text.clone() + "!")));
+        assert!(is_match("^(a|b)+$", &text));
+    }
+
+    #[test]
+    fn nested_quantifiers_match_in_linear_time() {
+        let text = "a".repeat(10_000) + "!";
+        assert!(!is_match("^(a+)+$", &text));
+        assert!(!is_match("^(a|aa)*$", &text));
+        assert!(is_match("^(a+)+!$", &text));
+    }
+
+    #[test]
+    fn unanchored_lookaheads_match_in_linear_time() {
+        let text = "a".repeat(20_000);
+        assert!(!is_match("(?=.*z)", &text));
+        assert!(is_match("(?=.*z)", &(text.clone() + "z")));
+        assert!(is_match("(?!.*z)a$", &text));
+        assert!(is_match("^(?:(?=a(?!b)).)*$", &text));
+        assert!(!is_match("^(?:(?=a(?!b)).)*$", &(text.clone() + "b")));
+        assert!(is_match("a(?=(?:a|b)*b$)", &(text + "b")));
+    }
+
+    #[test]
+    fn invalid_patterns() {
+        let error = |pattern: &str| Regex::new(pattern).unwrap_err().message;
+        assert_eq!(error("(a"), "missing `)`");
+        assert_eq!(error("a)"), "unmatched `)`");
+        assert_eq!(error("*a"), "nothing to repeat");
+        assert_eq!(error("a{3,1}"), "numbers out of order in `{}` quantifier");
+        assert_eq!(error("[z-a]"), "range out of order in character class");
+        assert_eq!(error("(a{1000}){1000}"), "pattern too large");
+        assert_eq!(error(&"(".repeat(1000)), "groups nested too deeply");
+        assert!(is_match("^a{,2}$", "a{,2}"));
+
+        // escapes the engine does not implement, rather than literals
+        for pattern in [
+            r"^\p{L}+$",
+            r"\P{L}",
+            r"^(a)\1$",
+            r"(?<x>a)\k<x>",
+            r"\cJ",
+            r"\a",
+            r"\01",
+            r"[\p{L}]",
+            r"[\1]",
+            r"\é",
+            r"\ ",
+        ] {
+            assert_eq!(error(pattern), "unsupported escape", "{}", pattern);
+        }
+        assert!(is_match(
+            r"^\^\$\\\.\*\+\?\(\)\[\]\{\}\|\/\-$",
+            r"^$\.*+?()[]{}|/-"
+        ));
+        assert!(is_match(r"^[\]\-\\]+$", r"]-\"));
+        assert!(is_match(r"^\0$", "\0"));
+    }
+}
@@ -0,0 +1,746 @@
+//! Serializing with serde, behind the `serde` feature.
+//!
+//! `to_string` writes compact JSON text straight from any `Serialize`
+//! value, with the same escaping as `Json`'s `Display`; a `Serializer` built
+//! with `with_escaping` adds more. Enums are externally tagged: `"Variant"`
+//! or `{"Variant": content}`.
+
+use std::fmt::{self, Write};
+
+use serde::ser::{
+    self, Impossible, Serialize, SerializeMap, SerializeSeq, SerializeStruct,
+    SerializeStructVariant, SerializeTuple, SerializeTupleStruct, SerializeTupleVariant,
+};
+
+use crate::parsen::{Escaping, Json, write_escaped_with};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Error {
+        Error(msg.to_string())
+    }
+}
+
+impl From<fmt::Error> for Error {
+    fn from(_: fmt::Error) -> Error {
+        Error("formatter error".to_string())
+    }
+}
+
+/// Writes `value` as compact JSON text.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String, Error> {
+    let mut serializer = Serializer::new();
+    value.serialize(&mut serializer)?;
+    Ok(serializer.into_inner())
+}
+
+#[derive(Debug, Default)]
+pub struct Serializer {
+    output: String,
+    escaping: Escaping,
+}
+
+impl Serializer {
+    pub fn new() -> Serializer {
+        Serializer::default()
+    }
+
+    /// A serializer applying extra escaping to strings and keys.
+    pub fn with_escaping(escaping: Escaping) -> Serializer {
+        Serializer {
+            output: String::new(),
+            escaping,
+        }
+    }
+
+    /// The text written so far.
+    pub fn into_inner(self) -> String {
+        self.output
+    }
+
+    fn write_number(&mut self, num: f64) -> Result<(), Error> {
+        if num.is_finite() {
+            write!(self.output, "{}", num)?;
+        } else {
+            self.output.push_str("null");
+        }
+        Ok(())
+    }
+
+    /// Opens `{"variant":` around an enum variant's content.
+    fn begin_variant(&mut self, variant: &str) -> Result<(), Error> {
+        self.output.push('{');
+        write_escaped_with(&mut self.output, variant, self.escaping)?;
+        self.output.push(':');
+        Ok(())
+    }
+}
+
+impl<'a> ser::Serializer for &'a mut Serializer {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Compound<'a>;
+    type SerializeTuple = Compound<'a>;
+    type SerializeTupleStruct = Compound<'a>;
+    type SerializeTupleVariant = Compound<'a>;
+    type SerializeMap = Compound<'a>;
+    type SerializeStruct = Compound<'a>;
+    type SerializeStructVariant = Compound<'a>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.output.push_str(if v { "true" } else { "false" });
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        write!(self.output, "{}", v)?;
+        Ok(())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), Error> {
+        write!(self.output, "{}", v)?;
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        write!(self.output, "{}", v)?;
+        Ok(())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), Error> {
+        write!(self.output, "{}", v)?;
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        self.write_number(v.into())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        self.write_number(v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        write_escaped_with(&mut self.output, v, self.escaping)?;
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), Error> {
+        let mut seq = self.serialize_seq(Some(v.len()))?;
+        for byte in v {
+            seq.element(byte)?;
+        }
+        seq.close()
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.output.push_str("null");
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.begin_variant(variant)?;
+        value.serialize(&mut *self)?;
+        self.output.push('}');
+        Ok(())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'a>, Error> {
+        self.output.push('[');
+        Ok(Compound::new(self, "]"))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        self.begin_variant(variant)?;
+        self.output.push('[');
+        Ok(Compound::new(self, "]}"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'a>, Error> {
+        self.output.push('{');
+        Ok(Compound::new(self, "}"))
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Compound<'a>, Error> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Compound<'a>, Error> {
+        self.begin_variant(variant)?;
+        self.output.push('{');
+        Ok(Compound::new(self, "}}"))
+    }
+}
+
+/// An array or object being written; `close` is written at the end.
+pub struct Compound<'a> {
+    ser: &'a mut Serializer,
+    first: bool,
+    close: &'static str,
+}
+
+impl<'a> Compound<'a> {
+    fn new(ser: &'a mut Serializer, close: &'static str) -> Compound<'a> {
+        Compound {
+            ser,
+            first: true,
+            close,
+        }
+    }
+
+    fn separate(&mut self) {
+        if !std::mem::replace(&mut self.first, false) {
+            self.ser.output.push(',');
+        }
+    }
+
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.separate();
+        value.serialize(&mut *self.ser)
+    }
+
+    fn member<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<(), Error> {
+        self.separate();
+        write_escaped_with(&mut self.ser.output, key, self.ser.escaping)?;
+        self.ser.output.push(':');
+        value.serialize(&mut *self.ser)
+    }
+
+    fn close(self) -> Result<(), Error> {
+        self.ser.output.push_str(self.close);
+        Ok(())
+    }
+}
+
+impl SerializeSeq for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.close()
+    }
+}
+
+impl SerializeTuple for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.close()
+    }
+}
+
+impl SerializeTupleStruct for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.close()
+    }
+}
+
+impl SerializeTupleVariant for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.close()
+    }
+}
+
+impl SerializeMap for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        self.separate();
+        key.serialize(KeySerializer {
+            output: &mut self.ser.output,
+            escaping: self.ser.escaping,
+        })?;
+        self.ser.output.push(':');
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.close()
+    }
+}
+
+impl SerializeStruct for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.member(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.close()
+    }
+}
+
+impl SerializeStructVariant for Compound<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.member(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.close()
+    }
+}
+
+/// Object keys must be strings; integers, booleans and chars are quoted,
+/// anything else is an error.
+struct KeySerializer<'a> {
+    output: &'a mut String,
+    escaping: Escaping,
+}
+
+impl KeySerializer<'_> {
+    fn quoted(self, key: impl fmt::Display) -> Result<(), Error> {
+        write!(self.output, "\"{}\"", key)?;
+        Ok(())
+    }
+}
+
+fn key_must_be_a_string() -> Error {
+    Error("object key must be a string".to_string())
+}
+
+impl ser::Serializer for KeySerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Impossible<(), Error>;
+    type SerializeTuple = Impossible<(), Error>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Impossible<(), Error>;
+    type SerializeStruct = Impossible<(), Error>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.quoted(v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.quoted(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.quoted(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.quoted(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.quoted(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), Error> {
+        self.quoted(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.quoted(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.quoted(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.quoted(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        self.quoted(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), Error> {
+        self.quoted(v)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<(), Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<(), Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.serialize_str(v.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        write_escaped_with(self.output, v, self.escaping)?;
+        Ok(())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<(), Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<(), Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Impossible<(), Error>, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Impossible<(), Error>, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Impossible<(), Error>, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Impossible<(), Error>, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Impossible<(), Error>, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Impossible<(), Error>, Error> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Impossible<(), Error>, Error> {
+        Err(key_must_be_a_string())
+    }
+}
+
+/// Integral numbers within `i64`/`u64` are written as integers so that
+/// formats with an integer type keep them as such.
+impl Serialize for Json {
+    fn serialize<S: ser::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Json::Null => serializer.serialize_unit(),
+            Json::Boolean(b) => serializer.serialize_bool(*b),
+            Json::Number(num)
+                if num.fract() == 0.0 && num.is_sign_positive() && *num < u64::MAX as f64 =>
+            {
+                serializer.serialize_u64(*num as u64)
+            }
+            Json::Number(num) if num.fract() == 0.0 && *num >= i64::MIN as f64 && *num < 0.0 => {
+                serializer.serialize_i64(*num as i64)
+            }
+            Json::Number(num) => serializer.serialize_f64(*num),
+            Json::Strings(st) => serializer.serialize_str(st),
+            Json::Array(vec) => {
+                let mut seq = serializer.serialize_seq(Some(vec.len()))?;
+                for json in vec {
+                    seq.serialize_element(json)?;
+                }
+                seq.end()
+            }
+            Json::Object(map) => {
+                let mut members = serializer.serialize_map(Some(map.len()))?;
+                for (key, json) in map.iter() {
+                    members.serialize_entry(key, json)?;
+                }
+                members.end()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Serialize;
+
+    use super::*;
+    use crate::json;
+
+    #[derive(Serialize)]
+    struct Unit;
+
+    #[derive(Serialize)]
+    struct Meters(f64);
+
+    #[derive(Serialize)]
+    enum Event {
+        Started,
+        Moved(i32, i32),
+        Named { name: &'static str },
+    }
+
+    #[test]
+    fn scalars() {
+        assert_eq!(to_string(&true).unwrap(), "true");
+        assert_eq!(to_string(&-3i8).unwrap(), "-3");
+        assert_eq!(to_string(&u128::MAX).unwrap(), u128::MAX.to_string());
+        assert_eq!(to_string(&0.25f32).unwrap(), "0.25");
+        assert_eq!(to_string(&'x').unwrap(), r#""x""#);
+        assert_eq!(to_string(&()).unwrap(), "null");
+        assert_eq!(to_string(&Unit).unwrap(), "null");
+        assert_eq!(to_string(&Meters(2.0)).unwrap(), "2");
+    }
+
+    #[test]
+    fn non_finite_numbers_are_written_as_null() {
+        assert_eq!(to_string(&f64::NAN).unwrap(), "null");
+        assert_eq!(to_string(&[f64::INFINITY]).unwrap(), "[null]");
+    }
+
+    #[test]
+    fn sequences_and_tuples_are_arrays() {
+        assert_eq!(to_string(&Vec::<u8>::new()).unwrap(), "[]");
+        assert_eq!(to_string(&(1, "a", None::<u8>)).unwrap(), r#"[1,"a",null]"#);
+        assert_eq!(to_string(&[[1], [2]]).unwrap(), "[[1],[2]]");
+    }
+
+    #[test]
+    fn variants_are_externally_tagged() {
+        assert_eq!(to_string(&Event::Started).unwrap(), r#""Started""#);
+        assert_eq!(
+            to_string(&Event::Moved(1, 2)).unwrap(),
+            r#"{"Moved":[1,2]}"#
+        );
+        assert_eq!(
+            to_string(&Event::Named { name: "n" }).unwrap(),
+            r#"{"Named":{"name":"n"}}"#
+        );
+    }
+
+    #[test]
+    fn map_keys_are_quoted_or_rejected() {
+        let map = BTreeMap::from([(1u8, "a"), (2, "b")]);
+        assert_eq!(to_string(&map).unwrap(), r#"{"1":"a","2":"b"}"#);
+        let map = BTreeMap::from([((1, 2), "a")]);
+        assert_eq!(to_string(&map).unwrap_err(), key_must_be_a_string());
+    }
+
+    #[test]
+    fn with_escaping_applies_to_strings_keys_and_variants() {
+        #[derive(Serialize)]
+        enum Tag {
+            #[serde(rename = "<é>")]
+            Markup(&'static str),
+        }
+
+        let mut serializer = Serializer::with_escaping(Escaping::ascii_only());
+        BTreeMap::from([("😀", "\u{2028}")])
+            .serialize(&mut serializer)
+            .unwrap();
+        assert_eq!(serializer.into_inner(), r#"{"\ud83d\ude00":"\u2028"}"#);
+
+        let mut serializer = Serializer::with_escaping(Escaping::html());
+        Tag::Markup("'&'").serialize(&mut serializer).unwrap();
+        assert_eq!(
+            serializer.into_inner(),
+            r#"{"\u003cé\u003e":"\u0027\u0026\u0027"}"#
+        );
+
+        let mut serializer = Serializer::with_escaping(Escaping::javascript());
+        "a\u{2029}".serialize(&mut serializer).unwrap();
+        assert_eq!(serializer.into_inner(), r#""a\u2029""#);
+    }
+
+    #[test]
+    fn to_string_only_escapes_what_json_requires() {
+        assert_eq!(to_string("<😀\u{2028}>").unwrap(), "\"<😀\u{2028}>\"");
+    }
+
+    #[test]
+    fn json_matches_display() {
+        let json = json!({"a": [1, -1, 1.5, "\n"], "b": null});
+        assert_eq!(to_string(&json).unwrap(), json.to_string());
+    }
+}
@@ -0,0 +1,242 @@
+//! Where each value of a parsed document came from, so that a check run on
+//! the `Json` tree can point back into the text.
+//!
+//! ```
+//! use json_parser::parsen::{Parser, ParserOptions};
+//!
+//! let text = "{\n  \"server\": {\n    \"port\": \"80\"\n  }\n}";
+//! let options = ParserOptions {
+//!     source_map: true,
+//!     ..ParserOptions::default()
+//! };
+//! let mut parser = Parser::from_string_with(text.to_string(), options);
+//! parser.parse();
+//! let map = parser.source_map().unwrap();
+//!
+//! let span = map.value_span("/server/port").unwrap();
+//! assert_eq!(&text[span.clone()], "\"80\"");
+//! assert_eq!(map.position(span.start), (3, 13));
+//! assert_eq!(map.pointer_at(span.start + 1), Some("/server/port"));
+//! ```
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// The spans of one value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spans {
+    pub pointer: String,
+    /// The quoted key, for object members.
+    pub key: Option<Range<usize>>,
+    /// The whole value, brackets included.
+    pub value: Range<usize>,
+}
+
+impl Spans {
+    /// From the start of the key, if any, to the end of the value.
+    pub fn member(&self) -> Range<usize> {
+        let start = self.key.as_ref().map_or(self.value.start, |key| key.start);
+        start..self.value.end
+    }
+}
+
+/// A side table from JSON Pointer to source spans, built by `Parser::parse`
+/// or `Parser::parse_recovering` when `ParserOptions::source_map` is set.
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    source: String,
+    /// In document order, each value before the values inside it.
+    spans: Vec<Spans>,
+    index: HashMap<String, usize>,
+}
+
+impl SourceMap {
+    pub(crate) fn new(source: String) -> SourceMap {
+        SourceMap {
+            source,
+            ..SourceMap::default()
+        }
+    }
+
+    /// Starts a value at `start`; the end is filled in by `close`.
+    pub(crate) fn open(&mut self, pointer: &str, key: Option<Range<usize>>, start: usize) -> usize {
+        let idx = self.spans.len();
+        self.spans.push(Spans {
+            pointer: pointer.to_string(),
+            key,
+            value: start..start,
+        });
+        // a repeated key keeps the last value, as the tree does
+        self.index.insert(pointer.to_string(), idx);
+        idx
+    }
+
+    pub(crate) fn close(&mut self, idx: usize, end: usize) {
+        self.spans[idx].value.end = end;
+    }
+
+    pub fn get(&self, pointer: &str) -> Option<&Spans> {
+        self.index.get(pointer).map(|&idx| &self.spans[idx])
+    }
+
+    pub fn key_span(&self, pointer: &str) -> Option<Range<usize>> {
+        self.get(pointer)?.key.clone()
+    }
+
+    pub fn value_span(&self, pointer: &str) -> Option<Range<usize>> {
+        self.get(pointer).map(|spans| spans.value.clone())
+    }
+
+    /// The innermost value whose member (key, colon and value) covers
+    /// `offset`.
+    pub fn pointer_at(&self, offset: usize) -> Option<&str> {
+        // later entries covering the offset can only be nested deeper
+        self.spans
+            .iter()
+            .rev()
+            .find(|spans| spans.member().contains(&offset))
+            .map(|spans| spans.pointer.as_str())
+    }
+
+    /// Every value, in document order.
+    pub fn iter(&self) -> impl Iterator<Item = &Spans> {
+        self.spans.iter()
+    }
+
+    /// One-based line and column of a byte offset, counting columns in
+    /// characters.
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let before = &self.source[..offset.min(self.source.len())];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        (line, before[line_start..].chars().count() + 1)
+    }
+
+    /// `"/server/port at line 3, column 5"`, from the start of the
+    /// member.
+    pub fn describe(&self, pointer: &str) -> Option<String> {
+        let (line, column) = self.position(self.get(pointer)?.member().start);
+        let name = if pointer.is_empty() {
+            "the document"
+        } else {
+            pointer
+        };
+        Some(format!("{} at line {}, column {}", name, line, column))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsen::{Parser, ParserOptions};
+
+    use super::*;
+
+    fn options() -> ParserOptions {
+        ParserOptions {
+            source_map: true,
+            ..ParserOptions::default()
+        }
+    }
+
+    fn map_of(text: &str) -> SourceMap {
+        let mut parser = Parser::from_string_with(text.to_string(), options());
+        parser.parse();
+        parser.source_map().unwrap().clone()
+    }
+
+    fn text_at(text: &str, span: Option<Range<usize>>) -> &str {
+        &text[span.unwrap()]
+    }
+
+    #[test]
+    fn nested_values_have_their_own_spans() {
+        let text = r#"{"a": {"b": [1, {"c": null}]}, "d": true}"#;
+        let map = map_of(text);
+        assert_eq!(text_at(text, map.value_span("")), text);
+        assert_eq!(
+            text_at(text, map.value_span("/a")),
+            r#"{"b": [1, {"c": null}]}"#
+        );
+        assert_eq!(text_at(text, map.value_span("/a/b")), r#"[1, {"c": null}]"#);
+        assert_eq!(text_at(text, map.value_span("/a/b/0")), "1");
+        assert_eq!(text_at(text, map.value_span("/a/b/1/c")), "null");
+        assert_eq!(text_at(text, map.key_span("/a/b/1/c")), r#""c""#);
+        assert_eq!(map.key_span("/a/b/0"), None);
+        assert_eq!(map.key_span(""), None);
+        assert_eq!(map.get("/a/b/2"), None);
+
+        let pointers: Vec<&str> = map.iter().map(|spans| spans.pointer.as_str()).collect();
+        assert_eq!(
+            pointers,
+            ["", "/a", "/a/b", "/a/b/0", "/a/b/1", "/a/b/1/c", "/d"]
+        );
+    }
+
+    #[test]
+    fn offsets_map_to_the_innermost_member() {
+        let text = r#"{"a": {"b": [1, {"c": null}]}, "d": true}"#;
+        let map = map_of(text);
+        let at = |needle: &str| text.find(needle).unwrap();
+        assert_eq!(map.pointer_at(at("null")), Some("/a/b/1/c"));
+        assert_eq!(map.pointer_at(at(r#""c""#)), Some("/a/b/1/c"));
+        assert_eq!(map.pointer_at(at("1,")), Some("/a/b/0"));
+        // the space between elements belongs to the array
+        assert_eq!(map.pointer_at(at(", {") + 1), Some("/a/b"));
+        assert_eq!(map.pointer_at(at(r#""d""#)), Some("/d"));
+        assert_eq!(map.pointer_at(0), Some(""));
+        assert_eq!(map.pointer_at(text.len()), None);
+    }
+
+    #[test]
+    fn spans_are_in_bytes_and_columns_in_characters() {
+        let text = "{\"é\": \"日本\",\n \"ß\": [\"😀\", 2]}";
+        let map = map_of(text);
+        assert_eq!(text_at(text, map.key_span("/é")), "\"é\"");
+        assert_eq!(text_at(text, map.value_span("/é")), "\"日本\"");
+        assert_eq!(text_at(text, map.value_span("/ß/0")), "\"😀\"");
+        let two = map.value_span("/ß/1").unwrap();
+        assert_eq!(&text[two.clone()], "2");
+        assert_eq!(map.position(two.start), (2, 13));
+        assert_eq!(
+            map.describe("/ß"),
+            Some("/ß at line 2, column 2".to_string())
+        );
+        assert_eq!(
+            map.describe(""),
+            Some("the document at line 1, column 1".to_string())
+        );
+    }
+
+    #[test]
+    fn a_repeated_key_maps_to_the_last_value() {
+        let text = r#"{"a": 1, "a": [2]}"#;
+        let map = map_of(text);
+        assert_eq!(text_at(text, map.value_span("/a")), "[2]");
+        assert_eq!(map.iter().filter(|spans| spans.pointer == "/a").count(), 2);
+    }
+
+    #[test]
+    fn recovering_parses_build_a_source_map_too() {
+        let text = r#"{"a": @, "b": [1 2], "c": "ok"}"#;
+        let mut parser = Parser::from_string_recovering(text.to_string(), options());
+        let recovered = parser.parse_recovering();
+        assert_eq!(recovered.errors.len(), 2);
+        let map = parser.source_map().unwrap();
+        assert_eq!(text_at(text, map.value_span("/a")), "@");
+        assert_eq!(text_at(text, map.value_span("/b/0")), "1");
+        assert_eq!(text_at(text, map.value_span("/c")), r#""ok""#);
+        assert_eq!(text_at(text, map.key_span("/c")), r#""c""#);
+        assert_eq!(map.value_span("").unwrap(), 0..text.len());
+    }
+
+    #[test]
+    fn no_source_map_unless_asked_for() {
+        let mut parser = Parser::from_string_with("[1]".to_string(), ParserOptions::default());
+        parser.parse();
+        assert!(parser.source_map().is_none());
+        let text = "[1]".to_string();
+        let mut parser = Parser::from_string_recovering(text, ParserOptions::default());
+        parser.parse_recovering();
+        assert!(parser.source_map().is_none());
+    }
+}
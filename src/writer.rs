@@ -0,0 +1,448 @@
+//! Streaming output: `JsonWriter` writes a document piece by piece, so an
+//! export never has to exist as a `Json` tree.
+//!
+//! ```
+//! use json_parser::json;
+//! use json_parser::writer::JsonWriter;
+//!
+//! let mut writer = JsonWriter::new(Vec::new());
+//! writer.begin_object()?;
+//! writer.key("rows")?;
+//! writer.begin_array()?;
+//! for i in 0..3 {
+//!     writer.value(&json!({"id": i}))?;
+//! }
+//! writer.end_array()?;
+//! writer.end_object()?;
+//! let bytes = writer.finish()?;
+//! assert_eq!(bytes, br#"{"rows":[{"id":0},{"id":1},{"id":2}]}"#);
+//! # Ok::<(), json_parser::writer::WriterError>(())
+//! ```
+
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::parsen::{Escaping, Json, write_escaped_with};
+
+/// A call that does not fit where the writer is in the document, or an
+/// I/O failure from the underlying writer.
+#[derive(Debug)]
+pub enum WriterError {
+    Io(io::Error),
+    /// A value inside an object without a `key` first.
+    ExpectedKey,
+    /// A `key` outside an object, or two keys in a row.
+    UnexpectedKey,
+    /// `end_object` or `end_array` that does not match the open container.
+    Mismatched {
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// A second value at the top level, or a close once the top-level value
+    /// is complete.
+    AlreadyComplete,
+    /// `finish` before the document was complete.
+    Incomplete,
+}
+
+impl fmt::Display for WriterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WriterError::Io(err) => write!(f, "{}", err),
+            WriterError::ExpectedKey => f.write_str("expected a key before the value"),
+            WriterError::UnexpectedKey => {
+                f.write_str("a key is only allowed before an object member")
+            }
+            WriterError::Mismatched { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+            WriterError::AlreadyComplete => f.write_str("the document is already complete"),
+            WriterError::Incomplete => f.write_str("the document is incomplete"),
+        }
+    }
+}
+
+impl std::error::Error for WriterError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WriterError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WriterError {
+    fn from(err: io::Error) -> WriterError {
+        WriterError::Io(err)
+    }
+}
+
+/// An open array or object.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Frame {
+    Array { empty: bool },
+    Object { empty: bool, has_key: bool },
+}
+
+pub struct JsonWriter<W: Write> {
+    out: W,
+    /// `None` for compact output.
+    indent: Option<String>,
+    stack: Vec<Frame>,
+    /// Whether the top-level value has been started.
+    started: bool,
+    escaping: Escaping,
+}
+
+impl<W: Write> JsonWriter<W> {
+    /// A writer producing compact output.
+    pub fn new(out: W) -> JsonWriter<W> {
+        JsonWriter {
+            out,
+            indent: None,
+            stack: Vec::new(),
+            started: false,
+            escaping: Escaping::default(),
+        }
+    }
+
+    /// A writer producing one member or element per line, indented by two
+    /// spaces per level.
+    pub fn pretty(out: W) -> JsonWriter<W> {
+        JsonWriter::with_indent(out, "  ")
+    }
+
+    /// A pretty writer indenting by `indent` per level.
+    pub fn with_indent(out: W, indent: &str) -> JsonWriter<W> {
+        JsonWriter {
+            indent: Some(indent.to_string()),
+            ..JsonWriter::new(out)
+        }
+    }
+
+    /// Escapes strings and keys for embedding, e.g.
+    /// `JsonWriter::new(out).escaping(Escaping::html())`.
+    pub fn escaping(mut self, escaping: Escaping) -> JsonWriter<W> {
+        self.escaping = escaping;
+        self
+    }
+
+    pub fn begin_object(&mut self) -> Result<(), WriterError> {
+        self.before_value()?;
+        self.out.write_all(b"{")?;
+        self.stack.push(Frame::Object {
+            empty: true,
+            has_key: false,
+        });
+        Ok(())
+    }
+
+    pub fn end_object(&mut self) -> Result<(), WriterError> {
+        match self.stack.last() {
+            Some(Frame::Object {
+                has_key: false,
+                empty,
+            }) => {
+                let empty = *empty;
+                self.stack.pop();
+                self.close(b"}", empty)
+            }
+            Some(Frame::Object { has_key: true, .. }) => Err(WriterError::Mismatched {
+                expected: "a value",
+                found: "end of object",
+            }),
+            Some(Frame::Array { .. }) => Err(WriterError::Mismatched {
+                expected: "end of array",
+                found: "end of object",
+            }),
+            None if self.started => Err(WriterError::AlreadyComplete),
+            None => Err(WriterError::Mismatched {
+                expected: "an open object",
+                found: "end of object",
+            }),
+        }
+    }
+
+    pub fn begin_array(&mut self) -> Result<(), WriterError> {
+        self.before_value()?;
+        self.out.write_all(b"[")?;
+        self.stack.push(Frame::Array { empty: true });
+        Ok(())
+    }
+
+    pub fn end_array(&mut self) -> Result<(), WriterError> {
+        match self.stack.last() {
+            Some(Frame::Array { empty }) => {
+                let empty = *empty;
+                self.stack.pop();
+                self.close(b"]", empty)
+            }
+            Some(Frame::Object { .. }) => Err(WriterError::Mismatched {
+                expected: "end of object",
+                found: "end of array",
+            }),
+            None if self.started => Err(WriterError::AlreadyComplete),
+            None => Err(WriterError::Mismatched {
+                expected: "an open array",
+                found: "end of array",
+            }),
+        }
+    }
+
+    /// Starts an object member; the next call writes its value.
+    pub fn key(&mut self, key: &str) -> Result<(), WriterError> {
+        let empty = match self.stack.last_mut() {
+            Some(Frame::Object { empty, has_key }) if !*has_key => {
+                *has_key = true;
+                std::mem::replace(empty, false)
+            }
+            _ => return Err(WriterError::UnexpectedKey),
+        };
+        if !empty {
+            self.out.write_all(b",")?;
+        }
+        self.newline()?;
+        self.write_string(key)?;
+        let separator: &[u8] = if self.indent.is_some() { b": " } else { b":" };
+        self.out.write_all(separator)?;
+        Ok(())
+    }
+
+    /// Writes a whole value: a scalar, or a tree written member by member.
+    pub fn value(&mut self, json: &Json) -> Result<(), WriterError> {
+        match json {
+            Json::Array(vec) => {
+                self.begin_array()?;
+                for json in vec {
+                    self.value(json)?;
+                }
+                self.end_array()
+            }
+            Json::Object(map) => {
+                self.begin_object()?;
+                for (key, json) in map.iter() {
+                    self.key(key)?;
+                    self.value(json)?;
+                }
+                self.end_object()
+            }
+            scalar => {
+                self.before_value()?;
+                match scalar {
+                    Json::Strings(st) => self.write_string(st)?,
+                    // other scalars come out exactly as the tree serializer
+                    // writes them
+                    other => write!(self.out, "{}", other)?,
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks the document is complete and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W, WriterError> {
+        if !self.started || !self.stack.is_empty() {
+            return Err(WriterError::Incomplete);
+        }
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    /// Validates that a value may go here, writing any separator first.
+    fn before_value(&mut self) -> Result<(), WriterError> {
+        match self.stack.last_mut() {
+            None if self.started => Err(WriterError::AlreadyComplete),
+            None => {
+                self.started = true;
+                Ok(())
+            }
+            Some(Frame::Object { has_key, .. }) => {
+                if !std::mem::replace(has_key, false) {
+                    return Err(WriterError::ExpectedKey);
+                }
+                Ok(())
+            }
+            Some(Frame::Array { empty }) => {
+                if !std::mem::replace(empty, false) {
+                    self.out.write_all(b",")?;
+                }
+                self.newline()
+            }
+        }
+    }
+
+    fn close(&mut self, bracket: &[u8], empty: bool) -> Result<(), WriterError> {
+        if !empty {
+            self.newline()?;
+        }
+        self.out.write_all(bracket)?;
+        Ok(())
+    }
+
+    /// Starts a new line at the current depth, in pretty mode.
+    fn newline(&mut self) -> Result<(), WriterError> {
+        if let Some(indent) = &self.indent {
+            self.out.write_all(b"\n")?;
+            for _ in 0..self.stack.len() {
+                self.out.write_all(indent.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_string(&mut self, st: &str) -> Result<(), WriterError> {
+        let mut escaped = String::with_capacity(st.len() + 2);
+        write_escaped_with(&mut escaped, st, self.escaping).expect("writing to a String");
+        self.out.write_all(escaped.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    fn text(writer: JsonWriter<Vec<u8>>) -> String {
+        String::from_utf8(writer.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn compact_output_matches_display() {
+        let json = json!({"a": [1, {"b": null}, []], "c": {}, "d": "e"});
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.value(&json).unwrap();
+        assert_eq!(text(writer), json.to_string());
+    }
+
+    #[test]
+    fn pretty_output_puts_each_member_on_its_own_line() {
+        let mut writer = JsonWriter::pretty(Vec::new());
+        writer
+            .value(&json!({"a": [1, 2], "b": {}, "c": [], "d": {"e": true}}))
+            .unwrap();
+        assert_eq!(
+            text(writer),
+            "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": {},\n  \"c\": [],\n  \
+             \"d\": {\n    \"e\": true\n  }\n}"
+        );
+    }
+
+    #[test]
+    fn custom_indent() {
+        let mut writer = JsonWriter::with_indent(Vec::new(), "\t");
+        writer.value(&json!([[1]])).unwrap();
+        assert_eq!(text(writer), "[\n\t[\n\t\t1\n\t]\n]");
+    }
+
+    #[test]
+    fn strings_and_keys_use_the_escaping() {
+        let mut writer = JsonWriter::new(Vec::new()).escaping(Escaping::html());
+        writer.value(&json!({"<k>": "a&b"})).unwrap();
+        assert_eq!(text(writer), r#"{"\u003ck\u003e":"a\u0026b"}"#);
+    }
+
+    #[test]
+    fn a_value_in_an_object_needs_a_key() {
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.begin_object().unwrap();
+        assert!(matches!(
+            writer.value(&json!(1)),
+            Err(WriterError::ExpectedKey)
+        ));
+        assert!(matches!(
+            writer.begin_array(),
+            Err(WriterError::ExpectedKey)
+        ));
+    }
+
+    #[test]
+    fn keys_belong_only_before_object_members() {
+        let mut writer = JsonWriter::new(Vec::new());
+        assert!(matches!(writer.key("a"), Err(WriterError::UnexpectedKey)));
+        writer.begin_array().unwrap();
+        assert!(matches!(writer.key("a"), Err(WriterError::UnexpectedKey)));
+        writer.end_array().unwrap();
+
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.begin_object().unwrap();
+        writer.key("a").unwrap();
+        assert!(matches!(writer.key("b"), Err(WriterError::UnexpectedKey)));
+    }
+
+    #[test]
+    fn closing_the_wrong_container_is_mismatched() {
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.begin_array().unwrap();
+        let err = writer.end_object().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected end of array, found end of object"
+        );
+
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.begin_object().unwrap();
+        let err = writer.end_array().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expected end of object, found end of array"
+        );
+        writer.key("a").unwrap();
+        let err = writer.end_object().unwrap_err();
+        assert_eq!(err.to_string(), "expected a value, found end of object");
+    }
+
+    #[test]
+    fn closing_before_anything_was_opened_is_mismatched() {
+        let mut writer = JsonWriter::new(Vec::new());
+        assert!(matches!(
+            writer.end_object(),
+            Err(WriterError::Mismatched {
+                expected: "an open object",
+                found: "end of object",
+            })
+        ));
+        assert!(matches!(
+            writer.end_array(),
+            Err(WriterError::Mismatched {
+                expected: "an open array",
+                found: "end of array",
+            })
+        ));
+    }
+
+    #[test]
+    fn anything_after_the_top_level_value_is_already_complete() {
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.value(&json!([])).unwrap();
+        assert!(matches!(
+            writer.value(&json!(1)),
+            Err(WriterError::AlreadyComplete)
+        ));
+        assert!(matches!(
+            writer.begin_object(),
+            Err(WriterError::AlreadyComplete)
+        ));
+        assert!(matches!(
+            writer.end_object(),
+            Err(WriterError::AlreadyComplete)
+        ));
+        assert!(matches!(
+            writer.end_array(),
+            Err(WriterError::AlreadyComplete)
+        ));
+        assert_eq!(text(writer), "[]");
+    }
+
+    #[test]
+    fn finishing_early_is_incomplete() {
+        let writer = JsonWriter::new(Vec::new());
+        assert!(matches!(writer.finish(), Err(WriterError::Incomplete)));
+
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.begin_object().unwrap();
+        writer.key("a").unwrap();
+        writer.begin_array().unwrap();
+        assert!(matches!(writer.finish(), Err(WriterError::Incomplete)));
+    }
+}
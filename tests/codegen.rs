@@ -0,0 +1,65 @@
+use std::fs;
+use std::process::Command;
+
+use json_parser::codegen::{CodegenOptions, generate};
+use json_parser::parsen::Parser;
+
+/// The code generated for the sample in `file` with the given root name.
+fn generated(file: &str, root: &str) -> String {
+    let text = fs::read_to_string(file).unwrap();
+    let sample = Parser::from_string(text).parse();
+    let options = CodegenOptions {
+        root: root.to_string(),
+        ..CodegenOptions::default()
+    };
+    generate([&sample], &options)
+}
+
+#[test]
+fn checked_in_code_is_what_codegen_generates() {
+    // the code the `pass` tests compile, kept in step with the generator
+    assert_eq!(
+        generated("json_obj.txt", "Root"),
+        fs::read_to_string("tests/ui/codegen/generated/image.rs").unwrap()
+    );
+    assert_eq!(
+        generated("array.txt", "Location"),
+        fs::read_to_string("tests/ui/codegen/generated/locations.rs").unwrap()
+    );
+}
+
+#[test]
+fn generated_code_compiles_and_round_trips_its_sample() {
+    trybuild::TestCases::new().pass("tests/ui/codegen/*.rs");
+}
+
+#[test]
+fn codegen_subcommand_prints_the_generated_code() {
+    let output = Command::new(env!("CARGO_BIN_EXE_json_parser"))
+        .args(["codegen", "--root", "Location", "array.txt"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        generated("array.txt", "Location")
+    );
+}
+
+#[test]
+fn codegen_subcommand_reports_bad_arguments() {
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_json_parser"))
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        assert!(output.stdout.is_empty());
+        String::from_utf8(output.stderr).unwrap()
+    };
+    let usage = "usage: json_parser [codegen [--root NAME] FILE...]\n";
+    assert_eq!(run(&["codegen"]), usage);
+    assert_eq!(run(&["codegen", "--root"]), usage);
+    assert_eq!(run(&["generate"]), usage);
+    assert!(run(&["codegen", "missing.json"]).starts_with("file read failure, "));
+}
@@ -0,0 +1,325 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use json_parser::convert::{ErrorKind, FromJson, ToJson};
+use json_parser::json;
+use json_parser::parsen::Json;
+
+/// Writes `value`, checks the exact text, and reads it back.
+fn round_trip<T: ToJson + FromJson + PartialEq + Debug>(value: T, expected: &str) {
+    let json = value.to_json();
+    assert_eq!(json.to_string(), expected);
+    assert_eq!(T::from_json(&json), Ok(value));
+}
+
+#[derive(ToJson, FromJson, Debug, PartialEq)]
+struct Location {
+    #[json(rename = "Latitude")]
+    latitude: f64,
+    #[json(rename = "Longitude")]
+    longitude: f64,
+    label: Option<String>,
+}
+
+#[test]
+fn structs_are_objects_in_field_order() {
+    round_trip(
+        Location {
+            latitude: 37.7,
+            longitude: -122.4,
+            label: Some("SF".to_string()),
+        },
+        r#"{"Latitude":37.7,"Longitude":-122.4,"label":"SF"}"#,
+    );
+}
+
+#[test]
+fn a_missing_option_reads_as_none() {
+    let json = json!({"Latitude": 1, "Longitude": 2});
+    assert_eq!(
+        Location::from_json(&json),
+        Ok(Location {
+            latitude: 1.0,
+            longitude: 2.0,
+            label: None,
+        })
+    );
+    let err = Location::from_json(&json!({"Latitude": 1})).unwrap_err();
+    assert_eq!(
+        err.kind(),
+        &ErrorKind::MissingField("Longitude".to_string())
+    );
+    let err = Location::from_json(&json!({"Latitude": "1", "Longitude": 2})).unwrap_err();
+    assert_eq!(err.to_string(), ".Latitude: expected number, found string");
+}
+
+#[derive(ToJson, FromJson, Debug, PartialEq)]
+struct Meters(f64);
+
+#[derive(ToJson, FromJson, Debug, PartialEq)]
+struct Pair(u8, String);
+
+#[derive(ToJson, FromJson, Debug, PartialEq)]
+struct Marker;
+
+#[test]
+fn newtype_tuple_and_unit_structs() {
+    round_trip(Meters(1.5), "1.5");
+    round_trip(Pair(1, "a".to_string()), r#"[1,"a"]"#);
+    round_trip(Marker, "null");
+}
+
+#[derive(ToJson, FromJson, Debug, PartialEq)]
+#[json(rename_all = "camelCase")]
+struct Request {
+    request_id: u32,
+    #[json(rename = "X-Trace")]
+    trace_id: String,
+    r#type: String,
+}
+
+#[derive(ToJson, FromJson, Debug, PartialEq)]
+#[json(rename_all = "SCREAMING-KEBAB-CASE")]
+struct Header {
+    content_type: String,
+}
+
+#[test]
+fn rename_all_applies_to_every_field_without_a_rename() {
+    round_trip(
+        Request {
+            request_id: 7,
+            trace_id: "t".to_string(),
+            r#type: "get".to_string(),
+        },
+        r#"{"requestId":7,"X-Trace":"t","type":"get"}"#,
+    );
+    round_trip(
+        Header {
+            content_type: "text/plain".to_string(),
+        },
+        r#"{"CONTENT-TYPE":"text/plain"}"#,
+    );
+}
+
+#[derive(ToJson, FromJson, Debug, PartialEq)]
+#[json(default)]
+struct Settings {
+    retries: u8,
+    verbose: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            retries: 3,
+            verbose: true,
+        }
+    }
+}
+
+fn default_port() -> u16 {
+    8080
+}
+
+#[derive(ToJson, FromJson, Debug, PartialEq)]
+struct Server {
+    host: String,
+    #[json(default = "default_port")]
+    port: u16,
+    #[json(default)]
+    tags: Vec<String>,
+    #[json(skip)]
+    connections: u32,
+}
+
+#[test]
+fn container_default_fills_missing_fields_from_default() {
+    assert_eq!(
+        Settings::from_json(&json!({"verbose": false})),
+        Ok(Settings {
+            retries: 3,
+            verbose: false,
+        })
+    );
+    round_trip(
+        Settings {
+            retries: 0,
+            verbose: false,
+        },
+        r#"{"retries":0,"verbose":false}"#,
+    );
+}
+
+#[test]
+fn field_defaults_and_skip() {
+    let server = Server::from_json(&json!({"host": "localhost", "connections": 9})).unwrap();
+    assert_eq!(
+        server,
+        Server {
+            host: "localhost".to_string(),
+            port: 8080,
+            tags: Vec::new(),
+            connections: 0,
+        }
+    );
+    let server = Server {
+        host: "h".to_string(),
+        port: 1,
+        tags: vec!["a".to_string()],
+        connections: 5,
+    };
+    assert_eq!(
+        server.to_json().to_string(),
+        r#"{"host":"h","port":1,"tags":["a"]}"#
+    );
+}
+
+#[derive(ToJson, FromJson, Debug, PartialEq)]
+struct Page {
+    items: Vec<u8>,
+    #[json(flatten)]
+    paging: Paging,
+    #[json(flatten)]
+    extra: BTreeMap<String, Json>,
+}
+
+#[derive(ToJson, FromJson, Debug, PartialEq)]
+struct Paging {
+    offset: u32,
+    limit: u32,
+}
+
+#[test]
+fn flatten_merges_members_into_the_parent() {
+    let page = Page {
+        items: vec![1],
+        paging: Paging {
+            offset: 10,
+            limit: 5,
+        },
+        extra: BTreeMap::from([("next".to_string(), json!("/p/3"))]),
+    };
+    let json = page.to_json();
+    assert_eq!(
+        json.to_string(),
+        r#"{"items":[1],"offset":10,"limit":5,"next":"/p/3"}"#
+    );
+    let back = Page::from_json(&json).unwrap();
+    assert_eq!(back.items, page.items);
+    assert_eq!(back.paging, page.paging);
+    assert_eq!(back.extra.get("next"), Some(&json!("/p/3")));
+}
+
+#[derive(ToJson, FromJson, Debug, PartialEq)]
+enum External {
+    Unit,
+    Newtype(u8),
+    Tuple(u8, bool),
+    Struct { x: i32 },
+}
+
+#[test]
+fn externally_tagged_enums() {
+    round_trip(External::Unit, r#""Unit""#);
+    round_trip(External::Newtype(1), r#"{"Newtype":1}"#);
+    round_trip(External::Tuple(1, true), r#"{"Tuple":[1,true]}"#);
+    round_trip(External::Struct { x: -1 }, r#"{"Struct":{"x":-1}}"#);
+    let err = External::from_json(&json!("Other")).unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::UnknownVariant("Other".to_string()));
+    let err = External::from_json(&json!({"Newtype": "1"})).unwrap_err();
+    assert_eq!(err.to_string(), ".Newtype: expected number, found string");
+}
+
+#[derive(ToJson, FromJson, Debug, PartialEq)]
+#[json(tag = "type", rename_all = "snake_case")]
+enum Internal {
+    Ping,
+    Move {
+        dx: i32,
+        dy: i32,
+    },
+    Wrapped(Paging),
+    #[json(rename_all = "camelCase")]
+    Resize {
+        new_width: u32,
+    },
+}
+
+#[test]
+fn internally_tagged_enums() {
+    round_trip(Internal::Ping, r#"{"type":"ping"}"#);
+    round_trip(
+        Internal::Move { dx: 1, dy: 2 },
+        r#"{"type":"move","dx":1,"dy":2}"#,
+    );
+    round_trip(
+        Internal::Wrapped(Paging {
+            offset: 0,
+            limit: 1,
+        }),
+        r#"{"type":"wrapped","offset":0,"limit":1}"#,
+    );
+    round_trip(
+        Internal::Resize { new_width: 3 },
+        r#"{"type":"resize","newWidth":3}"#,
+    );
+    let err = Internal::from_json(&json!({"dx": 1})).unwrap_err();
+    assert_eq!(err.kind(), &ErrorKind::MissingField("type".to_string()));
+}
+
+#[derive(ToJson, FromJson, Debug, PartialEq)]
+#[json(tag = "t", content = "c")]
+enum Adjacent {
+    Unit,
+    Newtype(String),
+    Tuple(u8, u8),
+    #[json(rename = "S")]
+    Struct {
+        x: bool,
+    },
+}
+
+#[test]
+fn adjacently_tagged_enums() {
+    round_trip(Adjacent::Unit, r#"{"t":"Unit"}"#);
+    round_trip(
+        Adjacent::Newtype("a".to_string()),
+        r#"{"t":"Newtype","c":"a"}"#,
+    );
+    round_trip(Adjacent::Tuple(1, 2), r#"{"t":"Tuple","c":[1,2]}"#);
+    round_trip(Adjacent::Struct { x: true }, r#"{"t":"S","c":{"x":true}}"#);
+}
+
+#[derive(ToJson, FromJson, Debug, PartialEq)]
+#[json(untagged)]
+enum Untagged {
+    Number(f64),
+    Text(String),
+    Point { x: i32, y: i32 },
+    Nothing,
+}
+
+#[test]
+fn untagged_enums_try_each_variant_in_order() {
+    round_trip(Untagged::Number(1.5), "1.5");
+    round_trip(Untagged::Text("a".to_string()), r#""a""#);
+    round_trip(Untagged::Point { x: 1, y: 2 }, r#"{"x":1,"y":2}"#);
+    round_trip(Untagged::Nothing, "null");
+    assert!(Untagged::from_json(&json!([1])).is_err());
+}
+
+#[derive(ToJson, FromJson, Debug, PartialEq)]
+struct Wrapper<T> {
+    inner: T,
+}
+
+#[test]
+fn generic_structs() {
+    round_trip(Wrapper { inner: vec![true] }, r#"{"inner":[true]}"#);
+}
+
+#[test]
+fn derive_errors_are_reported_at_compile_time() {
+    trybuild::TestCases::new().compile_fail("tests/ui/derive/*.rs");
+}
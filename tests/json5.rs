@@ -0,0 +1,213 @@
+use json_parser::Kind;
+use json_parser::json;
+use json_parser::lexen::Lexer;
+use json_parser::parsen::{Json, Parser, ParserOptions};
+
+fn parse(text: &str) -> Json {
+    Parser::from_string_with(text.to_string(), ParserOptions::json5()).parse()
+}
+
+fn errors(text: &str) -> Vec<String> {
+    Parser::from_string_recovering(text.to_string(), ParserOptions::json5())
+        .parse_recovering()
+        .errors
+        .iter()
+        .map(|err| err.to_string())
+        .collect()
+}
+
+fn first_error(text: &str) -> String {
+    Lexer::with_options(text.to_string(), ParserOptions::json5())
+        .recovering()
+        .lex()
+        .into_iter()
+        .find_map(|token| match token.get_kind() {
+            Kind::Error(message) => Some(message),
+            _ => None,
+        })
+        .expect("an error token")
+}
+
+/// The example from the front page of json5.org.
+#[test]
+fn the_json5_org_example() {
+    let text = r#"{
+  // comments
+  unquoted: 'and you can quote me on that',
+  singleQuotes: 'I can use "double quotes" here',
+  lineBreaks: "Look, Mom! \
+No \\n's!",
+  hexadecimal: 0xdecaf,
+  leadingDecimalPoint: .8675309, andTrailing: 8675309.,
+  positiveSign: +1,
+  trailingComma: 'in objects', andIn: ['arrays',],
+  "backwardsCompatible": "with JSON",
+}
+"#;
+    assert_eq!(
+        parse(text),
+        json!({
+            "unquoted": "and you can quote me on that",
+            "singleQuotes": "I can use \"double quotes\" here",
+            "lineBreaks": "Look, Mom! No \\n's!",
+            "hexadecimal": 912559,
+            "leadingDecimalPoint": 0.8675309,
+            "andTrailing": 8675309,
+            "positiveSign": 1,
+            "trailingComma": "in objects",
+            "andIn": ["arrays"],
+            "backwardsCompatible": "with JSON"
+        })
+    );
+}
+
+/// The examples of the spec's section on objects and arrays.
+#[test]
+fn spec_objects_and_arrays() {
+    let text = "// This file is written in JSON5 syntax, naturally, but npm needs a regular
+// JSON file, so compile via `npm run build`. Be sure to keep both in sync!
+
+{
+    name: 'json5',
+    version: '0.5.0',
+    description: 'JSON for the ES5 era.',
+    keywords: ['json', 'es5'],
+    author: 'Aseem Kishore <aseem.kishore@gmail.com>',
+    contributors: [
+        // TODO: Should we remove this section in favor of GitHub's list?
+        // https://github.com/aseemk/json5/contributors
+        'Max Nanasy <max.nanasy@gmail.com>',
+    ],
+    main: 'lib/json5.js',
+    bin: 'lib/cli.js',
+    files: [\"lib/\"],
+    dependencies: {},
+    devDependencies: {
+        gulp: \"^3.9.1\",
+        'gulp-jshint': \"^2.0.0\",
+    },
+}";
+    let json = parse(text);
+    assert_eq!(json["name"], json!("json5"));
+    assert_eq!(json["keywords"], json!(["json", "es5"]));
+    assert_eq!(
+        json["contributors"],
+        json!(["Max Nanasy <max.nanasy@gmail.com>"])
+    );
+    assert_eq!(json["dependencies"], json!({}));
+    assert_eq!(
+        json["devDependencies"],
+        json!({"gulp": "^3.9.1", "gulp-jshint": "^2.0.0"})
+    );
+
+    assert_eq!(
+        parse("{ image: { width: 800, height: 600, 'aspect-ratio': 1.3 } }"),
+        json!({"image": {"width": 800, "height": 600, "aspect-ratio": 1.3}})
+    );
+    assert_eq!(parse("[1, true, 'three',]"), json!([1, true, "three"]));
+}
+
+#[test]
+fn identifier_keys() {
+    assert_eq!(
+        parse("{$_: 1, _a1: 2, camelCase: 3, ūńîčõđë: 4}"),
+        json!({"$_": 1, "_a1": 2, "camelCase": 3, "ūńîčõđë": 4})
+    );
+    // keywords are identifiers too
+    assert_eq!(
+        parse("{null: 1, true: 2, false: 3, Infinity: 4, NaN: 5}"),
+        json!({"null": 1, "true": 2, "false": 3, "Infinity": 4, "NaN": 5})
+    );
+    assert_eq!(
+        errors("{+Infinity: 1}"),
+        ["expected string as key, found number at line 1, column 2"]
+    );
+}
+
+/// The examples of the spec's section on numbers.
+#[test]
+fn spec_numbers() {
+    assert_eq!(
+        parse("[123.456, .456, 123., -.5, +1, -1, 0]"),
+        json!([123.456, 0.456, 123, -0.5, 1, -1, 0])
+    );
+    assert_eq!(
+        parse("[0xFFFF, 0xdecaf, -0xC0FFEE, +0x1]"),
+        json!([65535, 912559, -12648430, 1])
+    );
+    assert_eq!(parse("[1e3, .5e-1, 5.E+2]"), json!([1000, 0.05, 500]));
+
+    let Json::Array(values) = parse("[Infinity, -Infinity, +Infinity, NaN, -NaN]") else {
+        panic!("an array");
+    };
+    let numbers: Vec<f64> = values.iter().map(|json| json.as_f64().unwrap()).collect();
+    assert_eq!(
+        numbers[..3],
+        [f64::INFINITY, f64::NEG_INFINITY, f64::INFINITY]
+    );
+    assert!(numbers[3].is_nan() && numbers[4].is_nan());
+}
+
+/// The examples of the spec's section on strings.
+#[test]
+fn spec_strings() {
+    assert_eq!(parse(r#"'I can\'t wait'"#), json!("I can't wait"));
+    assert_eq!(parse(r#""Say \"hi\"" "#), json!("Say \"hi\""));
+    assert_eq!(parse(r#"'He said "hi"'"#), json!("He said \"hi\""));
+    assert_eq!(
+        parse("'Lorem ipsum dolor sit amet, \\\nconsectetur adipiscing elit.'"),
+        json!("Lorem ipsum dolor sit amet, consectetur adipiscing elit.")
+    );
+    // a continuation after `\r\n`, and after U+2028
+    assert_eq!(parse("'a\\\r\nb\\\u{2028}c'"), json!("abc"));
+}
+
+#[test]
+fn json5_escapes() {
+    assert_eq!(parse(r#"'\x41B\v\0'"#), json!("AB\u{0B}\0"));
+    // any other character stands for itself
+    assert_eq!(parse(r#"'\a\q\''"#), json!("aq'"));
+}
+
+#[test]
+fn octal_escapes_are_rejected() {
+    assert_eq!(
+        first_error(r#"'\01'"#),
+        "invalid escape `\\0` before a digit"
+    );
+    assert_eq!(first_error(r#"'\1'"#), "invalid escape `\\1`");
+    assert_eq!(first_error(r#"'\9'"#), "invalid escape `\\9`");
+}
+
+#[test]
+#[should_panic(expected = "invalid escape `\\0` before a digit at line 1, column 3")]
+fn zero_escape_before_a_digit_panics_when_strict() {
+    parse(r#"'a\07'"#);
+}
+
+#[test]
+fn an_identifier_value_is_a_parse_error() {
+    assert_eq!(
+        errors("{a: b}"),
+        ["expected a value, found identifier at line 1, column 5 (in /a)"]
+    );
+    assert_eq!(
+        errors("[undefined]"),
+        ["expected a value, found identifier at line 1, column 2 (in /0)"]
+    );
+}
+
+#[test]
+#[should_panic(expected = "expected a value, found identifier at line 1, column 5")]
+fn an_identifier_value_panics_when_strict() {
+    parse("{a: b}");
+}
+
+#[test]
+fn strict_json_rejects_json5() {
+    for text in ["{a: 1}", "'a'", "[1,]", "0x1", ".5", "+1", "Infinity"] {
+        let recovered = Parser::from_string_recovering(text.to_string(), ParserOptions::default())
+            .parse_recovering();
+        assert!(!recovered.errors.is_empty(), "{}", text);
+    }
+}
@@ -0,0 +1,149 @@
+use json_parser::Kind;
+use json_parser::json;
+use json_parser::lexen::Lexer;
+use json_parser::parsen::{Json, Parser, ParserOptions};
+
+fn parse(text: &str) -> Json {
+    Parser::from_string_with(text.to_string(), ParserOptions::jsonc()).parse()
+}
+
+fn kinds(text: &str, options: ParserOptions) -> Vec<Kind> {
+    Lexer::with_options(text.to_string(), options)
+        .recovering()
+        .lex()
+        .into_iter()
+        .map(|token| token.get_kind())
+        .collect()
+}
+
+fn first_error(text: &str, options: ParserOptions) -> (String, u32, u32) {
+    Lexer::with_options(text.to_string(), options)
+        .recovering()
+        .lex()
+        .into_iter()
+        .find_map(|token| match token.get_kind() {
+            Kind::Error(message) => Some((message, token.get_line(), token.get_column())),
+            _ => None,
+        })
+        .expect("an error token")
+}
+
+#[test]
+fn line_comments_run_to_the_end_of_the_line() {
+    let text = "// settings\n{\n  \"a\": 1, // the first\n  \"b\": 2 //\n}// done";
+    assert_eq!(parse(text), json!({"a": 1, "b": 2}));
+}
+
+#[test]
+fn block_comments_may_span_lines_and_sit_between_any_tokens() {
+    let text = "/* head\n * more\n */[/**/1/* a */,/*\n*/2]/* tail */";
+    assert_eq!(parse(text), json!([1, 2]));
+    assert_eq!(parse(r#"{"a"/*x*/:/*y*/true}"#), json!({"a": true}));
+    // `*` and `/` on their own inside a block comment don't end it
+    assert_eq!(parse("[/* a * b / c **/ null]"), json!([null]));
+}
+
+#[test]
+fn comment_markers_inside_strings_are_text() {
+    assert_eq!(
+        parse(r#"["// not", "/* nor */"]"#),
+        json!(["// not", "/* nor */"])
+    );
+}
+
+#[test]
+fn comments_are_dropped_unless_kept() {
+    let text = "[1, // one\n/* two */ 2]";
+    let dropped = kinds(text, ParserOptions::jsonc());
+    assert!(!dropped.iter().any(|kind| matches!(kind, Kind::Comment(_))));
+
+    let options = ParserOptions {
+        keep_comments: true,
+        ..ParserOptions::jsonc()
+    };
+    let comments: Vec<Kind> = kinds(text, options)
+        .into_iter()
+        .filter(|kind| matches!(kind, Kind::Comment(_)))
+        .collect();
+    assert_eq!(
+        comments,
+        [
+            Kind::Comment("// one".to_string()),
+            Kind::Comment("/* two */".to_string())
+        ]
+    );
+}
+
+#[test]
+fn kept_comments_have_spans_and_positions() {
+    let text = "[1, // one\n  /* two */ 2]";
+    let options = ParserOptions {
+        keep_comments: true,
+        ..ParserOptions::jsonc()
+    };
+    let tokens = Lexer::with_options(text.to_string(), options).lex();
+    let comments: Vec<_> = tokens
+        .iter()
+        .filter(|token| matches!(token.get_kind(), Kind::Comment(_)))
+        .map(|token| {
+            (
+                &text[token.get_span()],
+                token.get_line(),
+                token.get_column(),
+            )
+        })
+        .collect();
+    assert_eq!(comments, [("// one", 0, 4), ("/* two */", 1, 2)]);
+    // the parser skips kept comments
+    let json = Parser::from_string_with(text.to_string(), options).parse();
+    assert_eq!(json, json!([1, 2]));
+}
+
+#[test]
+fn positions_after_a_multi_line_comment_stay_right() {
+    let text = "/*\n\n*/ [1,\n @]";
+    let (message, line, column) = first_error(text, ParserOptions::jsonc());
+    assert_eq!(message, "unexpected character `@`");
+    assert_eq!((line, column), (3, 1));
+}
+
+#[test]
+fn unterminated_block_comments_are_reported_where_they_start() {
+    let (message, line, column) = first_error("[1,\n  /* open\n 2]", ParserOptions::jsonc());
+    assert_eq!(message, "unterminated block comment");
+    assert_eq!((line, column), (1, 2));
+
+    let recovered = Parser::from_string_recovering("[1 /*".to_string(), ParserOptions::jsonc())
+        .parse_recovering();
+    let errors: Vec<String> = recovered.errors.iter().map(|e| e.to_string()).collect();
+    assert_eq!(errors[0], "unterminated block comment at line 1, column 4");
+}
+
+#[test]
+#[should_panic(expected = "unterminated block comment at line 2, column 3")]
+fn unterminated_block_comment_panics_when_strict() {
+    parse("[1,\n  /* open");
+}
+
+#[test]
+fn a_lone_slash_is_not_a_comment() {
+    let (message, _, column) = first_error("[1 / 2]", ParserOptions::jsonc());
+    assert_eq!((message.as_str(), column), ("unexpected character `/`", 3));
+}
+
+#[test]
+fn block_comments_do_not_nest() {
+    let (message, _, column) = first_error("[/* a /* b */ */ 1]", ParserOptions::jsonc());
+    assert_eq!((message.as_str(), column), ("unexpected character `*`", 14));
+}
+
+#[test]
+fn plain_json_rejects_comments() {
+    let (message, _, column) = first_error("[1 // one\n]", ParserOptions::default());
+    assert_eq!((message.as_str(), column), ("unexpected character `/`", 3));
+    assert!(
+        first_error("/* c */ 1", ParserOptions::default())
+            .0
+            .contains('/')
+    );
+}
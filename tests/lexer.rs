@@ -0,0 +1,186 @@
+use json_parser::Kind;
+use json_parser::lexen::Lexer;
+use json_parser::parsen::{Json, Parser, ParserOptions};
+
+fn parse(text: &str) -> Json {
+    Parser::from_string(text.to_string()).parse()
+}
+
+#[test]
+fn escapes_decode_to_the_characters_they_stand_for() {
+    assert_eq!(
+        parse(r#""a\"b\\c\/d\be\ff\ng\rh\ti""#),
+        Json::Strings("a\"b\\c/d\u{08}e\u{0C}f\ng\rh\ti".to_string())
+    );
+    assert_eq!(parse(r#""\u00e9\u4E2D""#), Json::Strings("é中".to_string()));
+}
+
+#[test]
+fn surrogate_pairs_decode_to_one_code_point() {
+    assert_eq!(
+        parse(r#""\ud83d\ude00""#),
+        Json::Strings("\u{1F600}".to_string())
+    );
+}
+
+#[test]
+fn multi_byte_characters_are_stepped_over() {
+    assert_eq!(
+        parse(r#"["héllo", "日本", "😀"]"#),
+        Json::Array(vec![
+            Json::Strings("héllo".to_string()),
+            Json::Strings("日本".to_string()),
+            Json::Strings("😀".to_string()),
+        ])
+    );
+}
+
+#[test]
+fn numbers_keep_their_fraction_and_exponent() {
+    assert_eq!(parse("1.5"), Json::Number(1.5));
+    assert_eq!(parse("-0.25"), Json::Number(-0.25));
+    assert_eq!(parse("1e3"), Json::Number(1000.0));
+    assert_eq!(parse("2.5E-2"), Json::Number(0.025));
+    assert_eq!(parse("1e+2"), Json::Number(100.0));
+}
+
+#[test]
+fn zero_may_start_a_fraction_or_exponent() {
+    assert_eq!(parse("0"), Json::Number(0.0));
+    assert_eq!(parse("-0.5"), Json::Number(-0.5));
+    assert_eq!(parse("0e2"), Json::Number(0.0));
+    assert_eq!(
+        parse("[10, 100]"),
+        Json::Array(vec![Json::Number(10.0), Json::Number(100.0)])
+    );
+}
+
+#[test]
+#[should_panic(expected = "leading zero in number `01` at line 1, column 2")]
+fn leading_zero_is_rejected() {
+    parse("[01]");
+}
+
+#[test]
+#[should_panic(expected = "leading zero in number `-007`")]
+fn negative_leading_zero_is_rejected() {
+    parse("-007");
+}
+
+#[test]
+#[should_panic]
+fn number_without_fraction_digits_is_rejected() {
+    parse("1.");
+}
+
+#[test]
+#[should_panic]
+fn number_without_exponent_digits_is_rejected() {
+    parse("1e+");
+}
+
+fn first_error(text: &str) -> (String, u32, u32) {
+    Lexer::new(text.to_string())
+        .recovering()
+        .lex()
+        .into_iter()
+        .find_map(|token| match token.get_kind() {
+            Kind::Error(message) => Some((message, token.get_line(), token.get_column())),
+            _ => None,
+        })
+        .expect("an error token")
+}
+
+#[test]
+fn numbers_need_a_digit_on_both_sides_of_the_point() {
+    for (text, number) in [
+        ("-.5", "-.5"),
+        ("[1.e5]", "1.e5"),
+        ("0.e1", "0.e1"),
+        ("-.e1", "-.e1"),
+        ("[1.]", "1."),
+    ] {
+        let (message, line, column) = first_error(text);
+        assert_eq!(
+            message,
+            format!("malformed number `{}`", number),
+            "{}",
+            text
+        );
+        assert_eq!(line, 0, "{}", text);
+        assert_eq!(
+            column,
+            text.find(['-', '0', '1']).unwrap() as u32,
+            "{}",
+            text
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "malformed number `-.5` at line 1, column 2")]
+fn number_without_integer_digits_is_rejected() {
+    parse("[-.5]");
+}
+
+#[test]
+fn numbers_out_of_range_are_rejected_unless_non_finite_is_allowed() {
+    for text in ["1e999", "[-1e999]", "1.5e400"] {
+        let number = text.trim_matches(['[', ']']);
+        let (message, ..) = first_error(text);
+        assert_eq!(message, format!("number `{}` is out of range", number));
+    }
+    // underflow is not an error
+    assert_eq!(parse("1e-999"), Json::Number(0.0));
+
+    let options = ParserOptions {
+        allow_non_finite: true,
+        ..ParserOptions::default()
+    };
+    let parsed = Parser::from_string_with("[1e999, -1e999]".to_string(), options).parse();
+    assert_eq!(
+        parsed,
+        Json::Array(vec![
+            Json::Number(f64::INFINITY),
+            Json::Number(f64::NEG_INFINITY)
+        ])
+    );
+}
+
+#[test]
+#[should_panic(expected = "number `1e999` is out of range at line 1, column 1")]
+fn number_out_of_range_is_rejected() {
+    parse("1e999");
+}
+
+#[test]
+fn unpaired_surrogates_are_reported_at_the_escape() {
+    // a lone high surrogate, one followed by an escape that is not a low
+    // surrogate, and a lone low surrogate
+    for text in [r#"["\ud83d"]"#, r#"["\ud83d\u0041"]"#, r#"["\ude00x"]"#] {
+        let (message, line, column) = first_error(text);
+        assert_eq!(message, "unpaired surrogate", "{}", text);
+        assert_eq!((line, column), (0, 2), "{}", text);
+    }
+}
+
+#[test]
+#[should_panic(expected = "unpaired surrogate at line 1, column 3")]
+fn unpaired_surrogate_is_rejected() {
+    parse(r#"["\ud83d"]"#);
+}
+
+#[test]
+#[should_panic(expected = "invalid escape `\\q` at line 1, column 3")]
+fn unknown_escape_is_rejected() {
+    parse(r#""a\qb""#);
+}
+
+#[test]
+fn unknown_escapes_are_reported_at_the_backslash() {
+    for (text, column) in [(r#"["\q"]"#, 2), (r#"["ok\'"]"#, 4), (r#"["\v"]"#, 2)] {
+        let (message, line, at) = first_error(text);
+        assert!(message.starts_with("invalid escape"), "{}", text);
+        assert_eq!((line, at), (0, column), "{}", text);
+    }
+}
@@ -0,0 +1,130 @@
+//! Each lenient toggle of `ParserOptions` on its own: it lets its input
+//! through, strict JSON does not, and no other toggle does either.
+
+use json_parser::json;
+use json_parser::parsen::{Json, Parser, ParserOptions};
+
+type Toggle = fn(&mut ParserOptions);
+
+const TOGGLES: [(&str, Toggle); 6] = [
+    ("trailing commas", |o| o.allow_trailing_commas = true),
+    ("non-finite numbers", |o| o.allow_non_finite = true),
+    ("leading BOM", |o| o.allow_leading_bom = true),
+    ("single quotes", |o| o.allow_single_quotes = true),
+    ("control characters", |o| o.allow_control_characters = true),
+    ("unquoted keys", |o| o.allow_unquoted_keys = true),
+];
+
+fn only(toggle: Toggle) -> ParserOptions {
+    let mut options = ParserOptions::default();
+    toggle(&mut options);
+    options
+}
+
+/// Strict options with just the toggle named `name` on.
+fn with(name: &str) -> ParserOptions {
+    let (_, toggle) = TOGGLES.iter().find(|(other, _)| *other == name).unwrap();
+    only(*toggle)
+}
+
+/// The tree, or the first error message.
+fn parse(text: &str, options: ParserOptions) -> Result<Json, String> {
+    let recovered = Parser::from_string_recovering(text.to_string(), options).parse_recovering();
+    match recovered.errors.first() {
+        Some(err) => Err(err.to_string()),
+        None => Ok(recovered.json),
+    }
+}
+
+/// Checks `text` parses to `expected` with the toggle named `name` on, and
+/// fails with strict options and with every other toggle on its own.
+fn accepted_only_by(name: &str, text: &str, expected: Json) {
+    for (other, toggle) in TOGGLES {
+        let result = parse(text, only(toggle));
+        if other == name {
+            assert_eq!(result, Ok(expected.clone()), "{:?} with {}", text, name);
+        } else {
+            assert!(result.is_err(), "{:?} accepted with {}", text, other);
+        }
+    }
+    assert!(parse(text, ParserOptions::default()).is_err(), "{:?}", text);
+}
+
+#[test]
+fn trailing_commas() {
+    accepted_only_by("trailing commas", "[1, 2,]", json!([1, 2]));
+    accepted_only_by("trailing commas", r#"{"a": [1,],}"#, json!({"a": [1]}));
+    let err = parse("[1,]", ParserOptions::default()).unwrap_err();
+    assert_eq!(err, "trailing comma before `]` at line 1, column 4");
+    // one comma, not several
+    let options = with("trailing commas");
+    assert!(parse("[1,,]", options).is_err());
+    assert!(parse("[,]", options).is_err());
+}
+
+#[test]
+fn non_finite_numbers() {
+    let text = "[NaN, Infinity, -Infinity]";
+    for (name, toggle) in TOGGLES {
+        let result = parse(text, only(toggle));
+        assert_eq!(result.is_ok(), name == "non-finite numbers", "{}", name);
+    }
+    let json = parse(text, with("non-finite numbers")).unwrap();
+    let numbers: Vec<f64> = json
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|json| json.as_f64().unwrap())
+        .collect();
+    assert!(numbers[0].is_nan());
+    assert_eq!(numbers[1..], [f64::INFINITY, f64::NEG_INFINITY]);
+    assert!(parse(text, ParserOptions::default()).is_err());
+    // the words are case-sensitive
+    assert!(parse("nan", with("non-finite numbers")).is_err());
+}
+
+#[test]
+fn leading_bom() {
+    accepted_only_by("leading BOM", "\u{FEFF}[1]", json!([1]));
+    // only before the document
+    assert!(parse("[\u{FEFF}1]", with("leading BOM")).is_err());
+}
+
+#[test]
+fn single_quotes() {
+    accepted_only_by("single quotes", "'a\"b'", json!("a\"b"));
+    accepted_only_by("single quotes", r#"{'k': 'it\'s'}"#, json!({"k": "it's"}));
+}
+
+#[test]
+fn control_characters() {
+    accepted_only_by("control characters", "\"a\tb\"", json!("a\tb"));
+    accepted_only_by("control characters", "[\"\u{1}\"]", json!(["\u{1}"]));
+    let err = parse("\"a\tb\"", ParserOptions::default()).unwrap_err();
+    assert!(
+        err.starts_with("unescaped control character U+0009"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn unquoted_keys() {
+    accepted_only_by(
+        "unquoted keys",
+        "{a: 1, $b_2: 2}",
+        json!({"a": 1, "$b_2": 2}),
+    );
+    // still only for keys
+    assert!(parse("[a]", with("unquoted keys")).is_err());
+    assert!(parse("{1a: 1}", with("unquoted keys")).is_err());
+}
+
+#[test]
+fn strict_options_accept_plain_json() {
+    let text = r#"{"a": [1, -2.5e3, "x\ty", true, null]}"#;
+    for (name, toggle) in TOGGLES {
+        assert!(parse(text, only(toggle)).is_ok(), "{}", name);
+    }
+    assert!(parse(text, ParserOptions::default()).is_ok());
+}
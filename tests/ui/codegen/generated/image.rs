@@ -0,0 +1,27 @@
+use json_parser::convert::{FromJson, ToJson};
+
+#[derive(Debug, Clone, PartialEq, ToJson, FromJson)]
+#[json(rename_all = "PascalCase")]
+pub struct Root {
+    pub image: Image,
+}
+
+#[derive(Debug, Clone, PartialEq, ToJson, FromJson)]
+#[json(rename_all = "PascalCase")]
+pub struct Image {
+    pub width: i64,
+    pub height: i64,
+    pub title: String,
+    pub thumbnail: Thumbnail,
+    pub animated: bool,
+    #[json(rename = "IDs")]
+    pub ids: Vec<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, ToJson, FromJson)]
+#[json(rename_all = "PascalCase")]
+pub struct Thumbnail {
+    pub url: String,
+    pub height: i64,
+    pub width: i64,
+}
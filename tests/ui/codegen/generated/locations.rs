@@ -0,0 +1,15 @@
+use json_parser::convert::{FromJson, ToJson};
+
+#[derive(Debug, Clone, PartialEq, ToJson, FromJson)]
+#[json(rename_all = "PascalCase")]
+pub struct Location {
+    #[json(rename = "precision")]
+    pub precision: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub address: String,
+    pub city: String,
+    pub state: String,
+    pub zip: String,
+    pub country: String,
+}
@@ -0,0 +1,12 @@
+include!("generated/image.rs");
+
+use json_parser::parsen::Parser;
+
+fn main() {
+    let text = include_str!("../../../json_obj.txt");
+    let json = Parser::from_string(text.to_string()).parse();
+    let root = Root::from_json(&json).unwrap();
+    assert_eq!(root.image.ids, [116, 943, 234, 38793]);
+    assert_eq!(root.image.thumbnail.width, 100);
+    assert_eq!(root.to_json(), json);
+}
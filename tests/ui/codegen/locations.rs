@@ -0,0 +1,12 @@
+include!("generated/locations.rs");
+
+use json_parser::parsen::Parser;
+
+fn main() {
+    let text = include_str!("../../../array.txt");
+    let json = Parser::from_string(text.to_string()).parse();
+    let locations = Vec::<Location>::from_json(&json).unwrap();
+    assert_eq!(locations[1].city, "SUNNYVALE");
+    assert_eq!(locations[0].precision, "zip\u{3bb}");
+    assert_eq!(locations.to_json(), json);
+}
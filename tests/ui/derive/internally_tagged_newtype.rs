@@ -0,0 +1,9 @@
+use json_parser::convert::ToJson;
+
+#[derive(ToJson)]
+#[json(tag = "type")]
+enum Event {
+    Count(u32),
+}
+
+fn main() {}
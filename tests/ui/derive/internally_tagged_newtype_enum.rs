@@ -0,0 +1,14 @@
+use json_parser::convert::ToJson;
+
+#[derive(ToJson)]
+enum Shape {
+    Circle,
+}
+
+#[derive(ToJson)]
+#[json(tag = "type")]
+enum Event {
+    Draw(Shape),
+}
+
+fn main() {}